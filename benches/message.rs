@@ -0,0 +1,25 @@
+//! Benchmark the cost of cloning messages, e.g. for fan-out to several receivers.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ruleco::{control_protocol::Message, core::ContentTypes};
+
+fn build_message() -> Message {
+    Message::build(
+        b"N1.receiver".to_vec(),
+        b"N1.sender".to_vec(),
+        None,
+        None,
+        1,
+        ContentTypes::Frames(vec![vec![7u8; 1_000_000], vec![8u8; 1_000]]),
+    )
+}
+
+fn clone_benchmark(c: &mut Criterion) {
+    let message = build_message();
+    // Cloning the frames corresponds to cloning a message owning its frames directly.
+    c.bench_function("clone frames", |b| b.iter(|| message.to_frames().clone()));
+    c.bench_function("clone message", |b| b.iter(|| message.clone()));
+}
+
+criterion_group!(benches, clone_benchmark);
+criterion_main!(benches);
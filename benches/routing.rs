@@ -0,0 +1,79 @@
+//! Benchmark routing a message between Components of a well filled Coordinator.
+//!
+//! "route message" measures the routing decision alone, "forward message" includes reading the
+//! message from and sending it through the sockets.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use ruleco::{control_protocol::Message, coordinator::Coordinator, core::ContentTypes};
+
+const COMPONENTS: usize = 1000;
+
+fn identity(index: usize) -> Vec<u8> {
+    format!("id_{index}").into_bytes()
+}
+
+fn name(index: usize) -> Vec<u8> {
+    format!("component_{index}").into_bytes()
+}
+
+/// Sign in `COMPONENTS` Components to `coordinator`.
+fn fill(coordinator: &mut Coordinator) {
+    for index in 0..COMPONENTS {
+        let (sign_in, _cid) =
+            Message::build_request(b"COORDINATOR".to_vec(), name(index), 1, "sign_in", None);
+        coordinator.route(identity(index), sign_in);
+    }
+}
+
+fn build_message(payload_size: usize) -> Message {
+    Message::build(
+        b"N1.component_500".to_vec(),
+        b"N1.component_7".to_vec(),
+        None,
+        None,
+        1,
+        ContentTypes::Frame(vec![7u8; payload_size]),
+    )
+}
+
+fn route_benchmark(c: &mut Criterion) {
+    let mut coordinator = Coordinator::new("N1".to_string(), Some(0), None);
+    fill(&mut coordinator);
+    c.bench_function("route message", |b| {
+        b.iter_batched(
+            || (identity(7), build_message(100)),
+            |(identity, message)| coordinator.route(identity, message),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn forward_benchmark(c: &mut Criterion) {
+    let context = zmq::Context::new();
+    let address = "inproc://routing_benchmark";
+    let mut coordinator = Coordinator::with_endpoint(
+        "N1".to_string(),
+        Some(&address.parse().unwrap()),
+        Some(context.clone()),
+        None,
+    );
+    fill(&mut coordinator);
+    let connect = |index| {
+        let socket = context.socket(zmq::DEALER).unwrap();
+        socket.set_identity(&identity(index)).unwrap();
+        socket.connect(address).unwrap();
+        socket
+    };
+    let (sender, receiver) = (connect(7), connect(500));
+    let message = build_message(1_000_000);
+    c.bench_function("forward message", |b| {
+        b.iter(|| {
+            sender.send_multipart(message.to_frames(), 0).unwrap();
+            coordinator.loop_element();
+            receiver.recv_multipart(0).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, route_benchmark, forward_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! # Oscillator
+//!
+//! A dummy oscillator exposed as the LECO Component `oscillator` via the local Coordinator.
+//!
+//! Read its current value with `get_parameters` (`{"parameters": ["value"]}`), change its
+//! `frequency` or `amplitude` with `set_parameters`, and restart it with the `reset` action.
+
+use std::{f64::consts::TAU, time::Duration};
+
+use ruleco::control_protocol::{actor::Actor, communicator::Communicator, Error};
+use serde_json::Value;
+
+/// A sine generator advancing its phase in the housekeeping task
+struct Oscillator {
+    frequency: f64,
+    amplitude: f64,
+    phase: f64,
+}
+
+impl Oscillator {
+    fn value(&self) -> f64 {
+        self.amplitude * self.phase.sin()
+    }
+
+    fn advance(&mut self, duration: Duration) {
+        self.phase = (self.phase + TAU * self.frequency * duration.as_secs_f64()) % TAU;
+    }
+}
+
+fn as_f64(value: Value) -> Result<f64, Error> {
+    value.as_f64().ok_or(Error::InvalidParams)
+}
+
+fn main() {
+    let step = Duration::from_millis(100);
+    let communicator = Communicator::build("oscillator", None, None, None).unwrap();
+    let oscillator = Oscillator {
+        frequency: 1.0,
+        amplitude: 1.0,
+        phase: 0.0,
+    };
+    let mut actor = Actor::new(communicator, oscillator);
+    actor.add_getter("value", |osc| Value::from(osc.value()));
+    actor.add_getter("frequency", |osc| Value::from(osc.frequency));
+    actor.add_getter("amplitude", |osc| Value::from(osc.amplitude));
+    actor.add_setter("frequency", |osc, value| {
+        osc.frequency = as_f64(value)?;
+        Ok(())
+    });
+    actor.add_setter("amplitude", |osc, value| {
+        osc.amplitude = as_f64(value)?;
+        Ok(())
+    });
+    actor.add_action("reset", |osc, _args, _kwargs| {
+        osc.phase = 0.0;
+        Ok(Value::Null)
+    });
+    actor.set_housekeeping(step, move |osc| osc.advance(step));
+    if let Err(err) = actor.run() {
+        eprintln!("Oscillator stopped: {err}");
+    }
+}
@@ -1,7 +1,17 @@
-use std::io;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use serde::Serialize;
+use serde_json::Value;
 
 use crate::{
     core::{create_conversation_id, ContentTypes, FullName},
+    json::{to_vec, ErrorResponse, Request, Response},
     VERSION,
 };
 
@@ -11,30 +21,179 @@ pub struct Header<'b> {
     pub message_type: &'b u8,
 }
 impl<'b> Header<'b> {
-    fn from_frame(frame: &'b Vec<u8>) -> Self {
+    fn from_frame(frame: &'b [u8]) -> Self {
         Self {
             conversation_id: &frame[..16],
             message_id: &frame[16..16 + 3],
             message_type: &frame[19],
         }
     }
+
+    /// The message id as a number (big-endian interpretation of the three bytes).
+    pub fn message_id(&self) -> u32 {
+        u32::from_be_bytes([
+            0,
+            self.message_id[0],
+            self.message_id[1],
+            self.message_id[2],
+        ])
+    }
+
+    /// Copy the header into a [`HeaderOwned`], which does not borrow from the message.
+    pub fn to_owned(&self) -> HeaderOwned {
+        let mut conversation_id = [0u8; 16];
+        conversation_id.copy_from_slice(self.conversation_id);
+        let mut message_id = [0u8; 3];
+        message_id.copy_from_slice(self.message_id);
+        HeaderOwned {
+            conversation_id,
+            message_id,
+            message_type: *self.message_type,
+        }
+    }
+}
+
+/// A header owning its data, e.g. to store metadata of sent messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HeaderOwned {
+    pub conversation_id: [u8; 16],
+    pub message_id: [u8; 3],
+    pub message_type: u8,
+}
+
+impl From<HeaderOwned> for Vec<u8> {
+    /// Reconstruct the header frame.
+    fn from(header: HeaderOwned) -> Self {
+        let mut frame = header.conversation_id.to_vec();
+        frame.extend_from_slice(&header.message_id);
+        frame.push(header.message_type);
+        frame
+    }
+}
+
+/// Length of the header frame: conversation id, message id, and message type.
+pub const HEADER_LENGTH: usize = 16 + 3 + 1;
+
+/// Largest message id which fits into the three bytes of the header.
+pub const MAX_MESSAGE_ID: u32 = 0xFF_FF_FF;
+
+/// Generate consecutive message ids, wrapping around after [`MAX_MESSAGE_ID`].
+pub struct MessageIdGenerator {
+    counter: AtomicU32,
 }
 
-#[derive(Clone)]
+impl MessageIdGenerator {
+    pub const fn new() -> Self {
+        Self {
+            counter: AtomicU32::new(1),
+        }
+    }
+
+    /// Get the next message id as header bytes.
+    pub fn next_id(&self) -> [u8; 3] {
+        let id = self.counter.fetch_add(1, Ordering::Relaxed) & MAX_MESSAGE_ID;
+        let bytes = id.to_be_bytes();
+        [bytes[1], bytes[2], bytes[3]]
+    }
+}
+
+impl Default for MessageIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Process-wide generator for messages built without explicit message id.
+static MESSAGE_ID_GENERATOR: MessageIdGenerator = MessageIdGenerator::new();
+
+/// Limits for incoming messages, to protect against excessive memory usage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of frames of a message
+    pub max_frames: usize,
+    /// Maximum number of bytes of all frames combined
+    pub max_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_frames: 64,
+            max_bytes: 100_000_000,
+        }
+    }
+}
+
+impl Limits {
+    /// Check whether the `frames` are within the limits.
+    pub fn check(&self, frames: &[Vec<u8>]) -> Result<(), io::Error> {
+        if frames.len() > self.max_frames {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Too many frames: {} > {}.", frames.len(), self.max_frames),
+            ));
+        }
+        let bytes: usize = frames.iter().map(|frame| frame.len()).sum();
+        if bytes > self.max_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Message too large: {bytes} > {} bytes.", self.max_bytes),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A message of the control protocol
+///
+/// The canonical frame layout is: version, receiver, sender, header, and payload frames.
+/// A message without content (`ContentTypes::Null`) has no payload frame at all,
+/// whereas an empty payload is a single empty frame.
+///
+/// The frames are shared between clones, such that cloning a message is cheap.
+/// Modifying a clone copies the frames (copy-on-write).
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Message {
-    frames: Vec<Vec<u8>>,
+    frames: Arc<Vec<Vec<u8>>>,
 }
 
 impl Message {
+    /// Create a message from received frames, applying the default [`Limits`].
     pub fn new(frames: Vec<Vec<u8>>) -> Result<Self, io::Error> {
+        Self::new_limited(frames, &Limits::default())
+    }
+    /// Create a message from received frames, which have to be within `limits`.
+    pub fn new_limited(frames: Vec<Vec<u8>>, limits: &Limits) -> Result<Self, io::Error> {
+        Self::check_layout(&frames)?;
+        limits.check(&frames)?;
+        Ok(Self {
+            frames: Arc::new(frames),
+        })
+    }
+    /// Check whether the `frames` contain at least the address and header frames, such that
+    /// reading them as a message cannot fail.
+    pub fn check_layout(frames: &[Vec<u8>]) -> Result<(), io::Error> {
         if frames.len() < 4 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Not enough frames.",
             ));
         }
-        Ok(Self { frames })
+        if frames[3].len() < HEADER_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Header too short: {} < {HEADER_LENGTH} bytes.",
+                    frames[3].len()
+                ),
+            ));
+        }
+        Ok(())
     }
+    /// Build a message from its parts.
+    ///
+    /// Without `conversation_id`, a new one is created.
+    /// Without `message_id`, the next id of a process-wide counter is used.
     pub fn build(
         receiver: Vec<u8>,
         sender: Vec<u8>,
@@ -46,7 +205,7 @@ impl Message {
         let mut header = conversation_id
             .unwrap_or(&create_conversation_id())
             .to_vec();
-        header.extend_from_slice(message_id.unwrap_or(&[0, 0, 0]));
+        header.extend_from_slice(message_id.unwrap_or(&MESSAGE_ID_GENERATOR.next_id()));
         header.push(message_type);
         let mut vec: Vec<Vec<u8>> = vec![vec![VERSION], receiver, sender, header];
         match content {
@@ -58,38 +217,221 @@ impl Message {
             }
             ContentTypes::Null => (),
         };
-        Self { frames: vec }
+        Self {
+            frames: Arc::new(vec),
+        }
+    }
+    /// Build a message with a JSON content frame followed by additional binary frames.
+    ///
+    /// Without any `blobs`, the layout is identical to a message with a single JSON frame.
+    pub fn build_with_binary(
+        receiver: Vec<u8>,
+        sender: Vec<u8>,
+        json_content: &impl Serialize,
+        blobs: Vec<Vec<u8>>,
+    ) -> Self {
+        let mut frames = vec![to_vec(json_content)];
+        frames.extend(blobs);
+        Self::build(
+            receiver,
+            sender,
+            None,
+            None,
+            message_types::JSON,
+            ContentTypes::Frames(frames),
+        )
+    }
+    /// Build a message containing a JSON-RPC request.
+    ///
+    /// Returns the message together with its new conversation id.
+    pub fn build_request(
+        receiver: Vec<u8>,
+        sender: Vec<u8>,
+        id: u16,
+        method: impl ToString,
+        params: Option<Value>,
+    ) -> (Self, [u8; 16]) {
+        let conversation_id = create_conversation_id();
+        let request = Request::build(id, method).with_params(params);
+        let message = Self::build(
+            receiver,
+            sender,
+            Some(&conversation_id),
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&request)),
+        );
+        (message, conversation_id)
+    }
+    /// Build a message containing a JSON-RPC response with `result`.
+    pub fn build_response(
+        receiver: Vec<u8>,
+        sender: Vec<u8>,
+        conversation_id: Option<&[u8]>,
+        id: u16,
+        result: impl Serialize,
+    ) -> Self {
+        Self::build(
+            receiver,
+            sender,
+            conversation_id,
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&Response::build(id, result))),
+        )
+    }
+    /// Build a message containing a JSON-RPC error response.
+    pub fn build_error_response(
+        receiver: Vec<u8>,
+        sender: Vec<u8>,
+        conversation_id: Option<&[u8]>,
+        id: impl Into<Option<u16>>,
+        error: &Error,
+    ) -> Self {
+        let response = ErrorResponse::from_error(id, error);
+        Self::build(
+            receiver,
+            sender,
+            conversation_id,
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&response)),
+        )
     }
     pub fn version(&self) -> Option<&u8> {
-        self.frames[0].get(0)
+        self.frames[0].first()
     }
     pub fn receiver_frame(&self) -> &Vec<u8> {
         &self.frames[1]
     }
-    pub fn receiver(&self) -> FullName {
-        FullName::from_vec(&self.frames[1]).unwrap()
+    /// The parsed receiver name, or a `ParseError` for an invalid name.
+    pub fn receiver(&self) -> Result<FullName<'_>, Error> {
+        FullName::from_vec(&self.frames[1]).map_err(|_| Error::ParseError)
     }
     pub fn sender_frame(&self) -> &Vec<u8> {
         &self.frames[2]
     }
-    pub fn sender(&self) -> FullName {
-        FullName::from_vec(&self.frames[2]).unwrap()
+    /// The parsed sender name, or a `ParseError` for an invalid name.
+    pub fn sender(&self) -> Result<FullName<'_>, Error> {
+        FullName::from_vec(&self.frames[2]).map_err(|_| Error::ParseError)
+    }
+    /// Whether the message is addressed to the Component `full_name`.
+    ///
+    /// A receiver without namespace matches any namespace.
+    pub fn is_for(&self, full_name: &FullName) -> bool {
+        match self.receiver() {
+            Ok(receiver) => {
+                receiver.name == full_name.name
+                    && (receiver.namespace.is_empty() || receiver.namespace == full_name.namespace)
+            }
+            Err(_) => false,
+        }
+    }
+    /// Whether the message is addressed to the Coordinator of `namespace`.
+    pub fn is_for_coordinator(&self, namespace: &[u8]) -> bool {
+        self.is_for(&FullName {
+            namespace,
+            name: b"COORDINATOR",
+        })
+    }
+    /// Replace the receiver frame.
+    pub fn set_receiver(&mut self, receiver: Vec<u8>) {
+        Arc::make_mut(&mut self.frames)[1] = receiver
+    }
+    /// Replace the sender frame, e.g. to complete the sender's full name.
+    pub fn set_sender(&mut self, sender: Vec<u8>) {
+        Arc::make_mut(&mut self.frames)[2] = sender
     }
-    pub fn header(&self) -> Header {
+    pub fn header(&self) -> Header<'_> {
         Header::from_frame(&self.frames[3])
     }
+    /// The message id as a number, see [`Header::message_id`].
+    pub fn message_id(&self) -> u32 {
+        self.header().message_id()
+    }
+    /// Set the message id.
+    ///
+    /// The message id has three bytes, such that only the lower 24 bits of `message_id` are used,
+    /// i.e. values above [`MAX_MESSAGE_ID`] are truncated.
+    pub fn with_message_id(mut self, message_id: u32) -> Self {
+        let bytes = message_id.to_be_bytes();
+        Arc::make_mut(&mut self.frames)[3][16..19].copy_from_slice(&bytes[1..]);
+        self
+    }
+    /// The first payload frame, or `None` if the message has no payload.
     pub fn content_frame(&self) -> Option<&Vec<u8>> {
         self.frames.get(4)
     }
     pub fn payload(&self) -> &[Vec<u8>] {
         &self.frames[4..]
     }
+    /// The JSON frame, i.e. the first payload frame.
+    pub fn json_frame(&self) -> Option<&Vec<u8>> {
+        self.content_frame()
+    }
+    /// Additional binary frames following the JSON frame.
+    pub fn binary_frames(&self) -> &[Vec<u8>] {
+        self.frames.get(5..).unwrap_or(&[])
+    }
     pub fn to_frames(&self) -> &Vec<Vec<u8>> {
         &self.frames
     }
+    /// Take the frames, e.g. for sending. Copies them only if they are shared with a clone.
+    pub fn into_frames(self) -> Vec<Vec<u8>> {
+        Arc::try_unwrap(self.frames).unwrap_or_else(|frames| (*frames).clone())
+    }
+    /// Number of bytes the message occupies on the wire, i.e. the sum of all frame lengths.
+    pub fn len_bytes(&self) -> usize {
+        self.frames.iter().map(|frame| frame.len()).sum()
+    }
+    /// Number of frames of the message.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    /// Describe the message on a few lines for debugging: the addresses, the header and every
+    /// payload frame with its length, a hex preview and, for a JSON frame, its text.
+    pub fn pretty(&self) -> String {
+        let header = self.header();
+        let mut text = format!(
+            "{} -> {} (conversation {}, message {}, type {})",
+            String::from_utf8_lossy(self.sender_frame()),
+            String::from_utf8_lossy(self.receiver_frame()),
+            uuid::Uuid::from_slice(header.conversation_id).unwrap_or_default(),
+            header.message_id(),
+            header.message_type,
+        );
+        for (index, frame) in self.payload().iter().enumerate() {
+            text.push_str(&format!(
+                "\n  [{index}] {} bytes: {}",
+                frame.len(),
+                hex_preview(frame)
+            ));
+            if index == 0 && message_types::is_json(self) {
+                text.push_str(&format!("\n      {}", String::from_utf8_lossy(frame)));
+            }
+        }
+        text
+    }
+}
+
+/// Number of bytes shown by [`hex_preview`].
+const PREVIEW_BYTES: usize = 16;
+
+/// The first bytes of `frame` in hex, followed by an ellipsis, if there are more.
+fn hex_preview(frame: &[u8]) -> String {
+    let mut preview = frame
+        .iter()
+        .take(PREVIEW_BYTES)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if frame.len() > PREVIEW_BYTES {
+        preview.push_str(" ...");
+    }
+    preview
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     // JSONRPC 2.0 defined errors
     InvalidRequest,
@@ -103,41 +445,111 @@ pub enum Error {
     DuplicateName,
     NodeUnknown,
     ReceiverUnknown,
+    // Application specific errors
+    /// The sender is not allowed to call the method, e.g. `shut_down` of the Coordinator.
+    Unauthorized,
+    /// A server error with a custom message and optional data.
+    ///
+    /// Create it with [`Error::custom`] to ensure the code is in the server error range.
+    Custom {
+        code: i16,
+        message: String,
+        data: Option<Value>,
+    },
 }
 
+/// Variant, code, and message of all errors with a fixed code
+const ERROR_TABLE: [(Error, i16, &str); 11] = [
+    // JSON-RPC 2.0
+    (Error::InvalidRequest, -32600, "Invalid Request"),
+    (Error::MethodNotFound, -32601, "Method not found"),
+    (Error::InvalidParams, -32602, "Invalid params"),
+    (Error::InternalError, -32603, "Internal error"),
+    (Error::ParseError, -32700, "Parse error"),
+    (Error::ServerError, -32000, "Server error."),
+    // LECO
+    (Error::NotSignedIn, -32090, "Component not signed in yet!"),
+    (Error::DuplicateName, -32091, "The name is already taken."),
+    (Error::NodeUnknown, -32092, "Node is unknown."),
+    (
+        Error::ReceiverUnknown,
+        -32093,
+        "Receiver is not in addresses list.",
+    ),
+    // ruleco
+    (Error::Unauthorized, -32094, "Sender is not authorized."),
+];
+
 impl Error {
+    /// All errors with a fixed code, i.e. all except [`Error::Custom`].
+    pub fn all() -> impl Iterator<Item = &'static Error> {
+        ERROR_TABLE.iter().map(|entry| &entry.0)
+    }
+
+    /// Find the table entry of an error with a fixed code.
+    fn table_entry(&self) -> &'static (Error, i16, &'static str) {
+        ERROR_TABLE
+            .iter()
+            .find(|entry| entry.0 == *self)
+            .expect("Every error except Custom is in the error table.")
+    }
+
     pub fn code(&self) -> i16 {
         match &self {
-            Self::InvalidRequest => -32600,
-            Self::MethodNotFound => -32601,
-            Self::InvalidParams => -32602,
-            Self::InternalError => -32603,
-            Self::ParseError => -32700,
-            Self::ServerError => -32000,
-            Self::NotSignedIn => -32090,
-            Self::DuplicateName => -32091,
-            Self::NodeUnknown => -32092,
-            Self::ReceiverUnknown => -32093,
-            //_ => -32000,
+            Self::Custom { code, .. } => *code,
+            _ => self.table_entry().1,
+        }
+    }
+
+    /// Create a custom error, if the `code` is in the server error range -32099..=-32000 and not
+    /// the code of another error, as [`Error::from_code`] has to return the error sent.
+    pub fn custom(code: i16, message: impl ToString, data: Option<Value>) -> Option<Self> {
+        if (-32099..=-32000).contains(&code) && Self::from_code(code).is_none() {
+            Some(Self::Custom {
+                code,
+                message: message.to_string(),
+                data,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Additional data of the error.
+    pub fn data(&self) -> Option<&Value> {
+        match &self {
+            Self::Custom { data, .. } => data.as_ref(),
+            _ => None,
         }
     }
 
+    /// Get the error corresponding to a JSON-RPC error code, if it is known.
+    pub fn from_code(code: i16) -> Option<Self> {
+        ERROR_TABLE
+            .iter()
+            .find(|entry| entry.1 == code)
+            .map(|entry| entry.0.clone())
+    }
+
     pub fn message(&self) -> &str {
         match &self {
-            Self::NotSignedIn => "Component not signed in yet!",
-            Self::DuplicateName => "The name is already taken.",
-            Self::NodeUnknown => "Node is unknown.",
-            Self::ReceiverUnknown => "Receiver is not in addresses list.",
-            _ => "Server error.",
+            Self::Custom { message, .. } => message,
+            _ => self.table_entry().2,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{core::FullName, VERSION};
+    use crate::{
+        core::{ContentTypes, FullName},
+        VERSION,
+    };
+
+    use std::sync::atomic::Ordering;
 
-    use super::Message;
+    use super::{Error, Limits, Message, MessageIdGenerator, HEADER_LENGTH, MAX_MESSAGE_ID};
+    use crate::json::{to_vec, ErrorResponse, Request, Response};
 
     fn create_message() -> Message {
         Message::build(
@@ -150,6 +562,97 @@ mod tests {
         )
     }
 
+    fn assert_round_trip(content: ContentTypes) -> Message {
+        let msg = Message::build(
+            b"N1.receiver".to_vec(),
+            b"N1.sender".to_vec(),
+            None,
+            None,
+            1,
+            content,
+        );
+        let reconstructed = Message::new(msg.to_frames().clone()).unwrap();
+        assert_eq!(reconstructed, msg);
+        reconstructed
+    }
+
+    #[test]
+    fn test_round_trip_null() {
+        let msg = assert_round_trip(ContentTypes::Null);
+        assert_eq!(msg.to_frames().len(), 4);
+        assert_eq!(msg.content_frame(), None);
+        assert!(msg.payload().is_empty());
+    }
+    #[test]
+    fn test_round_trip_empty_frame() {
+        let msg = assert_round_trip(ContentTypes::Frame(vec![]));
+        assert_eq!(msg.to_frames().len(), 5);
+        assert_eq!(msg.content_frame(), Some(&vec![]));
+    }
+    #[test]
+    fn test_round_trip_single_frame() {
+        let msg = assert_round_trip(ContentTypes::Frame(b"content".to_vec()));
+        assert_eq!(msg.payload(), vec![b"content".to_vec()]);
+    }
+    #[test]
+    fn test_round_trip_multiple_frames() {
+        let msg = assert_round_trip(ContentTypes::Frames(vec![b"a".to_vec(), b"b".to_vec()]));
+        assert_eq!(msg.payload(), vec![b"a".to_vec(), b"b".to_vec()]);
+    }
+
+    #[test]
+    fn test_len_bytes_and_frame_count() {
+        let msg = create_message();
+        // version + receiver + sender + header + content
+        assert_eq!(msg.len_bytes(), 1 + 11 + 9 + 20 + 7);
+        assert_eq!(msg.frame_count(), 5);
+    }
+
+    #[test]
+    fn test_pretty() {
+        let msg = Message::build_with_binary(
+            b"N1.rec".to_vec(),
+            b"send".to_vec(),
+            &serde_json::json!({"id": 5}),
+            vec![(0..20).collect()],
+        );
+        let text = msg.pretty();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("send -> N1.rec (conversation "));
+        let ending = format!(", message {}, type 1)", msg.message_id());
+        assert!(lines[0].ends_with(&ending));
+        assert_eq!(lines[1], "  [0] 8 bytes: 7b 22 69 64 22 3a 35 7d");
+        assert_eq!(lines[2], r#"      {"id":5}"#);
+        assert_eq!(
+            lines[3],
+            "  [1] 20 bytes: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f ..."
+        );
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_clone_shares_frames() {
+        let msg = create_message();
+        let clone = msg.clone();
+        assert!(std::ptr::eq(msg.to_frames(), clone.to_frames()));
+    }
+    #[test]
+    fn test_modifying_clone_keeps_original() {
+        let msg = create_message();
+        let mut clone = msg.clone();
+        clone.set_sender(b"N2.other".to_vec());
+        assert_eq!(msg.sender_frame(), b"N1.sender");
+        assert_eq!(clone.sender_frame(), b"N2.other");
+    }
+    #[test]
+    fn test_into_frames() {
+        let msg = create_message();
+        let frames = msg.to_frames().clone();
+        let clone = msg.clone();
+        assert_eq!(msg.into_frames(), frames);
+        assert_eq!(clone.into_frames(), frames);
+    }
+
     #[test]
     fn test_version() {
         let msg = create_message();
@@ -159,7 +662,7 @@ mod tests {
     fn test_receiver() {
         let msg = create_message();
         assert_eq!(
-            msg.receiver(),
+            msg.receiver().unwrap(),
             FullName {
                 namespace: b"N1",
                 name: b"receiver"
@@ -167,14 +670,130 @@ mod tests {
         )
     }
     #[test]
+    fn test_is_for_with_namespace() {
+        let msg = create_message();
+        assert!(msg.is_for(&FullName {
+            namespace: b"N1",
+            name: b"receiver"
+        }));
+        assert!(!msg.is_for(&FullName {
+            namespace: b"N2",
+            name: b"receiver"
+        }));
+    }
+    #[test]
+    fn test_is_for_without_namespace() {
+        let mut msg = create_message();
+        msg.set_receiver(b"receiver".to_vec());
+        assert!(msg.is_for(&FullName {
+            namespace: b"N1",
+            name: b"receiver"
+        }));
+    }
+    #[test]
+    fn test_is_for_wrong_component() {
+        let msg = create_message();
+        assert!(!msg.is_for(&FullName {
+            namespace: b"N1",
+            name: b"other"
+        }));
+    }
+    #[test]
+    fn test_is_for_coordinator() {
+        let mut msg = create_message();
+        assert!(!msg.is_for_coordinator(b"N1"));
+        msg.set_receiver(b"COORDINATOR".to_vec());
+        assert!(msg.is_for_coordinator(b"N1"));
+        msg.set_receiver(b"N2.COORDINATOR".to_vec());
+        assert!(!msg.is_for_coordinator(b"N1"));
+    }
+    #[test]
+    fn test_invalid_receiver() {
+        let mut msg = create_message();
+        msg.set_receiver(b"a.b.c".to_vec());
+        assert_eq!(msg.receiver(), Err(Error::ParseError))
+    }
+    #[test]
+    fn test_set_sender_and_receiver() {
+        let mut msg = create_message();
+        msg.set_sender(b"N2.sender".to_vec());
+        msg.set_receiver(b"N3.receiver".to_vec());
+        assert_eq!(msg.sender_frame(), b"N2.sender");
+        assert_eq!(msg.receiver_frame(), b"N3.receiver");
+        assert_eq!(*msg.content_frame().unwrap(), b"content".to_vec())
+    }
+    #[test]
     fn test_header() {
         let msg = create_message();
         let header = msg.header();
         assert_eq!(header.conversation_id.len(), 16);
-        assert_eq!(header.message_id, &[0u8; 3]);
+        assert_eq!(header.message_id.len(), 3);
         assert_eq!(header.message_type, &1);
     }
     #[test]
+    fn test_header_to_owned() {
+        let msg = create_message().with_message_id(258);
+        let header = msg.header().to_owned();
+        assert_eq!(header.conversation_id, msg.header().conversation_id);
+        assert_eq!(header.message_id, [0, 1, 2]);
+        assert_eq!(header.message_type, 1);
+    }
+    #[test]
+    fn test_header_owned_round_trip() {
+        let msg = create_message().with_message_id(5);
+        let frame: Vec<u8> = msg.header().to_owned().into();
+        assert_eq!(&frame, &msg.to_frames()[3]);
+    }
+    #[test]
+    fn test_message_id_default_is_unique() {
+        assert_ne!(create_message().message_id(), create_message().message_id())
+    }
+    #[test]
+    fn test_message_id_explicit_zero() {
+        let msg = Message::build(
+            b"N1.receiver".to_vec(),
+            b"N1.sender".to_vec(),
+            None,
+            Some(&[0, 0, 0]),
+            1,
+            ContentTypes::Null,
+        );
+        assert_eq!(msg.message_id(), 0)
+    }
+    #[test]
+    fn test_message_id_generator_wraps() {
+        let generator = MessageIdGenerator::new();
+        generator.counter.store(MAX_MESSAGE_ID, Ordering::Relaxed);
+        assert_eq!(generator.next_id(), [0xFF, 0xFF, 0xFF]);
+        assert_eq!(generator.next_id(), [0, 0, 0]);
+        assert_eq!(generator.next_id(), [0, 0, 1]);
+    }
+    #[test]
+    fn test_message_id_one() {
+        let msg = create_message().with_message_id(1);
+        assert_eq!(msg.message_id(), 1);
+        assert_eq!(msg.header().message_id, &[0, 0, 1]);
+    }
+    #[test]
+    fn test_message_id_max() {
+        let msg = create_message().with_message_id(MAX_MESSAGE_ID);
+        assert_eq!(msg.message_id(), 0xFFFFFF);
+        assert_eq!(msg.header().message_id(), 0xFFFFFF);
+    }
+    #[test]
+    fn test_message_id_truncated() {
+        let msg = create_message().with_message_id(0x01_00_00_05);
+        assert_eq!(msg.message_id(), 5);
+    }
+    #[test]
+    fn test_message_id_keeps_header() {
+        let msg = create_message();
+        let cid = msg.header().conversation_id.to_vec();
+        let msg = msg.with_message_id(7);
+        assert_eq!(msg.header().conversation_id, cid);
+        assert_eq!(msg.header().message_type, &1);
+    }
+    #[test]
     fn test_content() {
         let msg = create_message();
         assert_eq!(*msg.content_frame().unwrap(), b"content".to_vec())
@@ -184,6 +803,179 @@ mod tests {
         let msg = create_message();
         assert_eq!(msg.payload(), vec![b"content".to_vec()])
     }
+    /// Create `count` frames of `frame_size` bytes, except for a complete header frame.
+    fn create_frames(count: usize, frame_size: usize) -> Vec<Vec<u8>> {
+        let mut frames = vec![vec![0u8; frame_size]; count];
+        if let Some(header) = frames.get_mut(3) {
+            header.resize(HEADER_LENGTH, 0);
+        }
+        frames
+    }
+
+    const LIMITS: Limits = Limits {
+        max_frames: 6,
+        max_bytes: 4 * 12 + HEADER_LENGTH,
+    };
+
+    #[test]
+    fn test_new_limited_at_frame_limit() {
+        assert!(Message::new_limited(create_frames(6, 1), &LIMITS).is_ok())
+    }
+    #[test]
+    fn test_new_limited_over_frame_limit() {
+        assert!(Message::new_limited(create_frames(7, 1), &LIMITS).is_err())
+    }
+    #[test]
+    fn test_new_limited_at_byte_limit() {
+        assert!(Message::new_limited(create_frames(5, 12), &LIMITS).is_ok())
+    }
+    #[test]
+    fn test_new_limited_over_byte_limit() {
+        let mut frames = create_frames(5, 12);
+        frames[4].push(0);
+        assert!(Message::new_limited(frames, &LIMITS).is_err())
+    }
+    #[test]
+    fn test_new_not_enough_frames() {
+        assert!(Message::new(create_frames(3, 1)).is_err())
+    }
+    #[test]
+    fn test_new_short_header() {
+        let mut frames = create_frames(4, 1);
+        frames[3].pop();
+        let err = Message::new(frames).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_error_from_code_round_trip() {
+        for error in Error::all() {
+            assert_eq!(Error::from_code(error.code()).as_ref(), Some(error))
+        }
+    }
+    #[test]
+    fn test_error_codes() {
+        // Codes as defined by JSON-RPC 2.0 and LECO
+        let expected = [
+            (Error::InvalidRequest, -32600),
+            (Error::MethodNotFound, -32601),
+            (Error::InvalidParams, -32602),
+            (Error::InternalError, -32603),
+            (Error::ParseError, -32700),
+            (Error::ServerError, -32000),
+            (Error::NotSignedIn, -32090),
+            (Error::DuplicateName, -32091),
+            (Error::NodeUnknown, -32092),
+            (Error::ReceiverUnknown, -32093),
+            (Error::Unauthorized, -32094),
+        ];
+        assert_eq!(Error::all().count(), expected.len());
+        for (error, code) in expected {
+            assert!(Error::all().any(|known| *known == error));
+            assert_eq!(error.code(), code);
+        }
+    }
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(Error::NotSignedIn.message(), "Component not signed in yet!");
+        assert_eq!(Error::MethodNotFound.message(), "Method not found");
+    }
+    #[test]
+    fn test_error_from_unknown_code() {
+        assert_eq!(Error::from_code(-32050), None)
+    }
+    #[test]
+    fn test_custom_error() {
+        let error = Error::custom(-32010, "motor not homed", Some(serde_json::json!(5))).unwrap();
+        assert_eq!(error.code(), -32010);
+        assert_eq!(error.message(), "motor not homed");
+        assert_eq!(error.data(), Some(&serde_json::json!(5)));
+    }
+    #[test]
+    fn test_custom_error_outside_range() {
+        assert_eq!(Error::custom(-32100, "invalid", None), None);
+        assert_eq!(Error::custom(-31999, "invalid", None), None);
+    }
+    #[test]
+    fn test_custom_error_with_fixed_code() {
+        assert_eq!(Error::custom(-32000, "taken", None), None);
+        assert_eq!(Error::custom(-32093, "taken", None), None);
+    }
+    #[test]
+    fn test_build_request() {
+        let params = Some(serde_json::json!([1, 2]));
+        let (msg, cid) =
+            Message::build_request(b"rec".to_vec(), b"send".to_vec(), 7, "abc", params.clone());
+        assert_eq!(msg.header().conversation_id, cid);
+        let expected = to_vec(&Request::build(7, "abc").with_params(params));
+        assert_eq!(msg.content_frame().unwrap(), &expected);
+        assert_eq!(msg.receiver_frame(), b"rec");
+        assert_eq!(msg.sender_frame(), b"send");
+    }
+    #[test]
+    fn test_build_response() {
+        let cid = [5u8; 16];
+        let msg = Message::build_response(b"rec".to_vec(), b"send".to_vec(), Some(&cid), 7, 8);
+        assert_eq!(msg.header().conversation_id, cid);
+        assert_eq!(
+            msg.content_frame().unwrap(),
+            &to_vec(&Response::build(7, 8))
+        );
+    }
+    #[test]
+    fn test_build_error_response() {
+        let error = Error::NotSignedIn;
+        let msg = Message::build_error_response(b"rec".to_vec(), b"send".to_vec(), None, 7, &error);
+        let expected = to_vec(&ErrorResponse::build(
+            7,
+            error.code(),
+            error.message(),
+            None,
+        ));
+        assert_eq!(msg.content_frame().unwrap(), &expected);
+    }
+    #[test]
+    fn test_build_with_binary() {
+        let msg = Message::build_with_binary(
+            b"N1.receiver".to_vec(),
+            b"N1.sender".to_vec(),
+            &5,
+            vec![b"blob1".to_vec(), b"blob2".to_vec()],
+        );
+        assert_eq!(msg.json_frame().unwrap(), b"5");
+        assert_eq!(
+            msg.binary_frames(),
+            vec![b"blob1".to_vec(), b"blob2".to_vec()]
+        );
+        assert_eq!(msg.to_frames().len(), 7);
+        assert_eq!(msg.to_frames()[5], b"blob1");
+    }
+    #[test]
+    fn test_build_with_binary_without_blobs() {
+        let msg =
+            Message::build_with_binary(b"N1.receiver".to_vec(), b"N1.sender".to_vec(), &5, vec![]);
+        let plain = Message::build(
+            b"N1.receiver".to_vec(),
+            b"N1.sender".to_vec(),
+            Some(msg.header().conversation_id),
+            Some(msg.header().message_id),
+            1,
+            crate::core::ContentTypes::Frame(b"5".to_vec()),
+        );
+        assert_eq!(msg.to_frames(), plain.to_frames());
+        assert!(msg.binary_frames().is_empty());
+    }
 }
 
+pub mod actor;
+
+#[cfg(feature = "async")]
+pub mod async_communicator;
+
 pub mod communicator;
+
+pub mod listener;
+
+pub mod message_types;
+
+pub mod testing;
@@ -0,0 +1,295 @@
+//! Expose a device as a LECO Component, compatible with pyleco's Actor.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use serde_json::{Map, Value};
+
+use super::{
+    communicator::{Communicator, CommunicatorError},
+    listener::{Listener, StopSignal},
+    Error,
+};
+
+/// Read a property of the device
+pub type Getter<D> = Box<dyn Fn(&D) -> Value + Send + Sync>;
+/// Write a property of the device
+pub type Setter<D> = Box<dyn Fn(&mut D, Value) -> Result<(), Error> + Send + Sync>;
+/// Call an action of the device with positional and keyword arguments
+pub type Action<D> =
+    Box<dyn Fn(&mut D, Vec<Value>, Map<String, Value>) -> Result<Value, Error> + Send + Sync>;
+
+/// Properties and actions of the device
+struct Interface<D> {
+    getters: HashMap<String, Getter<D>>,
+    setters: HashMap<String, Setter<D>>,
+    actions: HashMap<String, Action<D>>,
+}
+
+/// A Component controlling a device `D` via `get_parameters`, `set_parameters`, and `call_action`.
+///
+/// ```no_run
+/// # use ruleco::control_protocol::{actor::Actor, communicator::Communicator};
+/// # use serde_json::Value;
+/// struct Lamp {
+///     on: bool,
+/// }
+/// let comm = Communicator::build("lamp", None, None, None).unwrap();
+/// let mut actor = Actor::new(comm, Lamp { on: false });
+/// actor.add_getter("on", |lamp| Value::from(lamp.on));
+/// actor.add_action("toggle", |lamp, _args, _kwargs| {
+///     lamp.on = !lamp.on;
+///     Ok(Value::Null)
+/// });
+/// actor.run().unwrap();
+/// ```
+pub struct Actor<D> {
+    device: Arc<Mutex<D>>,
+    listener: Listener,
+    interface: Interface<D>,
+}
+
+impl<D: Send + 'static> Actor<D> {
+    pub fn new(communicator: Communicator, device: D) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(device)),
+            listener: Listener::new(communicator),
+            interface: Interface {
+                getters: HashMap::new(),
+                setters: HashMap::new(),
+                actions: HashMap::new(),
+            },
+        }
+    }
+
+    /// The device, shared with the running Actor.
+    pub fn device(&self) -> Arc<Mutex<D>> {
+        Arc::clone(&self.device)
+    }
+
+    /// The Listener, e.g. to register additional methods.
+    pub fn listener_mut(&mut self) -> &mut Listener {
+        &mut self.listener
+    }
+
+    /// Make the property `name` readable via `get_parameters`.
+    pub fn add_getter<F>(&mut self, name: &str, getter: F)
+    where
+        F: Fn(&D) -> Value + Send + Sync + 'static,
+    {
+        self.interface
+            .getters
+            .insert(name.to_string(), Box::new(getter));
+    }
+
+    /// Make the property `name` writable via `set_parameters`.
+    pub fn add_setter<F>(&mut self, name: &str, setter: F)
+    where
+        F: Fn(&mut D, Value) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.interface
+            .setters
+            .insert(name.to_string(), Box::new(setter));
+    }
+
+    /// Make the action `name` callable via `call_action`.
+    pub fn add_action<F>(&mut self, name: &str, action: F)
+    where
+        F: Fn(&mut D, Vec<Value>, Map<String, Value>) -> Result<Value, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.interface
+            .actions
+            .insert(name.to_string(), Box::new(action));
+    }
+
+    /// Call `task` with the device about every `interval`, between handling requests.
+    pub fn set_housekeeping<F>(&mut self, interval: Duration, task: F)
+    where
+        F: Fn(&mut D) + Send + 'static,
+    {
+        let device = self.device();
+        self.listener
+            .set_housekeeping(interval, move || task(&mut device.lock().unwrap()));
+    }
+
+    /// Get a signal to stop the Actor from another thread.
+    pub fn stop_signal(&self) -> StopSignal {
+        self.listener.stop_signal()
+    }
+
+    /// Sign in and answer requests until stopped or shut down, see [`Listener::run`].
+    pub fn run(self) -> Result<(), CommunicatorError> {
+        let mut listener = self.listener;
+        let interface = Arc::new(self.interface);
+        let device = self.device;
+        let (shared, dev) = (Arc::clone(&interface), Arc::clone(&device));
+        listener.register_with_description(
+            "get_parameters",
+            "Get the values of the properties in 'parameters'.",
+            move |params| shared.get_parameters(&dev.lock().unwrap(), params),
+        );
+        let (shared, dev) = (Arc::clone(&interface), Arc::clone(&device));
+        listener.register_with_description(
+            "set_parameters",
+            "Set the properties to the values in 'parameters'.",
+            move |params| shared.set_parameters(&mut dev.lock().unwrap(), params),
+        );
+        listener.register_with_description(
+            "call_action",
+            "Call the 'action' with 'args' and 'kwargs'.",
+            move |params| interface.call_action(&mut device.lock().unwrap(), params),
+        );
+        listener.run()
+    }
+
+    /// Run the Actor in a new thread.
+    pub fn spawn(self) -> (JoinHandle<Result<(), CommunicatorError>>, StopSignal) {
+        let stop = self.stop_signal();
+        (std::thread::spawn(move || self.run()), stop)
+    }
+}
+
+impl<D> Interface<D> {
+    /// Answer `get_parameters` with `{"parameters": [names]}` by `{name: value}`.
+    fn get_parameters(&self, device: &D, params: Option<Value>) -> Result<Value, Error> {
+        let names = take_param(params, "parameters")?;
+        let names = names.as_array().ok_or(Error::InvalidParams)?;
+        let mut values = Map::new();
+        for name in names {
+            let name = name.as_str().ok_or(Error::InvalidParams)?;
+            let getter = self
+                .getters
+                .get(name)
+                .ok_or_else(|| unknown("property", name))?;
+            values.insert(name.to_string(), getter(device));
+        }
+        Ok(Value::Object(values))
+    }
+
+    /// Answer `set_parameters` with `{"parameters": {name: value}}`.
+    fn set_parameters(&self, device: &mut D, params: Option<Value>) -> Result<Value, Error> {
+        let Value::Object(values) = take_param(params, "parameters")? else {
+            return Err(Error::InvalidParams);
+        };
+        for (name, value) in values {
+            let setter = self
+                .setters
+                .get(&name)
+                .ok_or_else(|| unknown("property", &name))?;
+            setter(device, value)?;
+        }
+        Ok(Value::Null)
+    }
+
+    /// Answer `call_action` with `{"action": name, "args": [...], "kwargs": {...}}`.
+    fn call_action(&self, device: &mut D, params: Option<Value>) -> Result<Value, Error> {
+        let Some(Value::Object(mut params)) = params else {
+            return Err(Error::InvalidParams);
+        };
+        let name = match params.remove("action") {
+            Some(Value::String(name)) => name,
+            _ => return Err(Error::InvalidParams),
+        };
+        let args = match params.remove("args") {
+            Some(Value::Array(args)) => args,
+            None | Some(Value::Null) => Vec::new(),
+            Some(_) => return Err(Error::InvalidParams),
+        };
+        let kwargs = match params.remove("kwargs") {
+            Some(Value::Object(kwargs)) => kwargs,
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => return Err(Error::InvalidParams),
+        };
+        let action = self
+            .actions
+            .get(&name)
+            .ok_or_else(|| unknown("action", &name))?;
+        action(device, args, kwargs)
+    }
+}
+
+/// Get the parameter `key` out of the request's `params` object.
+fn take_param(params: Option<Value>, key: &str) -> Result<Value, Error> {
+    match params {
+        Some(Value::Object(mut params)) => params.remove(key).ok_or(Error::InvalidParams),
+        _ => Err(Error::InvalidParams),
+    }
+}
+
+/// Error for an unknown property or action `name`.
+fn unknown(kind: &str, name: &str) -> Error {
+    Error::custom(-32001, format!("Unknown {kind} '{name}'."), None).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    struct Counter {
+        count: i64,
+    }
+
+    fn make_interface() -> Interface<Counter> {
+        let mut actor = Actor::new(
+            Communicator::build("counter", None, Some(12399), None).unwrap(),
+            Counter { count: 0 },
+        );
+        actor.add_getter("count", |counter| Value::from(counter.count));
+        actor.add_setter("count", |counter, value| {
+            counter.count = value.as_i64().ok_or(Error::InvalidParams)?;
+            Ok(())
+        });
+        actor.add_action("add", |counter, args, kwargs| {
+            let step = args.first().or(kwargs.get("step")).and_then(Value::as_i64);
+            counter.count += step.unwrap_or(1);
+            Ok(Value::from(counter.count))
+        });
+        actor.interface
+    }
+
+    #[test]
+    fn test_parameters() {
+        let interface = make_interface();
+        let mut counter = Counter { count: 3 };
+        let params = json!({"parameters": ["count"]});
+        let result = interface.get_parameters(&counter, Some(params));
+        assert_eq!(result.unwrap(), json!({"count": 3}));
+        let params = json!({"parameters": {"count": 7}});
+        interface
+            .set_parameters(&mut counter, Some(params))
+            .unwrap();
+        assert_eq!(counter.count, 7);
+        let params = json!({"parameters": ["speed"]});
+        let result = interface.get_parameters(&counter, Some(params));
+        assert_eq!(result.unwrap_err().code(), -32001);
+    }
+
+    #[test]
+    fn test_call_action() {
+        let interface = make_interface();
+        let mut counter = Counter { count: 0 };
+        let result = interface.call_action(&mut counter, Some(json!({"action": "add"})));
+        assert_eq!(result.unwrap(), 1);
+        let params = json!({"action": "add", "args": [5]});
+        assert_eq!(
+            interface.call_action(&mut counter, Some(params)).unwrap(),
+            6
+        );
+        let params = json!({"action": "add", "kwargs": {"step": 4}});
+        assert_eq!(
+            interface.call_action(&mut counter, Some(params)).unwrap(),
+            10
+        );
+        let result = interface.call_action(&mut counter, Some(json!({"args": [5]})));
+        assert_eq!(result.unwrap_err(), Error::InvalidParams);
+    }
+}
@@ -0,0 +1,157 @@
+//! Async facade of the [`Communicator`] for tokio applications
+//!
+//! The blocking zmq calls run in tokio's blocking thread pool, such that the async runtime is not
+//! blocked. The futures can be wrapped in `tokio::time::timeout`; the blocking call itself still
+//! ends after the Communicator's own timeout.
+//!
+//! Cancelling a future does not abort its blocking call: It keeps the Communicator locked until
+//! it ends, such that the next call waits for it.
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use super::communicator::{Communicator, CommunicatorError};
+
+/// A [`Communicator`] with async methods.
+///
+/// Cloning is cheap, all clones share the same Communicator.
+#[derive(Clone)]
+pub struct AsyncCommunicator {
+    inner: Arc<Mutex<Communicator>>,
+}
+
+impl From<Communicator> for AsyncCommunicator {
+    fn from(communicator: Communicator) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(communicator)),
+        }
+    }
+}
+
+impl AsyncCommunicator {
+    /// See [`Communicator::build`].
+    pub fn build(
+        name: &str,
+        host: Option<&str>,
+        port: Option<u16>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, CommunicatorError> {
+        Communicator::build(name, host, port, timeout).map(Self::from)
+    }
+
+    /// Run `f` with the Communicator in the blocking thread pool.
+    async fn run<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Communicator) -> T + Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        let task = tokio::task::spawn_blocking(move || f(&mut inner.lock().unwrap()));
+        match task.await {
+            Ok(result) => result,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// See [`Communicator::sign_in`].
+    pub async fn sign_in(&self) -> Result<(), CommunicatorError> {
+        self.run(|comm| comm.sign_in()).await
+    }
+
+    /// See [`Communicator::sign_out`].
+    pub async fn sign_out(&self) -> Result<(), CommunicatorError> {
+        self.run(|comm| comm.sign_out()).await
+    }
+
+    /// See [`Communicator::send_rpc_message`].
+    pub async fn send_rpc_message(
+        &self,
+        receiver: String,
+        method: String,
+    ) -> Result<(Vec<u8>, u16), CommunicatorError> {
+        self.run(move |comm| comm.send_rpc_message(receiver, method))
+            .await
+    }
+
+    /// See [`Communicator::read_rpc_message`].
+    pub async fn read_rpc_message(&self) -> Result<Value, CommunicatorError> {
+        self.run(|comm| comm.read_rpc_message()).await
+    }
+
+    /// See [`Communicator::ask`].
+    pub async fn ask(
+        &self,
+        receiver: String,
+        method: String,
+        params: Option<Value>,
+        timeout: Duration,
+    ) -> Result<Value, CommunicatorError> {
+        self.run(move |comm| comm.ask(&receiver, &method, params, timeout))
+            .await
+    }
+
+    /// See [`Communicator::full_name`].
+    ///
+    /// Waits for a running call, as it might change the full name, e.g. by signing in again.
+    pub async fn full_name(&self) -> String {
+        self.run(|comm| comm.full_name().into_owned()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control_protocol::Message;
+
+    #[tokio::test]
+    async fn test_ask() {
+        let ctx = zmq::Context::new();
+        let router = ctx.socket(zmq::ROUTER).unwrap();
+        router.bind("tcp://*:12373").unwrap();
+        let handle = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let response = Message::build_response(
+                b"comm".to_vec(),
+                b"N1.COORDINATOR".to_vec(),
+                Some(request.header().conversation_id),
+                request.message_id() as u16,
+                5,
+            );
+            router.send(identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(response.to_frames(), 0).unwrap();
+        });
+        let comm = AsyncCommunicator::build("comm", None, Some(12373), None).unwrap();
+        let result = comm
+            .ask(
+                "COORDINATOR".to_string(),
+                "pong".to_string(),
+                None,
+                Duration::from_secs(1),
+            )
+            .await;
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_tokio_timeout() {
+        let comm = AsyncCommunicator::build("comm", None, Some(12374), None).unwrap();
+        let result = tokio::time::timeout(Duration::from_millis(20), comm.sign_in()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_name_after_cancelled_call() {
+        let router = zmq::Context::new().socket(zmq::ROUTER).unwrap();
+        router.bind("tcp://*:12394").unwrap();
+        let timeout = Some(Duration::from_millis(100));
+        let comm = AsyncCommunicator::build("comm", None, Some(12394), timeout).unwrap();
+        let result = tokio::time::timeout(Duration::from_millis(20), comm.sign_in()).await;
+        assert!(result.is_err());
+        assert_eq!(comm.full_name().await, "comm");
+    }
+}
@@ -1,96 +1,2880 @@
 //! Helper utility to communicate
 //!
 //!
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    fmt, io,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    core::FullName,
-    json::{to_vec, Request, Response},
+    core::{
+        compose_full_name, create_conversation_id, ContentTypes, CurveClientKeys, Endpoint,
+        FullName,
+    },
+    json::{to_vec, ErrorContent, Request, ResponseContent},
 };
-use serde_json::Error;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
 use zmq;
 
-use super::Message;
+use super::{message_types, Error, Message};
+
+/// Errors which may occur while communicating
+#[derive(Debug)]
+pub enum CommunicatorError {
+    /// The peer answered with a known LECO / JSON-RPC error
+    Rpc(Error),
+    /// The peer answered with an error code unknown to us
+    RpcError {
+        code: i16,
+        message: String,
+        data: Option<Value>,
+    },
+    /// The response could not be interpreted
+    Json(serde_json::Error),
+    /// No response arrived in time
+    Timeout,
+    /// The response belongs to the conversation, but answers a different request id
+    IdMismatch { expected: u16, received: u16 },
+    /// The host or port do not form a valid endpoint
+    InvalidEndpoint(String),
+    /// The zmq socket could not be created
+    Socket(zmq::Error),
+    /// The socket could not connect to the endpoint
+    Connect { endpoint: String, error: zmq::Error },
+    /// Polling, sending, or receiving on the socket failed
+    Transport(zmq::Error),
+    /// The message could not be queued without blocking, e.g. as the send queue is full
+    WouldBlock,
+    /// No response arrived in time, although the request was sent `attempts` times
+    RetriesExhausted { attempts: u32 },
+    /// The received frames do not form a valid LECO message
+    InvalidMessage(io::Error),
+}
+
+impl fmt::Display for CommunicatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rpc(error) => write!(f, "remote error {}: {}", error.code(), error.message()),
+            Self::RpcError { code, message, .. } => write!(f, "remote error {code}: {message}"),
+            Self::Json(err) => write!(f, "invalid response: {err}"),
+            Self::Timeout => write!(f, "no response in time"),
+            Self::IdMismatch { expected, received } => {
+                write!(f, "response to request {received} instead of {expected}")
+            }
+            Self::InvalidEndpoint(host) => write!(f, "invalid endpoint '{host}'"),
+            Self::Socket(err) => write!(f, "socket creation failed: {err}"),
+            Self::Connect { endpoint, error } => {
+                write!(f, "connecting to '{endpoint}' failed: {error}")
+            }
+            Self::Transport(err) => write!(f, "socket error: {err}"),
+            Self::WouldBlock => write!(f, "sending would block"),
+            Self::RetriesExhausted { attempts } => {
+                write!(f, "no response in time after {attempts} attempts")
+            }
+            Self::InvalidMessage(err) => write!(f, "invalid message: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CommunicatorError {}
+
+/// Failure of [`Communicator::ask_json`] with the context of the call
+#[derive(Debug)]
+pub struct RpcCallError {
+    pub receiver: String,
+    pub method: String,
+    pub conversation_id: Vec<u8>,
+    pub kind: Box<RpcCallErrorKind>,
+}
+
+#[derive(Debug)]
+pub enum RpcCallErrorKind {
+    /// The request failed or the peer answered with an error
+    Communication(CommunicatorError),
+    /// The result does not fit the expected type; `payload` is the (truncated) response content
+    Deserialize {
+        error: serde_json::Error,
+        payload: Vec<u8>,
+    },
+}
+
+/// Number of payload bytes kept in [`RpcCallErrorKind::Deserialize`]
+pub const MAX_PAYLOAD_CONTEXT: usize = 200;
+
+impl fmt::Display for RpcCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "calling '{}' of '{}' (conversation {}) failed: ",
+            self.method,
+            self.receiver,
+            Uuid::from_slice(&self.conversation_id).unwrap_or_default()
+        )?;
+        match self.kind.as_ref() {
+            RpcCallErrorKind::Communication(err) => write!(f, "{err}"),
+            RpcCallErrorKind::Deserialize { error, payload } => {
+                write!(f, "{error}, payload: {}", String::from_utf8_lossy(payload))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcCallError {}
+
+impl From<serde_json::Error> for CommunicatorError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<ErrorContent> for CommunicatorError {
+    /// Map known error codes to [`Error`] variants, keep the others as they are.
+    fn from(error: ErrorContent) -> Self {
+        match Error::from_code(error.code) {
+            Some(error) => Self::Rpc(error),
+            None => match Error::custom(error.code, &error.message, error.data.clone()) {
+                Some(error) => Self::Rpc(error),
+                None => Self::RpcError {
+                    code: error.code,
+                    message: error.message,
+                    data: error.data,
+                },
+            },
+        }
+    }
+}
+
+/// Timeout of blocking reads, if none is given in [`Communicator::build`]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Maximum number of buffered messages, see [`Communicator::read_buffered`], and of messages
+/// read by the heartbeat thread, which are not yet consumed
+pub const MAX_BUFFERED: usize = 100;
+/// Maximum time to wait for the sign-out response when dropping a Communicator
+const DROP_TIMEOUT: Duration = Duration::from_millis(100);
+/// Number of remembered conversations of earlier attempts, whose late responses are discarded
+const MAX_STALE: usize = 100;
+
+/// Settings of a [`Communicator`], see [`Communicator::build_with`]
+///
+/// ```
+/// # use std::time::Duration;
+/// # use ruleco::control_protocol::communicator::CommunicatorOptions;
+/// let options = CommunicatorOptions::new("motor")
+///     .timeout(Duration::from_millis(500))
+///     .heartbeat_interval(Duration::from_secs(5));
+/// ```
+#[derive(Clone)]
+pub struct CommunicatorOptions {
+    name: String,
+    endpoint: Endpoint,
+    context: Option<zmq::Context>,
+    timeout: Duration,
+    retry_policy: Option<RetryPolicy>,
+    rename_attempts: u8,
+    namespace_completion: bool,
+    auto_sign_in: bool,
+    heartbeat_interval: Option<Duration>,
+    non_blocking_send: bool,
+    curve_keys: Option<CurveClientKeys>,
+}
+
+impl CommunicatorOptions {
+    /// Options with defaults, connecting to the local Coordinator.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            endpoint: Endpoint::Tcp {
+                host: "localhost".to_string(),
+                port: 12300,
+            },
+            context: None,
+            timeout: DEFAULT_TIMEOUT,
+            retry_policy: None,
+            rename_attempts: 0,
+            namespace_completion: true,
+            auto_sign_in: true,
+            heartbeat_interval: None,
+            non_blocking_send: false,
+            curve_keys: None,
+        }
+    }
+
+    /// Connect to `endpoint`; an `inproc` endpoint requires the `context` of the Coordinator.
+    pub fn endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    pub fn context(mut self, context: zmq::Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// How long blocking reads wait for a message.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// See [`Communicator::with_retry_policy`].
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// See [`Communicator::with_rename_attempts`].
+    pub fn rename_attempts(mut self, attempts: u8) -> Self {
+        self.rename_attempts = attempts;
+        self
+    }
+
+    /// See [`Communicator::with_namespace_completion`].
+    pub fn namespace_completion(mut self, enabled: bool) -> Self {
+        self.namespace_completion = enabled;
+        self
+    }
+
+    /// See [`Communicator::with_auto_sign_in`].
+    pub fn auto_sign_in(mut self, enabled: bool) -> Self {
+        self.auto_sign_in = enabled;
+        self
+    }
+
+    /// Start the heartbeat (see [`Communicator::start_heartbeat`]) with `interval` whenever
+    /// signing in succeeds.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self
+    }
+
+    /// See [`Communicator::with_non_blocking_send`].
+    pub fn non_blocking_send(mut self, enabled: bool) -> Self {
+        self.non_blocking_send = enabled;
+        self
+    }
+
+    /// Encrypt the connection with CurveZMQ, for a Coordinator configured with CURVE.
+    pub fn curve_keys(mut self, keys: CurveClientKeys) -> Self {
+        self.curve_keys = Some(keys);
+        self
+    }
+}
+
+/// Result of [`Communicator::probe`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoordinatorInfo {
+    /// Round-trip time of a ping to the Coordinator
+    pub round_trip: Duration,
+    /// Full name of the Coordinator, e.g. `N1.COORDINATOR`
+    pub name: Option<String>,
+    pub namespace: Option<String>,
+    /// Version of the Coordinator's implementation
+    pub version: Option<String>,
+}
+
+/// Overrides of the Communicator's settings for a single [`Communicator::ask_with`] call
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AskOptions {
+    /// How long to wait for the response, instead of the Communicator's timeout
+    pub timeout: Option<Duration>,
+    /// Retry policy instead of the Communicator's one
+    pub retry_policy: Option<RetryPolicy>,
+    /// Whether to sign in again after a `NotSignedIn` error
+    pub auto_sign_in: Option<bool>,
+}
+
+/// How often and how fast [`Communicator::ask`] resends a request without response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub attempts: u32,
+    /// Pause between a timed out attempt and the next one
+    pub backoff: Duration,
+}
+
+/// Handler for unsolicited requests.
+///
+/// Returns the result (or error) to answer with, or `None` to hand the request to the caller.
+pub type RequestHandler = Box<dyn Fn(&Request) -> Option<Result<Value, Error>> + Send>;
+
+/// Direction of a message passing the Communicator's socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// Gets to see every message sent or received, see [`Communicator::set_observer`]
+pub type Observer = Box<dyn Fn(Direction, &Message) + Send>;
+
+/// Observer writing each message to the `log` crate at trace level.
+pub fn log_observer(direction: Direction, message: &Message) {
+    if !log::log_enabled!(log::Level::Trace) {
+        return;
+    }
+    let content = message
+        .content_frame()
+        .map(|content| String::from_utf8_lossy(content))
+        .unwrap_or_default();
+    log::trace!(
+        "{direction:?}: {} -> {}, conversation {}, message {}, type {}, content: {content}",
+        String::from_utf8_lossy(message.sender_frame()),
+        String::from_utf8_lossy(message.receiver_frame()),
+        Uuid::from_slice(message.header().conversation_id).unwrap_or_default(),
+        message.message_id(),
+        message.header().message_type,
+    );
+}
+
+/// Connection state of a [`Communicator`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommunicatorState {
+    /// The socket is not connected to any Coordinator
+    Disconnected,
+    /// Connected to a Coordinator, but not signed in
+    Connected,
+    /// Signed in to the Coordinator of `namespace`
+    SignedIn { namespace: String },
+}
+
+/// The socket, shared with the heartbeat thread and the handles
+struct Connection {
+    socket: zmq::Socket,
+    state: CommunicatorState,
+    /// Messages read by the heartbeat thread or by handles, which are not yet consumed
+    pending: VecDeque<Message>,
+    /// Handles waiting for the response in a conversation, see [`CommunicatorHandle::ask`]
+    waiting: HashMap<Vec<u8>, mpsc::Sender<Message>>,
+    observer: Option<Observer>,
+    /// Flags for sending, `zmq::DONTWAIT` for non-blocking sends
+    send_flags: i32,
+}
+
+impl Connection {
+    fn send(&self, message: Message) -> Result<(), CommunicatorError> {
+        self.observe(Direction::Out, &message);
+        self.socket
+            .send_multipart(message.into_frames(), self.send_flags)
+            .map_err(|err| match err {
+                zmq::Error::EAGAIN => CommunicatorError::WouldBlock,
+                err => CommunicatorError::Transport(err),
+            })
+    }
+
+    /// Show `message` to the observer, ignoring its panics.
+    fn observe(&self, direction: Direction, message: &Message) {
+        if let Some(observer) = &self.observer {
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| observer(direction, message)));
+        }
+    }
+
+    /// Wait up to `timeout_ms` for a message to arrive at the socket.
+    fn poll(&self, timeout_ms: i64) -> Result<bool, CommunicatorError> {
+        let events = self
+            .socket
+            .poll(zmq::POLLIN, timeout_ms)
+            .map_err(CommunicatorError::Transport)?;
+        Ok(events == 1)
+    }
+
+    /// Receive the next message from the socket.
+    fn recv(&self) -> Result<Message, CommunicatorError> {
+        let frames = self
+            .socket
+            .recv_multipart(0)
+            .map_err(CommunicatorError::Transport)?;
+        let message = Message::new(frames).map_err(CommunicatorError::InvalidMessage)?;
+        self.observe(Direction::In, &message);
+        Ok(message)
+    }
+
+    /// Receive a message, which already arrived, without waiting.
+    ///
+    /// Returns `None`, if no message is waiting or the socket fails. Invalid messages are skipped.
+    fn read_arrived(&self) -> Option<Message> {
+        while let Ok(true) = self.poll(0) {
+            match self.recv() {
+                Ok(message) => return Some(message),
+                Err(CommunicatorError::Transport(err)) => {
+                    log::warn!("Reading from the socket failed: {err}.");
+                    return None;
+                }
+                Err(_) => continue,
+            }
+        }
+        None
+    }
+
+    fn has_message(&self, timeout_ms: i64) -> Result<bool, CommunicatorError> {
+        Ok(!self.pending.is_empty() || self.poll(timeout_ms)?)
+    }
+
+    /// Get a pending message or wait until `deadline` for a new one.
+    ///
+    /// Responses awaited by handles are handed to them instead.
+    fn next_message(&mut self, deadline: Instant) -> Result<Option<Message>, CommunicatorError> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self.poll(remaining.as_micros().div_ceil(1000) as i64)? {
+                return Ok(None);
+            }
+            let message = self.recv()?;
+            if let Some(message) = self.dispatch(message) {
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    /// Hand `message` to the handle waiting for its conversation.
+    ///
+    /// Returns the message, if no handle waits for it.
+    fn dispatch(&mut self, message: Message) -> Option<Message> {
+        match self.waiting.remove(message.header().conversation_id) {
+            Some(handle) => {
+                let _ = handle.send(message);
+                None
+            }
+            None => Some(message),
+        }
+    }
+
+    /// Hand `message` to its waiting handle, answer it as `full_name`, if it is a ping, or keep
+    /// it pending for the owner of the Communicator.
+    fn deliver(&mut self, message: Message, full_name: &[u8]) {
+        let Some(message) = self.dispatch(message) else {
+            return;
+        };
+        match answer_ping(&message, full_name) {
+            Some(response) => {
+                let _ = self.send(response);
+            }
+            None => push_bounded(&mut self.pending, message),
+        }
+    }
+}
+
+/// The background thread started by [`Communicator::start_heartbeat`]
+struct Heartbeat {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Heartbeat {
+    fn start(
+        connection: Arc<Mutex<Connection>>,
+        full_name: Vec<u8>,
+        conversation_id: [u8; 16],
+        interval: Duration,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                {
+                    let Ok(connection) = connection.lock() else {
+                        return;
+                    };
+                    if !matches!(connection.state, CommunicatorState::SignedIn { .. }) {
+                        continue;
+                    }
+                    // The Coordinator answers pings to itself even before signing in, but routes
+                    // messages only for signed in Components.
+                    let _ = connection.send(Message::build(
+                        full_name.clone(),
+                        full_name.clone(),
+                        Some(&conversation_id),
+                        None,
+                        message_types::JSON,
+                        ContentTypes::Frame(to_vec(&Request::build(0, "pong"))),
+                    ));
+                }
+                // Answer pings, but leave everything else to the owner of the Communicator.
+                // The lock is released after each message, such that readers and senders are
+                // not blocked by a long drain.
+                loop {
+                    let Ok(mut connection) = connection.lock() else {
+                        return;
+                    };
+                    let Some(message) = connection.read_arrived() else {
+                        break;
+                    };
+                    if message.header().conversation_id == conversation_id {
+                        if is_not_signed_in(&message) {
+                            connection.state = CommunicatorState::Connected;
+                        }
+                        continue;
+                    }
+                    connection.deliver(message, &full_name);
+                }
+            }
+        });
+        Self { stop, thread }
+    }
+
+    fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
 
 pub struct Communicator {
     name: Vec<u8>,
+    /// Name used as sender: the name, namespaced after signing in
     full_name: Vec<u8>,
-    socket: zmq::Socket,
+    connection: Arc<Mutex<Connection>>,
+    heartbeat: Option<Heartbeat>,
+    /// Conversation id of all heartbeat requests, whose responses are skipped
+    heartbeat_id: [u8; 16],
+    /// Endpoint the socket is connected to
+    endpoint: Endpoint,
+    /// How long blocking reads wait for a message
+    timeout: Duration,
+    /// Messages received while waiting for a different response
+    buffer: VecDeque<Message>,
+    /// How often to retry signing in with a suffixed name, if the name is taken
+    rename_attempts: u8,
+    /// Answers unsolicited requests other than pings
+    request_handler: Option<RequestHandler>,
+    /// Id of the next request, used as JSON-RPC id and header message id
+    request_id: Arc<AtomicU16>,
+    /// Resend requests of [`Communicator::ask`] on timeout
+    retry_policy: Option<RetryPolicy>,
+    /// Conversation ids of answered requests, whose duplicate responses are discarded
+    stale: VecDeque<Vec<u8>>,
+    /// Prepend the own namespace to receiver names without namespace
+    complete_namespace: bool,
+    /// Sign in again, if the Coordinator answers with `NotSignedIn`
+    auto_sign_in: bool,
+    /// Interval of the heartbeat started after signing in
+    heartbeat_interval: Option<Duration>,
 }
 impl Communicator {
-    pub fn build(name: &str, host: Option<&str>, port: Option<u16>) -> Self {
-        Self {
+    pub fn build(
+        name: &str,
+        host: Option<&str>,
+        port: Option<u16>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, CommunicatorError> {
+        Self::build_with_endpoint(name, make_endpoint(host, port)?, None, timeout)
+    }
+
+    /// Create a Communicator connecting to any `endpoint`.
+    ///
+    /// An `inproc` endpoint requires the `context` of the Coordinator.
+    pub fn build_with_endpoint(
+        name: &str,
+        endpoint: Endpoint,
+        context: Option<zmq::Context>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, CommunicatorError> {
+        Self::connect(name, endpoint, context, timeout, None)
+    }
+
+    fn connect(
+        name: &str,
+        endpoint: Endpoint,
+        context: Option<zmq::Context>,
+        timeout: Option<Duration>,
+        curve_keys: Option<&CurveClientKeys>,
+    ) -> Result<Self, CommunicatorError> {
+        let context = context.unwrap_or_default();
+        Ok(Self {
             name: name.as_bytes().to_vec(),
             full_name: name.as_bytes().to_vec(),
-            socket: Self::create_socket(host, port),
-        }
+            connection: Arc::new(Mutex::new(Connection {
+                socket: Self::connect_socket(&endpoint, &context, curve_keys)?,
+                state: CommunicatorState::Connected,
+                pending: VecDeque::new(),
+                waiting: HashMap::new(),
+                observer: None,
+                send_flags: 0,
+            })),
+            heartbeat: None,
+            heartbeat_id: create_conversation_id(),
+            endpoint,
+            timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            buffer: VecDeque::new(),
+            rename_attempts: 0,
+            request_handler: None,
+            request_id: Arc::new(AtomicU16::new(1)),
+            retry_policy: None,
+            stale: VecDeque::new(),
+            complete_namespace: true,
+            auto_sign_in: true,
+            heartbeat_interval: None,
+        })
     }
 
-    pub fn create_socket(host: Option<&str>, port: Option<u16>) -> zmq::Socket {
-        let ctx = zmq::Context::new();
-        let socket = ctx.socket(zmq::DEALER).unwrap();
-        let host: &str = host.unwrap_or("localhost");
-        let port = port.unwrap_or(12300);
-        socket.connect(&format!("tcp://{host}:{port}")).unwrap();
+    /// Create a Communicator with all `options`.
+    pub fn build_with(options: CommunicatorOptions) -> Result<Self, CommunicatorError> {
+        let mut communicator = Self::connect(
+            &options.name,
+            options.endpoint,
+            options.context,
+            Some(options.timeout),
+            options.curve_keys.as_ref(),
+        )?;
+        communicator.retry_policy = options.retry_policy;
+        communicator.rename_attempts = options.rename_attempts;
+        communicator.complete_namespace = options.namespace_completion;
+        communicator.auto_sign_in = options.auto_sign_in;
+        communicator.heartbeat_interval = options.heartbeat_interval;
+        Ok(communicator.with_non_blocking_send(options.non_blocking_send))
+    }
+
+    /// Show every received message and every message about to be sent to `observer`.
+    ///
+    /// Panics of the observer are caught. Use [`log_observer`] to log all messages.
+    pub fn set_observer(&mut self, observer: Observer) {
+        self.connection.lock().unwrap().observer = Some(observer);
+    }
+
+    /// Answer unsolicited requests with `handler` while reading messages.
+    pub fn set_request_handler(&mut self, handler: RequestHandler) {
+        self.request_handler = Some(handler);
+    }
+
+    /// Retry signing in up to `attempts` times with a numeric suffix (`comm2`, `comm3`, ...), if
+    /// the name is already taken.
+    pub fn with_rename_attempts(mut self, attempts: u8) -> Self {
+        self.rename_attempts = attempts;
+        self
+    }
+
+    /// Resend requests of [`Communicator::ask`] and [`Communicator::call_method`], if no
+    /// response arrives in time.
+    ///
+    /// Retries keep the JSON-RPC id, but use a new conversation id. A response to any attempt is
+    /// accepted, late responses to the other attempts are discarded.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Whether to prepend the own namespace to receiver names without namespace (default).
+    ///
+    /// With it, `motor` is addressed as `N1.motor` after signing in to the Coordinator `N1`.
+    /// `COORDINATOR` stays untouched, such that it always reaches the own Coordinator.
+    pub fn with_namespace_completion(mut self, enabled: bool) -> Self {
+        self.complete_namespace = enabled;
+        self
+    }
+
+    /// Whether [`Communicator::ask`] signs in again and repeats the request once, if the
+    /// Coordinator answers with `NotSignedIn` (default).
+    ///
+    /// If the name is taken meanwhile, `ask` fails with `CommunicatorError::Rpc(DuplicateName)`.
+    pub fn with_auto_sign_in(mut self, enabled: bool) -> Self {
+        self.auto_sign_in = enabled;
+        self
+    }
+
+    /// Whether sending fails with `CommunicatorError::WouldBlock` instead of waiting, if the
+    /// message cannot be queued, e.g. without connection or with a full send queue.
+    pub fn with_non_blocking_send(self, enabled: bool) -> Self {
+        self.connection.lock().unwrap().send_flags = match enabled {
+            true => zmq::DONTWAIT,
+            false => 0,
+        };
+        self
+    }
+
+    /// Like [`Communicator::build`], but panics if the socket cannot be set up.
+    pub fn build_or_panic(
+        name: &str,
+        host: Option<&str>,
+        port: Option<u16>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self::build(name, host, port, timeout).expect("Could not set up the Communicator.")
+    }
+
+    pub fn create_socket(
+        host: Option<&str>,
+        port: Option<u16>,
+    ) -> Result<zmq::Socket, CommunicatorError> {
+        Self::connect_socket(&make_endpoint(host, port)?, &zmq::Context::new(), None)
+    }
+
+    fn connect_socket(
+        endpoint: &Endpoint,
+        context: &zmq::Context,
+        curve_keys: Option<&CurveClientKeys>,
+    ) -> Result<zmq::Socket, CommunicatorError> {
+        let socket = context
+            .socket(zmq::DEALER)
+            .map_err(CommunicatorError::Socket)?;
+        // Do not block on drop with messages, which never reached a Coordinator.
+        socket.set_linger(0).map_err(CommunicatorError::Socket)?;
+        if let Some(keys) = curve_keys {
+            keys.apply(&socket).map_err(CommunicatorError::Socket)?;
+        }
+        let endpoint = endpoint.to_string();
         socket
+            .connect(&endpoint)
+            .map_err(|error| CommunicatorError::Connect { endpoint, error })?;
+        Ok(socket)
+    }
+
+    /// The name given in [`Communicator::build`] (or the suffixed one after renaming).
+    pub fn name(&self) -> &str {
+        // Created from a `&str`, so it is valid UTF-8.
+        std::str::from_utf8(&self.name).unwrap_or_default()
+    }
+
+    /// The name used as sender: `namespace.name` while signed in, otherwise just the name.
+    ///
+    /// Use it as topic of a [`DataPublisher`](crate::data_protocol::DataPublisher):
+    /// ```no_run
+    /// # use ruleco::{control_protocol::communicator::Communicator, data_protocol::DataPublisher};
+    /// let mut comm = Communicator::build("motor", None, None, None).unwrap();
+    /// comm.sign_in().unwrap();
+    /// let publisher = DataPublisher::new(comm.full_name().to_string(), "localhost", 11100);
+    /// publisher.send_message(b"5".to_vec());
+    /// ```
+    pub fn full_name(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.full_name)
+    }
+
+    /// The namespace of the Coordinator, while signed in.
+    pub fn namespace(&self) -> Option<&str> {
+        if !self.is_signed_in() {
+            return None;
+        }
+        let full_name = FullName::from_slice(&self.full_name).ok()?;
+        std::str::from_utf8(full_name.namespace).ok()
+    }
+
+    /// Whether the Coordinator accepted the sign-in (as far as we know).
+    pub fn is_signed_in(&self) -> bool {
+        matches!(self.state(), CommunicatorState::SignedIn { .. })
+    }
+
+    /// The current connection state.
+    ///
+    /// It becomes `Connected` again, as soon as the Coordinator answers with `NotSignedIn`.
+    pub fn state(&self) -> CommunicatorState {
+        self.connection.lock().unwrap().state.clone()
     }
 
-    pub fn send_message(&self, message: Message) {
-        let _ = self.socket.send_multipart(message.frames, 0);
+    fn set_state(&self, state: CommunicatorState) {
+        self.connection.lock().unwrap().state = state;
+    }
+
+    /// The endpoint of the Coordinator, the socket is connected to.
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// Connect to a (possibly different) Coordinator and sign in again.
+    ///
+    /// `host` and `port` have the same defaults as in [`Communicator::build`].
+    pub fn reconnect(
+        &mut self,
+        host: Option<&str>,
+        port: Option<u16>,
+    ) -> Result<(), CommunicatorError> {
+        self.reconnect_to(make_endpoint(host, port)?)
+    }
+
+    /// Connect to the Coordinator at `endpoint` and sign in again.
+    pub fn reconnect_to(&mut self, endpoint: Endpoint) -> Result<(), CommunicatorError> {
+        let mut connection = self.connection.lock().unwrap();
+        let _ = connection.socket.disconnect(&self.endpoint.to_string());
+        connection.state = CommunicatorState::Disconnected;
+        let address = endpoint.to_string();
+        if let Err(error) = connection.socket.connect(&address) {
+            return Err(CommunicatorError::Connect {
+                endpoint: address,
+                error,
+            });
+        }
+        connection.state = CommunicatorState::Connected;
+        drop(connection);
+        self.endpoint = endpoint;
+        // The new Coordinator may have a different namespace.
+        self.full_name = self.name.clone();
+        self.sign_in()
+    }
+
+    /// Send a pre-built `message` as it is.
+    pub fn send_message(&self, message: Message) -> Result<(), CommunicatorError> {
+        self.connection.lock().unwrap().send(message)
+    }
+
+    /// Send `content` of `message_type` (see [`message_types`]) to `receiver`.
+    ///
+    /// Returns the conversation id of the message.
+    pub fn send_message_to(
+        &self,
+        receiver: &str,
+        content: ContentTypes,
+        message_type: u8,
+    ) -> Result<Vec<u8>, CommunicatorError> {
+        let message = Message::build(
+            self.complete_receiver(receiver.as_bytes().to_vec()),
+            self.full_name.to_vec(),
+            None,
+            None,
+            message_type,
+            content,
+        );
+        let cid = message.header().conversation_id.to_vec();
+        self.send_message(message)?;
+        Ok(cid)
     }
 
     /// Poll whether a new message arrived
-    pub fn poll(&self, timeout_ms: i64) -> bool {
-        self.socket.poll(zmq::POLLIN, timeout_ms).unwrap() == 1
+    pub fn poll(&self, timeout_ms: i64) -> Result<bool, CommunicatorError> {
+        self.connection.lock().unwrap().has_message(timeout_ms)
+    }
+
+    /// Send a `pong` request to ourselves via the Coordinator every `interval` from a background
+    /// thread.
+    ///
+    /// This keeps the Communicator signed in, even if it is idle for longer than the Coordinator's
+    /// expiration time, and tells, when the Coordinator forgot us. The thread answers pings as
+    /// well, other messages are kept for the next read. Start the heartbeat after signing in, as
+    /// it uses the current full name; it is restarted with the new full name, whenever signing in
+    /// succeeds again.
+    pub fn start_heartbeat(&mut self, interval: Duration) {
+        self.halt_heartbeat();
+        self.heartbeat_interval = Some(interval);
+        self.heartbeat = Some(Heartbeat::start(
+            Arc::clone(&self.connection),
+            self.full_name.clone(),
+            self.heartbeat_id,
+            interval,
+        ));
+    }
+
+    /// Get a clonable handle, which sends requests over this Communicator's connection.
+    ///
+    /// The handles may be used from other threads, responses are matched by conversation id:
+    /// Whoever reads the socket, be it a handle, the Communicator itself, or its heartbeat,
+    /// hands a response to the handle waiting for it. Get the handle after signing in, as it uses
+    /// the current full name.
+    pub fn handle(&self) -> CommunicatorHandle {
+        CommunicatorHandle {
+            full_name: self.full_name.clone(),
+            namespace: self.namespace().unwrap_or_default().to_string(),
+            complete_namespace: self.complete_namespace,
+            connection: Arc::clone(&self.connection),
+            request_id: Arc::clone(&self.request_id),
+        }
+    }
+
+    /// Stop the heartbeat thread, if running, and do not restart it after signing in again.
+    pub fn stop_heartbeat(&mut self) {
+        self.heartbeat_interval = None;
+        self.halt_heartbeat();
+    }
+
+    /// Stop the heartbeat thread until the next sign-in.
+    fn halt_heartbeat(&mut self) {
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.stop();
+        }
+    }
+
+    /// Read the next message, waiting at most the configured timeout.
+    ///
+    /// Incoming requests answered by [`Communicator::handle_incoming_request`] are skipped.
+    pub fn read_message(&self) -> Result<Message, CommunicatorError> {
+        self.receive(Instant::now() + self.timeout)
+    }
+
+    /// Read a message, if one is available, without waiting.
+    ///
+    /// Like [`Communicator::read_message`], incoming requests answered by
+    /// [`Communicator::handle_incoming_request`] are skipped.
+    pub fn try_read_message(&self) -> Result<Option<Message>, CommunicatorError> {
+        match self.receive(Instant::now()) {
+            Ok(message) => Ok(Some(message)),
+            Err(CommunicatorError::Timeout) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// The file descriptor of the socket, to register it with an external event loop.
+    ///
+    /// The file descriptor is edge-triggered and signals only, that the socket's state changed:
+    /// Whenever it becomes readable, call [`Communicator::try_read_message`] until it returns
+    /// `Ok(None)`, otherwise further messages will not wake up the event loop.
+    #[cfg(unix)]
+    pub fn raw_fd(&self) -> Result<RawFd, CommunicatorError> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .socket
+            .get_fd()
+            .map_err(CommunicatorError::Transport)
     }
-    pub fn read_message(&self) -> Message {
-        let frames = self.socket.recv_multipart(0).unwrap();
-        Message::new(frames).unwrap()
+
+    /// Read the next message not handled by [`Communicator::handle_incoming_request`].
+    fn receive(&self, deadline: Instant) -> Result<Message, CommunicatorError> {
+        loop {
+            let next = self.connection.lock().unwrap().next_message(deadline)?;
+            let message = next.ok_or(CommunicatorError::Timeout)?;
+            let cid = message.header().conversation_id;
+            if cid == self.heartbeat_id && is_not_signed_in(&message) {
+                self.set_state(CommunicatorState::Connected);
+            }
+            let skipped = cid == self.heartbeat_id || self.stale.iter().any(|stale| stale == cid);
+            if !skipped && !self.handle_incoming_request(&message) {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Answer `message`, if it is a ping or a request accepted by the request handler.
+    ///
+    /// Returns whether the message has been answered.
+    pub fn handle_incoming_request(&self, message: &Message) -> bool {
+        let Ok(request) =
+            serde_json::from_slice::<Request>(message.content_frame().unwrap_or(&vec![]))
+        else {
+            return false;
+        };
+        if let Some(response) = answer_ping(message, &self.full_name) {
+            let _ = self.send_message(response);
+            return true;
+        }
+        let result = match self
+            .request_handler
+            .as_ref()
+            .and_then(|handler| handler(&request))
+        {
+            Some(result) => result,
+            None => return false,
+        };
+        let receiver = message.sender_frame().to_vec();
+        let conversation_id = Some(message.header().conversation_id);
+        let response = match result {
+            Ok(value) => Message::build_response(
+                receiver,
+                self.full_name.to_vec(),
+                conversation_id,
+                request.id,
+                value,
+            ),
+            Err(error) => Message::build_error_response(
+                receiver,
+                self.full_name.to_vec(),
+                conversation_id,
+                request.id,
+                &error,
+            ),
+        };
+        let _ = self.send_message(response);
+        true
+    }
+
+    /// Send a JSON-RPC request.
+    ///
+    /// Returns the conversation id and the request id of the request.
+    pub fn send_rpc_message<T: ToString>(
+        &self,
+        receiver: String,
+        method: T,
+    ) -> Result<(Vec<u8>, u16), CommunicatorError> {
+        let (request, cid, id) = self.build_request(receiver.into_bytes(), method, None);
+        self.send_message(request)?;
+        Ok((cid, id))
+    }
+
+    /// Build a request with the next request id as JSON-RPC id and header message id.
+    fn build_request<T: ToString>(
+        &self,
+        receiver: Vec<u8>,
+        method: T,
+        params: Option<Value>,
+    ) -> (Message, Vec<u8>, u16) {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let (request, cid) = self.build_request_with_id(receiver, id, method, params);
+        (request, cid, id)
+    }
+
+    /// Build a request with the JSON-RPC and header message id `id`.
+    fn build_request_with_id<T: ToString>(
+        &self,
+        receiver: Vec<u8>,
+        id: u16,
+        method: T,
+        params: Option<Value>,
+    ) -> (Message, Vec<u8>) {
+        let receiver = self.complete_receiver(receiver);
+        let (request, cid) =
+            Message::build_request(receiver, self.full_name.to_vec(), id, method, params);
+        (request.with_message_id(id.into()), cid.to_vec())
     }
 
-    pub fn send_rpc_message<T: ToString>(&self, receiver: String, method: T) -> Vec<u8> {
-        let request_content = Request::build(0, method);
+    /// Prepend the own namespace to `receiver`, if enabled and the receiver has no namespace.
+    fn complete_receiver(&self, receiver: Vec<u8>) -> Vec<u8> {
+        complete_receiver(
+            self.complete_namespace,
+            self.namespace().unwrap_or_default(),
+            receiver,
+        )
+    }
+
+    /// Send a JSON-RPC request with `params`.
+    ///
+    /// Returns the conversation id and the JSON-RPC id of the request, such that the response can be
+    /// correlated.
+    pub fn send_rpc_message_with_params<T: ToString, P: Serialize>(
+        &self,
+        receiver: String,
+        method: T,
+        params: Option<P>,
+    ) -> Result<(Vec<u8>, u16), CommunicatorError> {
+        let params = params.map(serde_json::to_value).transpose()?;
+        let (request, cid, id) = self.build_request(receiver.into_bytes(), method, params);
+        self.send_message(request)?;
+        Ok((cid, id))
+    }
+
+    /// Send a request and wait for the response with the same conversation id.
+    ///
+    /// A result response has to carry the request id of the request.
+    /// Messages with other conversation ids, which arrive in the meantime, are buffered and can be
+    /// retrieved with [`Communicator::read_buffered`].
+    ///
+    /// If the Coordinator does not know this Communicator anymore (e.g. after a restart), it signs
+    /// in again and repeats the request once, see [`Communicator::with_auto_sign_in`].
+    pub fn ask(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<Value>,
+        timeout: Duration,
+    ) -> Result<Value, CommunicatorError> {
+        let options = AskOptions {
+            timeout: Some(timeout),
+            ..Default::default()
+        };
+        self.ask_with(receiver, method, params, options)
+    }
+
+    /// Like [`Communicator::ask`], but with `options` overriding the Communicator's settings.
+    pub fn ask_with(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<Value>,
+        options: AskOptions,
+    ) -> Result<Value, CommunicatorError> {
+        let timeout = options.timeout.unwrap_or(self.timeout);
+        let retry_policy = options.retry_policy.or(self.retry_policy);
+        let auto_sign_in = options.auto_sign_in.unwrap_or(self.auto_sign_in);
+        // Only sign in again, if we have been signed in before.
+        let was_signed_in = self.is_signed_in();
+        match self.ask_once(receiver, method, params.clone(), timeout, retry_policy) {
+            Err(CommunicatorError::Rpc(Error::NotSignedIn)) => {
+                self.full_name = self.name.clone();
+                self.set_state(CommunicatorState::Connected);
+                if !(auto_sign_in && was_signed_in) {
+                    return Err(CommunicatorError::Rpc(Error::NotSignedIn));
+                }
+                self.sign_in()?;
+                self.ask_once(receiver, method, params, timeout, retry_policy)
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request (with retries according to `retry_policy`) and read the response.
+    fn ask_once(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<Value>,
+        timeout: Duration,
+        retry_policy: Option<RetryPolicy>,
+    ) -> Result<Value, CommunicatorError> {
+        let Some(policy) = retry_policy else {
+            let (request, cid, id) =
+                self.build_request(receiver.as_bytes().to_vec(), method, params);
+            self.send_message(request)?;
+            let response = self.read_response(&cid, timeout)?;
+            return Self::parse_response(&response, id);
+        };
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let mut cids = Vec::new();
+        for attempt in 0..policy.attempts.max(1) {
+            if attempt > 0 {
+                std::thread::sleep(policy.backoff);
+            }
+            let (request, cid) = self.build_request_with_id(
+                receiver.as_bytes().to_vec(),
+                id,
+                method,
+                params.clone(),
+            );
+            self.send_message(request)?;
+            cids.push(cid);
+            let conversation_ids: Vec<&[u8]> = cids.iter().map(Vec::as_slice).collect();
+            match self.read_response_to_any(&conversation_ids, Instant::now() + timeout) {
+                Ok(response) => {
+                    let answered = response.header().conversation_id;
+                    for cid in cids.iter().filter(|cid| cid.as_slice() != answered) {
+                        self.mark_stale(cid.clone());
+                    }
+                    return Self::parse_response(&response, id);
+                }
+                Err(CommunicatorError::Timeout) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        for cid in cids.iter() {
+            self.mark_stale(cid.clone());
+        }
+        Err(CommunicatorError::RetriesExhausted {
+            attempts: policy.attempts.max(1),
+        })
+    }
+
+    /// Check whether the Coordinator is reachable and ask for its name, namespace, and version.
+    ///
+    /// Works before signing in as well. Each request waits at most `timeout`. The name,
+    /// namespace, and version are `None`, if the Coordinator does not offer `coordinator_info`.
+    pub fn probe(&mut self, timeout: Duration) -> Result<CoordinatorInfo, CommunicatorError> {
+        let start = Instant::now();
+        match self.ask_once("COORDINATOR", "pong", None, timeout, None) {
+            // The Coordinator answered, even if it does not know us.
+            Ok(_) | Err(CommunicatorError::Rpc(Error::NotSignedIn)) => {}
+            Err(err) => return Err(err),
+        }
+        let round_trip = start.elapsed();
+        let info = match self.ask_once("COORDINATOR", "coordinator_info", None, timeout, None) {
+            Ok(info) => info,
+            Err(CommunicatorError::Rpc(_) | CommunicatorError::RpcError { .. }) => Value::Null,
+            Err(err) => return Err(err),
+        };
+        let field = |key: &str| info.get(key).and_then(Value::as_str).map(str::to_string);
+        Ok(CoordinatorInfo {
+            round_trip,
+            name: field("name"),
+            namespace: field("namespace"),
+            version: field("version"),
+        })
+    }
+
+    /// Discard future responses in the conversation `cid`.
+    fn mark_stale(&mut self, cid: Vec<u8>) {
+        if self.stale.len() >= MAX_STALE {
+            self.stale.pop_front();
+        }
+        self.stale.push_back(cid);
+    }
+
+    /// Get the result of the `response` to the request `id`.
+    fn parse_response(response: &Message, id: u16) -> Result<Value, CommunicatorError> {
+        let content = response
+            .content_frame()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match serde_json::from_slice::<ResponseContent>(content)? {
+            ResponseContent::Result(response) if response.id != id => {
+                Err(CommunicatorError::IdMismatch {
+                    expected: id,
+                    received: response.id,
+                })
+            }
+            ResponseContent::Result(response) => Ok(response.result),
+            ResponseContent::Error(error_response) => Err(error_response.error.into()),
+        }
+    }
+
+    /// Call `method` of `receiver` and deserialize the result.
+    ///
+    /// Use `T = ()` for methods without return value.
+    pub fn call_method<T: DeserializeOwned>(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<impl Serialize>,
+        timeout: Duration,
+    ) -> Result<T, CommunicatorError> {
+        let params = params.map(serde_json::to_value).transpose()?;
+        let result = self.ask(receiver, method, params, timeout)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Call `method` of `receiver` and deserialize the result, keeping the context in errors.
+    ///
+    /// Waits at most the Communicator's timeout.
+    pub fn ask_json<T: DeserializeOwned>(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<impl Serialize>,
+    ) -> Result<T, RpcCallError> {
+        let error = |conversation_id: &[u8], kind| RpcCallError {
+            receiver: receiver.to_string(),
+            method: method.to_string(),
+            conversation_id: conversation_id.to_vec(),
+            kind: Box::new(kind),
+        };
+        let params = match params.map(serde_json::to_value).transpose() {
+            Ok(params) => params,
+            Err(err) => return Err(error(&[], RpcCallErrorKind::Communication(err.into()))),
+        };
+        let (request, cid, id) = self.build_request(receiver.as_bytes().to_vec(), method, params);
+        let result = self
+            .send_message(request)
+            .and_then(|()| self.read_response(&cid, self.timeout))
+            .and_then(|response| Ok((Self::parse_response(&response, id)?, response)));
+        let (result, response) =
+            result.map_err(|err| error(&cid, RpcCallErrorKind::Communication(err)))?;
+        serde_json::from_value(result).map_err(|err| {
+            let mut payload = response.content_frame().cloned().unwrap_or_default();
+            payload.truncate(MAX_PAYLOAD_CONTEXT);
+            error(
+                &cid,
+                RpcCallErrorKind::Deserialize {
+                    error: err,
+                    payload,
+                },
+            )
+        })
+    }
+
+    /// Get the values of `properties` of a pyleco component via `get_parameters`.
+    pub fn get_properties(
+        &mut self,
+        receiver: &str,
+        properties: &[&str],
+    ) -> Result<HashMap<String, Value>, CommunicatorError> {
+        let params = json!({ "parameters": properties });
+        self.call_method(receiver, "get_parameters", Some(params), self.timeout)
+    }
+
+    /// Set `properties` of a pyleco component via `set_parameters`.
+    pub fn set_properties(
+        &mut self,
+        receiver: &str,
+        properties: HashMap<String, Value>,
+    ) -> Result<(), CommunicatorError> {
+        let params = json!({ "parameters": properties });
+        self.call_method(receiver, "set_parameters", Some(params), self.timeout)
+    }
+
+    /// Call `action` of a pyleco component with positional `args` and keyword `kwargs`.
+    ///
+    /// Empty `args` and `kwargs` are omitted, as pyleco does.
+    pub fn call_action(
+        &mut self,
+        receiver: &str,
+        action: &str,
+        args: &[Value],
+        kwargs: HashMap<String, Value>,
+    ) -> Result<Value, CommunicatorError> {
+        let mut params = Map::new();
+        params.insert("action".to_string(), Value::from(action));
+        if !args.is_empty() {
+            params.insert("args".to_string(), Value::from(args));
+        }
+        if !kwargs.is_empty() {
+            params.insert("kwargs".to_string(), json!(kwargs));
+        }
+        self.call_method(receiver, "call_action", Some(params), self.timeout)
+    }
+
+    /// Call several methods of `receiver` with a single JSON-RPC batch message.
+    ///
+    /// Returns the results in the order of `calls`, each either the result or the error of
+    /// that call. Entries missing in the response yield `CommunicatorError::Rpc(InternalError)`.
+    /// If the receiver rejects the batch as a whole, that error is returned instead.
+    pub fn ask_batch(
+        &mut self,
+        receiver: &str,
+        calls: Vec<(&str, Option<Value>)>,
+    ) -> Result<Vec<Result<Value, CommunicatorError>>, CommunicatorError> {
+        let requests: Vec<Request> = calls
+            .into_iter()
+            .map(|(method, params)| {
+                let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+                Request::build(id, method).with_params(params)
+            })
+            .collect();
         let request = Message::build(
-            receiver.into_bytes(),
-            self.name.to_vec(),
+            self.complete_receiver(receiver.as_bytes().to_vec()),
+            self.full_name.to_vec(),
             None,
             None,
-            1,
-            crate::core::ContentTypes::Frame(to_vec(&request_content)),
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&requests)),
         );
         let cid = request.header().conversation_id.to_vec();
-        self.send_message(request);
-        cid
+        self.send_message(request)?;
+        let response = self.read_response(&cid, self.timeout)?;
+        let content = response
+            .content_frame()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let responses = match serde_json::from_slice::<Vec<ResponseContent>>(content) {
+            Ok(responses) => responses,
+            Err(error) => match serde_json::from_slice::<ResponseContent>(content) {
+                Ok(ResponseContent::Error(error_response)) => {
+                    return Err(error_response.error.into())
+                }
+                _ => return Err(error.into()),
+            },
+        };
+        // Errors without id cannot be assigned to a request.
+        let mut results: HashMap<u16, Result<Value, CommunicatorError>> = responses
+            .into_iter()
+            .filter_map(|response| match response {
+                ResponseContent::Result(response) => Some((response.id, Ok(response.result))),
+                ResponseContent::Error(response) => {
+                    Some((response.id?, Err(response.error.into())))
+                }
+            })
+            .collect();
+        Ok(requests
+            .iter()
+            .map(|request| {
+                results
+                    .remove(&request.id)
+                    .unwrap_or(Err(CommunicatorError::Rpc(Error::InternalError)))
+            })
+            .collect())
     }
 
-    pub fn read_rpc_message(&self) -> Result<serde_json::Value, Error> {
-        let response = self.read_message();
-        match serde_json::from_slice::<Response>(response.content_frame().unwrap_or(&vec![])) {
-            Ok(response) => Ok(response.result),
-            Err(err) => Err(err),
+    /// Read messages until one with `conversation_id` arrives, buffering all others.
+    fn read_response(
+        &mut self,
+        conversation_id: &[u8],
+        timeout: Duration,
+    ) -> Result<Message, CommunicatorError> {
+        self.read_response_to_any(&[conversation_id], Instant::now() + timeout)
+    }
+
+    /// Read messages until one with any of `conversation_ids` arrives, buffering all others.
+    fn read_response_to_any(
+        &mut self,
+        conversation_ids: &[&[u8]],
+        deadline: Instant,
+    ) -> Result<Message, CommunicatorError> {
+        loop {
+            let message = self.receive(deadline)?;
+            if conversation_ids.contains(&message.header().conversation_id) {
+                return Ok(message);
+            }
+            self.buffer_message(message);
+        }
+    }
+
+    /// Get the oldest message, which arrived while waiting for a response to another request.
+    pub fn read_buffered(&mut self) -> Option<Message> {
+        self.buffer.pop_front()
+    }
+
+    /// Whether messages are waiting in the buffer.
+    pub fn has_buffered(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// Wait up to `timeout` for a request of another Component.
+    ///
+    /// Buffered requests are returned first. Other messages arriving meanwhile are buffered.
+    /// Answer the request with [`IncomingRequest::respond`] or [`IncomingRequest::respond_error`].
+    pub fn next_request(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<IncomingRequest, CommunicatorError> {
+        let buffered = self
+            .buffer
+            .iter()
+            .position(|message| parse_request(message).is_some());
+        if let Some(message) = buffered.and_then(|index| self.buffer.remove(index)) {
+            return Ok(self.incoming_request(message));
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let message = self.receive(deadline)?;
+            if parse_request(&message).is_some() {
+                return Ok(self.incoming_request(message));
+            }
+            self.buffer_message(message);
         }
     }
 
-    pub fn sign_in(&mut self) {
-        self.send_rpc_message("COORDINATOR".to_string(), "sign_in");
-        let response = self.read_message();
-        match serde_json::from_slice::<Response>(response.content_frame().unwrap_or(&vec![])) {
-            Ok(_response) => self.finish_sign_in(response.sender()),
-            Err(_err) => (),
+    /// Wrap a request `message` for answering it via this Communicator's connection.
+    fn incoming_request(&self, message: Message) -> IncomingRequest {
+        IncomingRequest {
+            request: parse_request(&message).expect("Checked to be a request."),
+            message,
+            full_name: self.full_name.clone(),
+            connection: Arc::clone(&self.connection),
         }
     }
+
+    /// Keep `message` for [`Communicator::read_buffered`], dropping the oldest one if full.
+    fn buffer_message(&mut self, message: Message) {
+        push_bounded(&mut self.buffer, message);
+    }
+
+    /// Send a request with additional binary frames and wait for the response.
+    ///
+    /// Returns the result of the response together with its binary frames. Like in
+    /// [`Communicator::ask`], the response is matched by conversation id.
+    pub fn ask_with_binary(
+        &mut self,
+        receiver: &str,
+        method: &str,
+        params: Option<Value>,
+        blobs: Vec<Vec<u8>>,
+    ) -> Result<(Value, Vec<Vec<u8>>), CommunicatorError> {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let request_content = Request::build(id, method).with_params(params);
+        let request = Message::build_with_binary(
+            self.complete_receiver(receiver.as_bytes().to_vec()),
+            self.full_name.to_vec(),
+            &request_content,
+            blobs,
+        )
+        .with_message_id(id.into());
+        let cid = request.header().conversation_id.to_vec();
+        self.send_message(request)?;
+        let response = self.read_response(&cid, self.timeout)?;
+        let result = Self::parse_response(&response, id)?;
+        Ok((result, response.into_frames().split_off(5)))
+    }
+
+    /// Read a message and return the result of the JSON-RPC response.
+    ///
+    /// An error response is returned as `CommunicatorError::Rpc`, such that you can match on it:
+    /// ```no_run
+    /// # use ruleco::control_protocol::{communicator::{Communicator, CommunicatorError}, Error};
+    /// # let mut comm = Communicator::build("comm", None, None, None).unwrap();
+    /// if let Err(CommunicatorError::Rpc(Error::NotSignedIn)) = comm.read_rpc_message() {
+    ///     comm.sign_in().unwrap();
+    /// }
+    /// ```
+    pub fn read_rpc_message(&self) -> Result<serde_json::Value, CommunicatorError> {
+        let response = self.read_message()?;
+        Self::parse_rpc_content(response.content_frame().unwrap_or(&vec![]))
+    }
+
+    fn parse_rpc_content(content: &[u8]) -> Result<serde_json::Value, CommunicatorError> {
+        match serde_json::from_slice::<ResponseContent>(content)? {
+            ResponseContent::Result(response) => Ok(response.result),
+            ResponseContent::Error(error_response) => Err(error_response.error.into()),
+        }
+    }
+
+    /// Sign in to the Coordinator.
+    ///
+    /// Fails with `CommunicatorError::Rpc(Error::DuplicateName)`, if the name is already taken.
+    /// With [`Communicator::with_rename_attempts`], a suffixed name is tried instead, which
+    /// replaces the name on success.
+    pub fn sign_in(&mut self) -> Result<(), CommunicatorError> {
+        let original_name = self.name.clone();
+        let mut attempt = 0;
+        loop {
+            match self.try_sign_in() {
+                Err(CommunicatorError::Rpc(Error::DuplicateName))
+                    if attempt < self.rename_attempts =>
+                {
+                    attempt += 1;
+                    let mut name = original_name.clone();
+                    name.extend((attempt + 1).to_string().as_bytes());
+                    self.full_name = name.clone();
+                    self.name = name;
+                }
+                Err(err) => {
+                    self.name = original_name.clone();
+                    self.full_name = original_name;
+                    return Err(err);
+                }
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+
+    fn try_sign_in(&mut self) -> Result<(), CommunicatorError> {
+        let (cid, _id) = self.send_rpc_message("COORDINATOR".to_string(), "sign_in")?;
+        let response = self.read_response(&cid, self.timeout)?;
+        self.handle_sign_in_response(response)
+    }
+
+    /// Sign in, repeating the request every `interval`, until the Coordinator answers.
+    ///
+    /// Useful, if the Coordinator might not be running yet. A response to any of the attempts is
+    /// accepted. Fails with `CommunicatorError::Timeout` after `attempts` unanswered requests.
+    pub fn sign_in_with_retries(
+        &mut self,
+        attempts: u32,
+        interval: Duration,
+    ) -> Result<(), CommunicatorError> {
+        let mut conversation_ids = Vec::new();
+        for _ in 0..attempts {
+            let (cid, _id) = self.send_rpc_message("COORDINATOR".to_string(), "sign_in")?;
+            conversation_ids.push(cid);
+            let cids: Vec<&[u8]> = conversation_ids.iter().map(Vec::as_slice).collect();
+            match self.read_response_to_any(&cids, Instant::now() + interval) {
+                Ok(response) => return self.handle_sign_in_response(response),
+                Err(CommunicatorError::Timeout) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(CommunicatorError::Timeout)
+    }
+
+    fn handle_sign_in_response(&mut self, response: Message) -> Result<(), CommunicatorError> {
+        // Only a successful sign-in gives us a namespace.
+        Self::parse_rpc_content(response.content_frame().unwrap_or(&vec![]))?;
+        if let Ok(coordinator_name) = response.sender() {
+            self.finish_sign_in(coordinator_name)
+        }
+        Ok(())
+    }
     fn finish_sign_in(&mut self, coordinator_name: FullName) {
-        let mut full_name: Vec<u8> = coordinator_name.namespace.to_vec();
-        full_name.push(46);
-        full_name.extend(&self.name);
-        self.full_name = full_name
+        self.full_name = compose_full_name(coordinator_name.namespace, &self.name);
+        let namespace = String::from_utf8_lossy(coordinator_name.namespace).into_owned();
+        self.set_state(CommunicatorState::SignedIn { namespace });
+        if let Some(interval) = self.heartbeat_interval {
+            self.start_heartbeat(interval);
+        }
+    }
+
+    pub fn sign_out(&mut self) -> Result<(), CommunicatorError> {
+        self.halt_heartbeat();
+        let (cid, _id) = self.send_rpc_message("COORDINATOR".to_string(), "sign_out")?;
+        let _response = self.read_response(&cid, self.timeout)?;
+        self.full_name = self.name.clone();
+        self.set_state(CommunicatorState::Connected);
+        Ok(())
+    }
+
+    /// Sign out (if signed in) and close the connection.
+    pub fn close(mut self) -> Result<(), CommunicatorError> {
+        let result = match self.is_signed_in() {
+            true => self.sign_out(),
+            false => Ok(()),
+        };
+        // Do not try again in `drop`.
+        self.full_name = self.name.clone();
+        self.set_state(CommunicatorState::Connected);
+        result
+    }
+
+    pub fn ping(&self, receiver: String) -> Result<(), CommunicatorError> {
+        self.send_rpc_message(receiver, "pong").map(|_| ())
+    }
+}
+
+impl Drop for Communicator {
+    /// Sign out on a best-effort basis, such that the Coordinator forgets us immediately.
+    fn drop(&mut self) {
+        self.halt_heartbeat();
+        // A thread panicking with the lock poisons it, skip signing out then instead of panicking.
+        let signed_in = self
+            .connection
+            .lock()
+            .map(|connection| matches!(connection.state, CommunicatorState::SignedIn { .. }));
+        if signed_in.unwrap_or(false) {
+            self.timeout = self.timeout.min(DROP_TIMEOUT);
+            let _ = self.sign_out();
+        }
+        if let Ok(connection) = self.connection.lock() {
+            let _ = connection.socket.set_linger(0);
+            let _ = connection.socket.disconnect(&self.endpoint.to_string());
+        }
+    }
+}
+
+/// A clonable handle to send requests via a [`Communicator`] from several threads.
+///
+/// Created by [`Communicator::handle`].
+#[derive(Clone)]
+pub struct CommunicatorHandle {
+    full_name: Vec<u8>,
+    /// Namespace to complete receiver names with
+    namespace: String,
+    /// Prepend the namespace to receiver names without namespace
+    complete_namespace: bool,
+    connection: Arc<Mutex<Connection>>,
+    request_id: Arc<AtomicU16>,
+}
+
+impl CommunicatorHandle {
+    /// How long a waiting handle leaves reading to others, before it reads arrived messages
+    const READ_INTERVAL: Duration = Duration::from_millis(5);
+
+    /// Send a JSON-RPC request.
+    ///
+    /// Returns the conversation id and the JSON-RPC id of the request.
+    pub fn send_rpc_message<T: ToString>(
+        &self,
+        receiver: String,
+        method: T,
+    ) -> Result<(Vec<u8>, u16), CommunicatorError> {
+        let (request, cid, id) = self.build_request(receiver.into_bytes(), method, None);
+        self.connection.lock().unwrap().send(request)?;
+        Ok((cid, id))
     }
 
-    pub fn sign_out(&mut self) {
-        self.send_rpc_message("COORDINATOR".to_string(), "sign_out");
-        let _response = self.read_message();
-        self.full_name = self.name.clone()
+    /// Send a request and wait for the response with the same conversation id.
+    ///
+    /// While waiting, pings are answered and other handles get their responses. Other messages
+    /// are left for the Communicator.
+    pub fn ask(
+        &self,
+        receiver: &str,
+        method: &str,
+        params: Option<Value>,
+        timeout: Duration,
+    ) -> Result<Value, CommunicatorError> {
+        let deadline = Instant::now() + timeout;
+        let (request, cid, id) = self.build_request(receiver.as_bytes().to_vec(), method, params);
+        let (waiting, responses) = mpsc::channel();
+        {
+            let mut connection = self.connection.lock().unwrap();
+            connection.waiting.insert(cid.clone(), waiting);
+            if let Err(err) = connection.send(request) {
+                connection.waiting.remove(&cid);
+                return Err(err);
+            }
+        }
+        loop {
+            // Read ourselves only, if nobody else holds the connection, e.g. for reading.
+            if let Ok(mut connection) = self.connection.try_lock() {
+                while let Some(message) = connection.read_arrived() {
+                    connection.deliver(message, &self.full_name);
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match responses.recv_timeout(remaining.min(Self::READ_INTERVAL)) {
+                Ok(response) => return Communicator::parse_response(&response, id),
+                Err(RecvTimeoutError::Timeout) if !remaining.is_zero() => continue,
+                Err(_) => break,
+            }
+        }
+        self.connection.lock().unwrap().waiting.remove(&cid);
+        // The response might have been handed over just before.
+        match responses.try_recv() {
+            Ok(response) => Communicator::parse_response(&response, id),
+            Err(_) => Err(CommunicatorError::Timeout),
+        }
+    }
+
+    fn build_request<T: ToString>(
+        &self,
+        receiver: Vec<u8>,
+        method: T,
+        params: Option<Value>,
+    ) -> (Message, Vec<u8>, u16) {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let receiver = complete_receiver(self.complete_namespace, &self.namespace, receiver);
+        let (request, cid) =
+            Message::build_request(receiver, self.full_name.clone(), id, method, params);
+        (request.with_message_id(id.into()), cid.to_vec(), id)
+    }
+}
+
+/// A request of another Component, received by [`Communicator::next_request`]
+pub struct IncomingRequest {
+    request: Request,
+    message: Message,
+    /// Own full name at the time of receiving, used as sender of the response
+    full_name: Vec<u8>,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl IncomingRequest {
+    /// The parsed JSON-RPC request.
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    pub fn method(&self) -> &str {
+        &self.request.method
+    }
+
+    pub fn params(&self) -> Option<&Value> {
+        self.request.params.as_ref()
+    }
+
+    /// Full name of the requesting Component.
+    pub fn sender(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.message.sender_frame())
+    }
+
+    /// The whole request message, e.g. for additional binary frames.
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Answer the request with `result`.
+    pub fn respond(self, result: impl Serialize) -> Result<(), CommunicatorError> {
+        let response = Message::build_response(
+            self.message.sender_frame().to_vec(),
+            self.full_name,
+            Some(self.message.header().conversation_id),
+            self.request.id,
+            result,
+        );
+        self.connection.lock().unwrap().send(response)
+    }
+
+    /// Answer the request with `error`.
+    pub fn respond_error(self, error: &Error) -> Result<(), CommunicatorError> {
+        let response = Message::build_error_response(
+            self.message.sender_frame().to_vec(),
+            self.full_name,
+            Some(self.message.header().conversation_id),
+            self.request.id,
+            error,
+        );
+        self.connection.lock().unwrap().send(response)
+    }
+}
+
+/// Append `message` to `queue`, dropping the oldest message, if [`MAX_BUFFERED`] are queued.
+fn push_bounded(queue: &mut VecDeque<Message>, message: Message) {
+    if queue.len() >= MAX_BUFFERED {
+        if let Some(dropped) = queue.pop_front() {
+            log::warn!(
+                "Buffer full, dropping message from {:?}.",
+                String::from_utf8_lossy(dropped.sender_frame())
+            );
+        }
+    }
+    queue.push_back(message);
+}
+
+/// The JSON-RPC request in `message`, if it contains one.
+fn parse_request(message: &Message) -> Option<Request> {
+    serde_json::from_slice(message.content_frame()?).ok()
+}
+
+/// Prepend `namespace` to `receiver`, if `enabled` and the receiver has neither a namespace nor is
+/// the Coordinator.
+fn complete_receiver(enabled: bool, namespace: &str, receiver: Vec<u8>) -> Vec<u8> {
+    if !enabled || receiver.contains(&b'.') || receiver == b"COORDINATOR" {
+        return receiver;
+    }
+    compose_full_name(namespace.as_bytes(), &receiver)
+}
+
+/// Whether `message` is an error response with the `NotSignedIn` error.
+fn is_not_signed_in(message: &Message) -> bool {
+    let content = message
+        .content_frame()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    matches!(
+        serde_json::from_slice::<ResponseContent>(content),
+        Ok(ResponseContent::Error(response)) if response.error.code == Error::NotSignedIn.code()
+    )
+}
+
+/// Build the response to `message`, if it is a ping request.
+fn answer_ping(message: &Message, sender: &[u8]) -> Option<Message> {
+    let request: Request = serde_json::from_slice(message.content_frame()?).ok()?;
+    (request.method == "pong").then(|| {
+        Message::build_response(
+            message.sender_frame().to_vec(),
+            sender.to_vec(),
+            Some(message.header().conversation_id),
+            request.id,
+            Value::Null,
+        )
+    })
+}
+
+/// Create a tcp endpoint, defaulting to the local Coordinator.
+fn make_endpoint(host: Option<&str>, port: Option<u16>) -> Result<Endpoint, CommunicatorError> {
+    let host = host.unwrap_or("localhost");
+    Endpoint::tcp(host, port.unwrap_or(12300))
+        .map_err(|_| CommunicatorError::InvalidEndpoint(host.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{to_vec, ErrorResponse, Response};
+
+    /// Create a ROUTER socket acting as a fake Coordinator.
+    fn make_fake_coordinator(port: u16) -> zmq::Socket {
+        let ctx = zmq::Context::new();
+        let router = ctx.socket(zmq::ROUTER).unwrap();
+        router.bind(&format!("tcp://*:{port}")).unwrap();
+        router
+    }
+
+    /// Answer the next message arriving at the fake Coordinator with `content`.
+    fn answer(router: &zmq::Socket, content: Vec<u8>) {
+        let identity = router.recv_bytes(0).unwrap();
+        let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        let response = Message::build(
+            request.sender_frame().to_vec(),
+            b"N1.COORDINATOR".to_vec(),
+            Some(request.header().conversation_id),
+            None,
+            1,
+            crate::core::ContentTypes::Frame(content),
+        );
+        router.send(identity, zmq::SNDMORE).unwrap();
+        router.send_multipart(response.to_frames(), 0).unwrap();
+    }
+
+    #[test]
+    fn test_ask_skips_unrelated_message() {
+        let router = make_fake_coordinator(12354);
+        let mut comm = Communicator::build("comm", None, Some(12354), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let id = request.message_id();
+            let unrelated =
+                Message::build_response(b"comm".to_vec(), b"N1.other".to_vec(), None, 0, 3);
+            let response = Message::build_response(
+                b"comm".to_vec(),
+                b"N1.COORDINATOR".to_vec(),
+                Some(request.header().conversation_id),
+                id as u16,
+                5,
+            );
+            for message in [unrelated, response] {
+                router.send(&identity, zmq::SNDMORE).unwrap();
+                router.send_multipart(message.to_frames(), 0).unwrap();
+            }
+        });
+        let result = comm.ask("COORDINATOR", "pong", None, Duration::from_secs(1));
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), 5);
+        assert!(comm.has_buffered());
+        let buffered = comm.read_buffered().unwrap();
+        assert_eq!(buffered.sender_frame(), b"N1.other");
+        assert!(comm.read_buffered().is_none());
+        assert!(!comm.has_buffered());
+    }
+
+    #[test]
+    fn test_heartbeat_pending_drops_oldest() {
+        let router = make_fake_coordinator(12390);
+        let mut comm = Communicator::build("comm", None, Some(12390), None).unwrap();
+        // The heartbeat is sent only while signed in.
+        comm.set_state(CommunicatorState::SignedIn {
+            namespace: "N1".to_string(),
+        });
+        comm.start_heartbeat(Duration::from_millis(10));
+        // The first heartbeat tells the identity of the Communicator.
+        let identity = router.recv_bytes(0).unwrap();
+        router.recv_multipart(0).unwrap();
+        for i in 0..=MAX_BUFFERED {
+            let message = Message::build_response(
+                b"comm".to_vec(),
+                format!("N1.c{i}").into_bytes(),
+                None,
+                0,
+                (),
+            );
+            router.send(&identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(message.into_frames(), 0).unwrap();
+        }
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let pending = || comm.connection.lock().unwrap().pending.len();
+        while pending() < MAX_BUFFERED && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        comm.stop_heartbeat();
+        comm.set_state(CommunicatorState::Connected);
+        let connection = comm.connection.lock().unwrap();
+        assert_eq!(connection.pending.len(), MAX_BUFFERED);
+        assert_eq!(connection.pending[0].sender_frame(), b"N1.c1");
+    }
+
+    #[test]
+    fn test_heartbeat_restarts_after_sign_in() {
+        let router = make_fake_coordinator(12391);
+        router.set_rcvtimeo(1000).unwrap();
+        let mut comm = Communicator::build("comm", None, Some(12391), None).unwrap();
+        comm.finish_sign_in(FullName::from_slice(b"N1.COORDINATOR").unwrap());
+        comm.start_heartbeat(Duration::from_millis(10));
+        // Signing in to another Coordinator, e.g. after reconnecting
+        comm.finish_sign_in(FullName::from_slice(b"N2.COORDINATOR").unwrap());
+        loop {
+            router.recv_bytes(0).unwrap();
+            let heartbeat = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            if heartbeat.sender_frame() == b"N2.comm" {
+                break;
+            }
+        }
+        comm.stop_heartbeat();
+        assert_eq!(comm.heartbeat_interval, None);
+        comm.set_state(CommunicatorState::Connected);
+    }
+
+    #[test]
+    fn test_drop_with_poisoned_connection() {
+        let comm = Communicator::build("comm", None, Some(12399), None).unwrap();
+        comm.set_state(CommunicatorState::SignedIn {
+            namespace: "N1".to_string(),
+        });
+        let connection = Arc::clone(&comm.connection);
+        let poisoner = std::thread::spawn(move || {
+            let _connection = connection.lock().unwrap();
+            panic!("Poison the connection.");
+        });
+        assert!(poisoner.join().is_err());
+        drop(comm);
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest() {
+        let mut comm = Communicator::build("comm", None, Some(12399), None).unwrap();
+        for i in 0..=MAX_BUFFERED {
+            let sender = format!("N1.c{i}").into_bytes();
+            comm.buffer_message(Message::build_response(
+                b"comm".to_vec(),
+                sender,
+                None,
+                0,
+                (),
+            ));
+        }
+        assert!(comm.has_buffered());
+        assert_eq!(comm.read_buffered().unwrap().sender_frame(), b"N1.c1");
+        assert_eq!(comm.buffer.len(), MAX_BUFFERED - 1);
+    }
+
+    #[test]
+    fn test_ask_answers_ping() {
+        let router = make_fake_coordinator(12361);
+        let mut comm = Communicator::build("comm", None, Some(12361), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let (ping, ping_cid) = Message::build_request(
+                b"comm".to_vec(),
+                b"N1.COORDINATOR".to_vec(),
+                7,
+                "pong",
+                None,
+            );
+            router.send(&identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(ping.to_frames(), 0).unwrap();
+            // The ping is answered, before the original request gets its response.
+            let _identity = router.recv_bytes(0).unwrap();
+            let pong = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let response = Message::build_response(
+                b"comm".to_vec(),
+                b"N1.COORDINATOR".to_vec(),
+                Some(request.header().conversation_id),
+                request.message_id() as u16,
+                5,
+            );
+            router.send(&identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(response.to_frames(), 0).unwrap();
+            (pong, ping_cid)
+        });
+        let result = comm.ask("COORDINATOR", "other", None, Duration::from_secs(1));
+        let (pong, ping_cid) = handle.join().unwrap();
+        assert_eq!(result.unwrap(), 5);
+        assert!(comm.read_buffered().is_none());
+        assert_eq!(pong.header().conversation_id, ping_cid);
+        assert_eq!(pong.receiver_frame(), b"N1.COORDINATOR");
+        assert_eq!(
+            pong.content_frame().unwrap(),
+            &to_vec(&Response::build(7, ()))
+        );
+    }
+
+    #[test]
+    fn test_request_handler() {
+        let router = make_fake_coordinator(12362);
+        let mut comm = Communicator::build("comm", None, Some(12362), None).unwrap();
+        comm.set_request_handler(Box::new(|request| match request.method.as_str() {
+            "get_speed" => Some(Ok(Value::from(3))),
+            "home" => Some(Err(Error::InvalidParams)),
+            _ => None,
+        }));
+        comm.send_rpc_message("COORDINATOR".to_string(), "sign_in")
+            .unwrap();
+        let identity = router.recv_bytes(0).unwrap();
+        let _ = router.recv_multipart(0).unwrap();
+        for method in ["get_speed", "home", "unknown"] {
+            let (request, _) =
+                Message::build_request(b"comm".to_vec(), b"N1.other".to_vec(), 2, method, None);
+            router.send(&identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(request.to_frames(), 0).unwrap();
+        }
+        let unknown = comm.read_message().unwrap();
+        let content: Request = serde_json::from_slice(unknown.content_frame().unwrap()).unwrap();
+        assert_eq!(content.method, "unknown");
+        let mut answers = Vec::new();
+        for _ in 0..2 {
+            let _identity = router.recv_bytes(0).unwrap();
+            let answer = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            answers.push(answer.content_frame().unwrap().clone());
+        }
+        assert_eq!(answers[0], to_vec(&Response::build(2, 3)));
+        assert_eq!(
+            answers[1],
+            to_vec(&ErrorResponse::from_error(2, &Error::InvalidParams))
+        );
+    }
+
+    #[test]
+    fn test_ask_id_mismatch() {
+        let router = make_fake_coordinator(12363);
+        let mut comm = Communicator::build("comm", None, Some(12363), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            answer(&router, to_vec(&Response::build(99, 5)));
+        });
+        let result = comm.ask("COORDINATOR", "pong", None, Duration::from_secs(1));
+        handle.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::IdMismatch {
+                expected: 1,
+                received: 99
+            })
+        ));
+    }
+
+    #[test]
+    fn test_request_ids_increment() {
+        let router = make_fake_coordinator(12364);
+        let comm = Communicator::build("comm", None, Some(12364), None).unwrap();
+        assert_eq!(
+            comm.send_rpc_message("N1.a".to_string(), "pong").unwrap().1,
+            1
+        );
+        assert_eq!(
+            comm.send_rpc_message("N1.a".to_string(), "pong").unwrap().1,
+            2
+        );
+        for id in [1, 2] {
+            let _identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            assert_eq!(request.header().message_id, &[0, 0, id]);
+            let content: Request =
+                serde_json::from_slice(request.content_frame().unwrap()).unwrap();
+            assert_eq!(content.id, id as u16);
+        }
+    }
+
+    /// Call `method` against a fake Coordinator answering with `result`.
+    fn call_with_result<T: DeserializeOwned>(
+        port: u16,
+        result: Value,
+    ) -> Result<T, CommunicatorError> {
+        let router = make_fake_coordinator(port);
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            answer(&router, to_vec(&Response::build(1, result)));
+        });
+        let result = comm.call_method("N1.motor", "get", None::<()>, Duration::from_secs(1));
+        handle.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn test_call_method_string() {
+        let result: String = call_with_result(12365, serde_json::json!("abc")).unwrap();
+        assert_eq!(result, "abc");
+    }
+
+    #[test]
+    fn test_call_method_struct() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Position {
+            x: f64,
+            y: f64,
+        }
+        let result: Position =
+            call_with_result(12366, serde_json::json!({"x": 1.5, "y": -2.0})).unwrap();
+        assert_eq!(result, Position { x: 1.5, y: -2.0 });
+    }
+
+    #[test]
+    fn test_call_method_unit() {
+        call_with_result::<()>(12367, Value::Null).unwrap();
+    }
+
+    #[test]
+    fn test_call_method_type_mismatch() {
+        let result = call_with_result::<u32>(12368, serde_json::json!("abc"));
+        match result {
+            Err(CommunicatorError::Json(err)) => assert!(err.to_string().contains("expected u32")),
+            _ => panic!("Type mismatch expected."),
+        }
+    }
+
+    /// Let a fake component answer the next request with `result` and return the request.
+    fn fake_component(port: u16, result: Value) -> std::thread::JoinHandle<Request> {
+        let router = make_fake_coordinator(port);
+        std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let message = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let request: Request =
+                serde_json::from_slice(message.content_frame().unwrap()).unwrap();
+            let response = Message::build_response(
+                b"comm".to_vec(),
+                b"N1.motor".to_vec(),
+                Some(message.header().conversation_id),
+                request.id,
+                result,
+            );
+            router.send(identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(response.to_frames(), 0).unwrap();
+            request
+        })
+    }
+
+    /// Send a response with `result` to `request` via the fake Coordinator.
+    fn respond(router: &zmq::Socket, identity: &[u8], request: &Message, result: Value) {
+        let content: Request = serde_json::from_slice(request.content_frame().unwrap()).unwrap();
+        let response = Message::build_response(
+            b"comm".to_vec(),
+            b"N1.motor".to_vec(),
+            Some(request.header().conversation_id),
+            content.id,
+            result,
+        );
+        router.send(identity, zmq::SNDMORE).unwrap();
+        router.send_multipart(response.to_frames(), 0).unwrap();
+    }
+
+    #[test]
+    fn test_ask_retries_after_dropped_request() {
+        let router = make_fake_coordinator(12381);
+        let policy = RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(10),
+        };
+        let timeout = Some(Duration::from_millis(200));
+        let mut comm = Communicator::build("comm", None, Some(12381), timeout)
+            .unwrap()
+            .with_retry_policy(policy);
+        let handle = std::thread::spawn(move || {
+            let _identity = router.recv_bytes(0).unwrap();
+            let first = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let identity = router.recv_bytes(0).unwrap();
+            let second = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            assert_eq!(first.message_id(), second.message_id());
+            assert_ne!(
+                first.header().conversation_id,
+                second.header().conversation_id
+            );
+            respond(&router, &identity, &second, json!(7));
+            // A late response to the first attempt.
+            respond(&router, &identity, &first, json!(1));
+            router
+        });
+        let result = comm.ask("N1.motor", "get_speed", None, Duration::from_millis(100));
+        let _router = handle.join().unwrap();
+        assert_eq!(result.unwrap(), 7);
+        assert!(matches!(
+            comm.read_message(),
+            Err(CommunicatorError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_ask_retries_exhausted() {
+        let _router = make_fake_coordinator(12382);
+        let policy = RetryPolicy {
+            attempts: 2,
+            backoff: Duration::ZERO,
+        };
+        let mut comm = Communicator::build("comm", None, Some(12382), None)
+            .unwrap()
+            .with_retry_policy(policy);
+        let result = comm.ask("N1.motor", "get_speed", None, Duration::from_millis(50));
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::RetriesExhausted { attempts: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_try_read_message() {
+        let router = make_fake_coordinator(12387);
+        let comm = Communicator::build("comm", None, Some(12387), None).unwrap();
+        #[cfg(unix)]
+        assert!(comm.raw_fd().unwrap() >= 0);
+        assert!(comm.try_read_message().unwrap().is_none());
+        comm.send_rpc_message("N1.a".to_string(), "get_speed")
+            .unwrap();
+        let identity = router.recv_bytes(0).unwrap();
+        let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        respond(&router, &identity, &request, json!(5));
+        let start = Instant::now();
+        let message = loop {
+            if let Some(message) = comm.try_read_message().unwrap() {
+                break message;
+            }
+            assert!(start.elapsed() < Duration::from_secs(1));
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(
+            message.header().conversation_id,
+            request.header().conversation_id
+        );
+        assert!(comm.try_read_message().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_malformed_message() {
+        let router = make_fake_coordinator(12380);
+        let comm = Communicator::build("comm", None, Some(12380), None).unwrap();
+        comm.send_rpc_message("N1.a".to_string(), "pong").unwrap();
+        let identity = router.recv_bytes(0).unwrap();
+        let _request = router.recv_multipart(0).unwrap();
+        router.send(identity, zmq::SNDMORE).unwrap();
+        router
+            .send_multipart([b"\x00".to_vec(), b"comm".to_vec()], 0)
+            .unwrap();
+        assert!(matches!(
+            comm.read_message(),
+            Err(CommunicatorError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_handle_concurrent_asks() {
+        let router = make_fake_coordinator(12379);
+        let comm = Communicator::build("comm", None, Some(12379), None).unwrap();
+        let coordinator = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for _ in 0..4 {
+                let identity = router.recv_bytes(0).unwrap();
+                requests.push((
+                    identity,
+                    Message::new(router.recv_multipart(0).unwrap()).unwrap(),
+                ));
+            }
+            // Answer in reverse order, echoing the params.
+            for (identity, message) in requests.into_iter().rev() {
+                let request: Request =
+                    serde_json::from_slice(message.content_frame().unwrap()).unwrap();
+                let response = Message::build_response(
+                    b"comm".to_vec(),
+                    b"N1.echo".to_vec(),
+                    Some(message.header().conversation_id),
+                    request.id,
+                    request.params,
+                );
+                router.send(identity, zmq::SNDMORE).unwrap();
+                router.send_multipart(response.to_frames(), 0).unwrap();
+            }
+        });
+        let handle = comm.handle();
+        let askers: Vec<_> = (0..4)
+            .map(|i| {
+                let handle = handle.clone();
+                std::thread::spawn(move || {
+                    handle.ask("N1.echo", "echo", Some(json!(i)), Duration::from_secs(2))
+                })
+            })
+            .collect();
+        for (i, asker) in askers.into_iter().enumerate() {
+            assert_eq!(asker.join().unwrap().unwrap(), json!(i));
+        }
+        coordinator.join().unwrap();
+        assert!(!comm.poll(0).unwrap());
+    }
+
+    #[test]
+    fn test_handle_ask_answers_ping() {
+        let router = make_fake_coordinator(12392);
+        router.set_rcvtimeo(1000).unwrap();
+        let comm = Communicator::build("comm", None, Some(12392), None).unwrap();
+        let coordinator = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let send = |message: Message| {
+                router.send(&identity, zmq::SNDMORE).unwrap();
+                router.send_multipart(message.into_frames(), 0).unwrap();
+            };
+            // More unrelated messages, than the Communicator keeps
+            for i in 0..=MAX_BUFFERED {
+                let sender = format!("N1.c{i}").into_bytes();
+                send(Message::build_response(
+                    b"comm".to_vec(),
+                    sender,
+                    None,
+                    0,
+                    (),
+                ));
+            }
+            let (ping, ping_cid) =
+                Message::build_request(b"comm".to_vec(), b"N1.other".to_vec(), 7, "pong", None);
+            send(ping);
+            router.recv_bytes(0).unwrap();
+            let pong = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            assert_eq!(pong.header().conversation_id, ping_cid);
+            send(Message::build_response(
+                b"comm".to_vec(),
+                b"N1.echo".to_vec(),
+                Some(request.header().conversation_id),
+                request.message_id() as u16,
+                5,
+            ));
+        });
+        let result = comm
+            .handle()
+            .ask("N1.echo", "echo", None, Duration::from_secs(2));
+        coordinator.join().unwrap();
+        assert_eq!(result.unwrap(), 5);
+        let connection = comm.connection.lock().unwrap();
+        assert_eq!(connection.pending.len(), MAX_BUFFERED);
+        assert!(connection.waiting.is_empty());
+    }
+
+    #[test]
+    fn test_handle_ask_timeout() {
+        let _router = make_fake_coordinator(12393);
+        let comm = Communicator::build("comm", None, Some(12393), None).unwrap();
+        let result = comm
+            .handle()
+            .ask("N1.echo", "echo", None, Duration::from_millis(20));
+        assert!(matches!(result, Err(CommunicatorError::Timeout)));
+        assert!(comm.connection.lock().unwrap().waiting.is_empty());
+    }
+
+    #[test]
+    fn test_get_properties() {
+        let handle = fake_component(12369, json!({"speed": 5, "unit": "mm"}));
+        let mut comm = Communicator::build("comm", None, Some(12369), None).unwrap();
+        let values = comm.get_properties("N1.motor", &["speed", "unit"]).unwrap();
+        let request = handle.join().unwrap();
+        assert_eq!(request.method, "get_parameters");
+        assert_eq!(
+            request.params,
+            Some(json!({"parameters": ["speed", "unit"]}))
+        );
+        assert_eq!(values["speed"], 5);
+        assert_eq!(values["unit"], "mm");
+    }
+
+    #[test]
+    fn test_set_properties() {
+        let handle = fake_component(12370, Value::Null);
+        let mut comm = Communicator::build("comm", None, Some(12370), None).unwrap();
+        let properties = HashMap::from([("speed".to_string(), json!(7))]);
+        comm.set_properties("N1.motor", properties).unwrap();
+        let request = handle.join().unwrap();
+        assert_eq!(request.method, "set_parameters");
+        assert_eq!(request.params, Some(json!({"parameters": {"speed": 7}})));
+    }
+
+    #[test]
+    fn test_call_action() {
+        let handle = fake_component(12371, json!(12));
+        let mut comm = Communicator::build("comm", None, Some(12371), None).unwrap();
+        let kwargs = HashMap::from([("fast".to_string(), json!(true))]);
+        let result = comm
+            .call_action("N1.motor", "move", &[json!(3)], kwargs)
+            .unwrap();
+        let request = handle.join().unwrap();
+        assert_eq!(result, 12);
+        assert_eq!(request.method, "call_action");
+        assert_eq!(
+            request.params,
+            Some(json!({"action": "move", "args": [3], "kwargs": {"fast": true}}))
+        );
+    }
+
+    #[test]
+    fn test_call_action_without_arguments() {
+        let handle = fake_component(12372, Value::Null);
+        let mut comm = Communicator::build("comm", None, Some(12372), None).unwrap();
+        comm.call_action("N1.motor", "stop", &[], HashMap::new())
+            .unwrap();
+        let request = handle.join().unwrap();
+        assert_eq!(request.params, Some(json!({"action": "stop"})));
+    }
+
+    #[test]
+    fn test_ask_json() {
+        let handle = fake_component(12375, json!([1, 2]));
+        let mut comm = Communicator::build("comm", None, Some(12375), None).unwrap();
+        let result: Vec<u8> = comm.ask_json("N1.motor", "get", None::<()>).unwrap();
+        handle.join().unwrap();
+        assert_eq!(result, [1, 2]);
+    }
+
+    #[test]
+    fn test_ask_json_deserialization_error() {
+        let handle = fake_component(12376, Value::Null);
+        let mut comm = Communicator::build("comm", None, Some(12376), None).unwrap();
+        let err = comm
+            .ask_json::<f64>("N1.motor", "get_speed", None::<()>)
+            .unwrap_err();
+        handle.join().unwrap();
+        let text = err.to_string();
+        let cid = Uuid::from_slice(&err.conversation_id).unwrap().to_string();
+        for piece in [
+            "N1.motor",
+            "get_speed",
+            &cid,
+            "invalid type: null",
+            "\"result\":null",
+        ] {
+            assert!(text.contains(piece), "'{piece}' missing in '{text}'");
+        }
+    }
+
+    #[test]
+    fn test_ask_json_remote_error() {
+        let router = make_fake_coordinator(12377);
+        let handle = std::thread::spawn(move || {
+            answer(
+                &router,
+                to_vec(&ErrorResponse::from_error(1, &Error::InvalidParams)),
+            );
+        });
+        let mut comm = Communicator::build("comm", None, Some(12377), None).unwrap();
+        let err = comm
+            .ask_json::<f64>("N1.motor", "set_speed", Some([1]))
+            .unwrap_err();
+        handle.join().unwrap();
+        assert!(matches!(
+            *err.kind,
+            RpcCallErrorKind::Communication(CommunicatorError::Rpc(Error::InvalidParams))
+        ));
+        let text = err.to_string();
+        for piece in ["N1.motor", "set_speed", "-32602", "Invalid params"] {
+            assert!(text.contains(piece), "'{piece}' missing in '{text}'");
+        }
+    }
+
+    #[test]
+    fn test_ask_timeout() {
+        let _router = make_fake_coordinator(12355);
+        let mut comm = Communicator::build("comm", None, Some(12355), None).unwrap();
+        let result = comm.ask("COORDINATOR", "pong", None, Duration::from_millis(50));
+        assert!(matches!(result, Err(CommunicatorError::Timeout)));
+    }
+
+    #[test]
+    fn test_build_invalid_endpoint() {
+        for host in ["tcp://:::1", ":::1", "", "local host"] {
+            let result = Communicator::build("comm", Some(host), None, None);
+            assert!(
+                matches!(result, Err(CommunicatorError::InvalidEndpoint(_))),
+                "{host} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let comm = Communicator::build("comm", Some("[::1]"), Some(12399), None).unwrap();
+        assert_eq!(comm.endpoint().to_string(), "tcp://[::1]:12399");
+    }
+
+    #[test]
+    fn test_reconnect_invalid_endpoint_keeps_connection() {
+        let mut comm = Communicator::build("comm", None, Some(12399), None).unwrap();
+        let result = comm.reconnect(Some("a b"), None);
+        assert!(matches!(result, Err(CommunicatorError::InvalidEndpoint(_))));
+        assert_eq!(comm.endpoint().to_string(), "tcp://localhost:12399");
+    }
+
+    #[test]
+    fn test_build_ipv6() {
+        assert!(Communicator::build("comm", Some("[::1]"), None, None).is_ok());
+    }
+
+    #[test]
+    fn test_probe_dead_endpoint() {
+        // Nobody listens at this port.
+        let mut comm = Communicator::build("comm", None, Some(12356), None).unwrap();
+        let result = comm.probe(Duration::from_millis(100));
+        assert!(matches!(result, Err(CommunicatorError::Timeout)));
+    }
+
+    #[test]
+    fn test_send_to_disconnected_socket_would_block() {
+        let comm = Communicator::build("comm", None, Some(12356), None)
+            .unwrap()
+            .with_non_blocking_send(true);
+        {
+            let connection = comm.connection.lock().unwrap();
+            connection
+                .socket
+                .disconnect("tcp://localhost:12356")
+                .unwrap();
+        }
+        let result = comm.send_rpc_message("N1.motor".to_string(), "pong");
+        assert!(matches!(result, Err(CommunicatorError::WouldBlock)));
+    }
+
+    #[test]
+    fn test_send_with_full_queue_would_block() {
+        // Nobody listens at this port, such that the queue fills up.
+        let options = CommunicatorOptions::new("comm")
+            .endpoint(Endpoint::tcp("localhost", 12356).unwrap())
+            .non_blocking_send(true);
+        let comm = Communicator::build_with(options).unwrap();
+        {
+            // The high water mark applies to new connections only.
+            let connection = comm.connection.lock().unwrap();
+            connection
+                .socket
+                .disconnect("tcp://localhost:12356")
+                .unwrap();
+            connection.socket.set_sndhwm(1).unwrap();
+            connection.socket.connect("tcp://localhost:12356").unwrap();
+        }
+        let results: Vec<_> = (0..10)
+            .map(|_| comm.send_message_to("N1.motor", ContentTypes::Frame(vec![1]), 0))
+            .collect();
+        assert!(results[0].is_ok());
+        assert!(results
+            .iter()
+            .any(|result| matches!(result, Err(CommunicatorError::WouldBlock))));
+    }
+
+    #[test]
+    fn test_send_message_to() {
+        let router = make_fake_coordinator(12389);
+        let comm = Communicator::build("comm", None, Some(12389), None).unwrap();
+        let content = ContentTypes::Frames(vec![vec![1, 2], vec![3]]);
+        let cid = comm
+            .send_message_to("N1.camera", content, message_types::NOT_DEFINED)
+            .unwrap();
+        let _identity = router.recv_bytes(0).unwrap();
+        let message = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(message.receiver_frame(), b"N1.camera");
+        assert_eq!(message.sender_frame(), b"comm");
+        assert_eq!(message.header().conversation_id, &cid[..]);
+        assert_eq!(*message.header().message_type, message_types::NOT_DEFINED);
+        assert_eq!(message.payload(), [vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn test_read_message_timeout() {
+        // Nobody listens at this port.
+        let timeout = Duration::from_millis(100);
+        let comm = Communicator::build("comm", None, Some(12356), Some(timeout)).unwrap();
+        let start = Instant::now();
+        assert!(matches!(
+            comm.read_message(),
+            Err(CommunicatorError::Timeout)
+        ));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= timeout && elapsed < timeout * 5);
+    }
+
+    #[test]
+    fn test_sign_in_with_retries_exhausted() {
+        let mut comm = Communicator::build("comm", None, Some(12356), None).unwrap();
+        let start = Instant::now();
+        let result = comm.sign_in_with_retries(3, Duration::from_millis(20));
+        assert!(matches!(result, Err(CommunicatorError::Timeout)));
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[test]
+    fn test_sign_in_timeout() {
+        let mut comm =
+            Communicator::build("comm", None, Some(12356), Some(Duration::from_millis(50)))
+                .unwrap();
+        assert!(matches!(comm.sign_in(), Err(CommunicatorError::Timeout)));
+        assert!(matches!(comm.sign_out(), Err(CommunicatorError::Timeout)));
+    }
+
+    #[test]
+    fn test_read_rpc_message_unknown_error() {
+        let router = make_fake_coordinator(12358);
+        let comm = Communicator::build("comm", None, Some(12358), None).unwrap();
+        comm.send_rpc_message("COORDINATOR".to_string(), "pong")
+            .unwrap();
+        let data = Some(serde_json::json!([1]));
+        answer(
+            &router,
+            to_vec(&ErrorResponse::build(0, 5, "odd", data.clone())),
+        );
+        match comm.read_rpc_message() {
+            Err(CommunicatorError::RpcError {
+                code,
+                message,
+                data: received,
+            }) => {
+                assert_eq!(code, 5);
+                assert_eq!(message, "odd");
+                assert_eq!(received, data);
+            }
+            _ => panic!("RpcError expected."),
+        }
+    }
+
+    #[test]
+    fn test_sign_in_duplicate_name() {
+        let router = make_fake_coordinator(12359);
+        let mut comm = Communicator::build("comm", None, Some(12359), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            answer(
+                &router,
+                to_vec(&ErrorResponse::from_error(0, &Error::DuplicateName)),
+            );
+        });
+        let result = comm.sign_in();
+        handle.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::DuplicateName))
+        ));
+        assert_eq!(comm.full_name, b"comm");
+    }
+
+    #[test]
+    fn test_namespace_completion() {
+        let router = make_fake_coordinator(12384);
+        let mut comm = Communicator::build("comm", None, Some(12384), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            answer(&router, to_vec(&Response::build(1, Value::Null)));
+            router
+        });
+        comm.sign_in().unwrap();
+        let router = handle.join().unwrap();
+        let sent_receiver = |receiver: &str| {
+            comm.send_rpc_message(receiver.to_string(), "get_speed")
+                .unwrap();
+            let _identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            request.receiver_frame().clone()
+        };
+        assert_eq!(sent_receiver("motor"), b"N1.motor");
+        assert_eq!(sent_receiver("N2.motor"), b"N2.motor");
+        assert_eq!(sent_receiver("COORDINATOR"), b"COORDINATOR");
+        let handle_receiver = |comm: &Communicator| {
+            let (request, _cid, _id) = comm.handle().build_request(b"motor".to_vec(), "a", None);
+            request.receiver_frame().clone()
+        };
+        assert_eq!(handle_receiver(&comm), b"N1.motor");
+        comm = comm.with_namespace_completion(false);
+        assert_eq!(comm.complete_receiver(b"motor".to_vec()), b"motor");
+        assert_eq!(handle_receiver(&comm), b"motor");
+    }
+
+    #[test]
+    fn test_build_with_options() {
+        let options = CommunicatorOptions::new("comm")
+            .endpoint(Endpoint::tcp("localhost", 12385).unwrap())
+            .timeout(Duration::from_secs(5))
+            .namespace_completion(false)
+            .rename_attempts(2);
+        let comm = Communicator::build_with(options).unwrap();
+        assert_eq!(comm.name(), "comm");
+        assert_eq!(comm.endpoint().to_string(), "tcp://localhost:12385");
+        assert_eq!(comm.timeout, Duration::from_secs(5));
+        assert!(!comm.complete_namespace);
+        assert_eq!(comm.rename_attempts, 2);
+        assert!(comm.auto_sign_in);
+    }
+
+    #[test]
+    fn test_ask_with_short_timeout() {
+        let _router = make_fake_coordinator(12386);
+        let options = CommunicatorOptions::new("comm")
+            .endpoint(Endpoint::tcp("localhost", 12386).unwrap())
+            .timeout(Duration::from_secs(5));
+        let mut comm = Communicator::build_with(options).unwrap();
+        let options = AskOptions {
+            timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let result = comm.ask_with("N1.motor", "get_speed", None, options);
+        assert!(matches!(result, Err(CommunicatorError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_observer() {
+        use std::sync::atomic::AtomicUsize;
+
+        let router = make_fake_coordinator(12383);
+        let mut comm = Communicator::build("comm", None, Some(12383), None).unwrap();
+        let counts = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+        let observed = Arc::clone(&counts);
+        comm.set_observer(Box::new(move |direction, _message| {
+            observed[direction as usize].fetch_add(1, Ordering::Relaxed);
+            panic!("Observers may panic.");
+        }));
+        let handle = std::thread::spawn(move || {
+            answer(&router, to_vec(&Response::build(1, Value::Null)));
+            answer(&router, to_vec(&Response::build(2, 5)));
+        });
+        comm.sign_in().unwrap();
+        let result = comm.ask("N1.motor", "get_speed", None, Duration::from_secs(1));
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(counts[Direction::In as usize].load(Ordering::Relaxed), 2);
+        assert_eq!(counts[Direction::Out as usize].load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_sign_in_skips_unrelated_message() {
+        let router = make_fake_coordinator(12378);
+        let mut comm = Communicator::build("comm", None, Some(12378), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let stale =
+                Message::build_response(b"comm".to_vec(), b"X9.other".to_vec(), None, 0, ());
+            let response = Message::build_response(
+                b"comm".to_vec(),
+                b"N1.COORDINATOR".to_vec(),
+                Some(request.header().conversation_id),
+                request.message_id() as u16,
+                (),
+            );
+            for message in [stale, response] {
+                router.send(&identity, zmq::SNDMORE).unwrap();
+                router.send_multipart(message.to_frames(), 0).unwrap();
+            }
+        });
+        comm.sign_in().unwrap();
+        handle.join().unwrap();
+        assert_eq!(comm.full_name(), "N1.comm");
+        assert_eq!(comm.read_buffered().unwrap().sender_frame(), b"X9.other");
+    }
+
+    #[test]
+    fn test_sign_in() {
+        let router = make_fake_coordinator(12360);
+        let mut comm = Communicator::build("comm", None, Some(12360), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            answer(&router, to_vec(&Response::build(0, None::<()>)));
+        });
+        assert_eq!(comm.namespace(), None);
+        comm.sign_in().unwrap();
+        handle.join().unwrap();
+        assert_eq!(comm.name(), "comm");
+        assert_eq!(comm.full_name(), "N1.comm");
+        assert_eq!(comm.namespace(), Some("N1"));
+    }
+
+    #[test]
+    fn test_send_rpc_message_with_params() {
+        let router = make_fake_coordinator(12357);
+        let comm = Communicator::build("comm", None, Some(12357), None).unwrap();
+        let (cid, id) = comm
+            .send_rpc_message_with_params(
+                "N1.motor".to_string(),
+                "set",
+                Some(std::collections::HashMap::from([("speed", 5)])),
+            )
+            .unwrap();
+        let _identity = router.recv_bytes(0).unwrap();
+        let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(request.header().conversation_id, cid);
+        let content: Request = serde_json::from_slice(request.content_frame().unwrap()).unwrap();
+        assert_eq!(content.id, id);
+        assert_eq!(content.method, "set");
+        assert_eq!(content.params, Some(serde_json::json!({"speed": 5})));
+    }
+
+    #[test]
+    fn test_read_rpc_message_result() {
+        let router = make_fake_coordinator(12351);
+        let comm = Communicator::build("comm", None, Some(12351), None).unwrap();
+        comm.send_rpc_message("COORDINATOR".to_string(), "pong")
+            .unwrap();
+        answer(&router, to_vec(&Response::build(0, 7)));
+        assert_eq!(comm.read_rpc_message().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_read_rpc_message_error() {
+        let router = make_fake_coordinator(12352);
+        let comm = Communicator::build("comm", None, Some(12352), None).unwrap();
+        comm.send_rpc_message("COORDINATOR".to_string(), "pong")
+            .unwrap();
+        let error = Error::NotSignedIn;
+        answer(&router, to_vec(&ErrorResponse::from_error(0, &error)));
+        assert!(matches!(
+            comm.read_rpc_message(),
+            Err(CommunicatorError::Rpc(Error::NotSignedIn))
+        ));
+    }
+
+    #[test]
+    fn test_read_rpc_message_custom_error() {
+        let router = make_fake_coordinator(12353);
+        let comm = Communicator::build("comm", None, Some(12353), None).unwrap();
+        comm.send_rpc_message("COORDINATOR".to_string(), "pong")
+            .unwrap();
+        let error = Error::custom(-32010, "motor not homed", Some(serde_json::json!("x"))).unwrap();
+        answer(&router, to_vec(&ErrorResponse::from_error(0, &error)));
+        match comm.read_rpc_message() {
+            Err(CommunicatorError::Rpc(received)) => assert_eq!(received, error),
+            _ => panic!("Custom error expected."),
+        }
     }
 
-    pub fn ping(&self, receiver: String) {
-        self.send_rpc_message(receiver, "pong");
+    #[test]
+    fn test_ask_batch() {
+        let router = make_fake_coordinator(12388);
+        let mut comm = Communicator::build("comm", None, Some(12388), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            let identity = router.recv_bytes(0).unwrap();
+            let request = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+            let requests: Vec<Request> =
+                serde_json::from_slice(request.content_frame().unwrap()).unwrap();
+            // Answer out of order, with an error, and without the last entry.
+            let content = json!([
+                ErrorResponse::from_error(requests[1].id, &Error::MethodNotFound),
+                Response::build(requests[0].id, json!(4)),
+            ]);
+            let response = Message::build(
+                request.sender_frame().to_vec(),
+                b"N1.motor".to_vec(),
+                Some(request.header().conversation_id),
+                None,
+                message_types::JSON,
+                ContentTypes::Frame(to_vec(&content)),
+            );
+            router.send(identity, zmq::SNDMORE).unwrap();
+            router.send_multipart(response.to_frames(), 0).unwrap();
+            requests
+        });
+        let calls = vec![
+            ("double", Some(json!(2))),
+            ("halve", None),
+            ("triple", Some(json!(1))),
+        ];
+        let results = comm.ask_batch("N1.motor", calls).unwrap();
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, "double");
+        assert_eq!(requests[0].params, Some(json!(2)));
+        assert_ne!(requests[0].id, requests[1].id);
+        assert_eq!(results[0].as_ref().unwrap(), &json!(4));
+        assert!(matches!(
+            results[1],
+            Err(CommunicatorError::Rpc(Error::MethodNotFound))
+        ));
+        assert!(matches!(
+            results[2],
+            Err(CommunicatorError::Rpc(Error::InternalError))
+        ));
     }
 }
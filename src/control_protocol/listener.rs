@@ -0,0 +1,336 @@
+//! Answer incoming requests with registered methods.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    core::ContentTypes,
+    json::{discover_document, to_vec, ErrorResponse, Request, Response},
+};
+
+use super::{
+    communicator::{Communicator, CommunicatorError},
+    message_types, Error, Message,
+};
+
+/// A method callable via JSON-RPC, receiving the request's params
+pub type MethodHandler = Box<dyn FnMut(Option<Value>) -> Result<Value, Error> + Send>;
+
+/// A registered method with its description for `rpc.discover`
+struct Method {
+    handler: MethodHandler,
+    description: Option<String>,
+}
+
+/// A task run periodically by the Listener's loop
+struct Housekeeping {
+    interval: Duration,
+    task: Box<dyn FnMut() + Send>,
+    last: Instant,
+}
+
+/// How long the loop waits for a message before checking stop signal and housekeeping
+const POLL_INTERVAL_MS: i64 = 50;
+
+/// Methods answered by the Listener itself
+const BUILT_IN_METHODS: [(&str, &str); 3] = [
+    ("pong", "Check whether the Component is alive."),
+    ("rpc.discover", "List the available methods."),
+    ("shut_down", "Stop the Component."),
+];
+
+/// Stop a running [`Listener`] from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct StopSignal(Arc<AtomicBool>);
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Receiving half of a Component: answers requests with registered methods.
+///
+/// Pings are answered by the [`Communicator`] itself, `shut_down` stops the loop, and
+/// `rpc.discover` lists all methods.
+/// ```no_run
+/// # use ruleco::control_protocol::{communicator::Communicator, listener::Listener};
+/// # use serde_json::json;
+/// let comm = Communicator::build("thermometer", None, None, None).unwrap();
+/// let mut listener = Listener::new(comm);
+/// listener.register("get_temperature", |_params| Ok(json!(21.5)));
+/// listener.run().unwrap();
+/// ```
+pub struct Listener {
+    communicator: Communicator,
+    methods: HashMap<String, Method>,
+    stop: StopSignal,
+    housekeeping: Option<Housekeeping>,
+}
+
+impl Listener {
+    pub fn new(communicator: Communicator) -> Self {
+        Self {
+            communicator,
+            methods: HashMap::new(),
+            stop: StopSignal::default(),
+            housekeeping: None,
+        }
+    }
+
+    /// Answer requests for `method` with `handler`.
+    pub fn register<F>(&mut self, method: &str, handler: F)
+    where
+        F: FnMut(Option<Value>) -> Result<Value, Error> + Send + 'static,
+    {
+        self.insert(method, None, Box::new(handler));
+    }
+
+    /// Like [`Listener::register`], but with a `description` for `rpc.discover`.
+    pub fn register_with_description<F>(&mut self, method: &str, description: &str, handler: F)
+    where
+        F: FnMut(Option<Value>) -> Result<Value, Error> + Send + 'static,
+    {
+        self.insert(method, Some(description.to_string()), Box::new(handler));
+    }
+
+    fn insert(&mut self, method: &str, description: Option<String>, handler: MethodHandler) {
+        let method_entry = Method {
+            handler,
+            description,
+        };
+        self.methods.insert(method.to_string(), method_entry);
+    }
+
+    /// Describe the registered and built-in methods.
+    fn discover(&self) -> Value {
+        let registered = self
+            .methods
+            .iter()
+            .map(|(name, method)| (name.as_str(), method.description.as_deref()));
+        let built_in = BUILT_IN_METHODS
+            .iter()
+            .filter(|(name, _)| !self.methods.contains_key(*name))
+            .map(|(name, description)| (*name, Some(*description)));
+        discover_document(&self.communicator.full_name(), registered.chain(built_in))
+    }
+
+    /// Run `task` about every `interval` between handling requests, e.g. for device housekeeping.
+    pub fn set_housekeeping<F>(&mut self, interval: Duration, task: F)
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.housekeeping = Some(Housekeeping {
+            interval,
+            task: Box::new(task),
+            last: Instant::now(),
+        });
+    }
+
+    /// Get a signal to stop the loop from another thread.
+    pub fn stop_signal(&self) -> StopSignal {
+        self.stop.clone()
+    }
+
+    /// Sign in (if not yet done) and answer requests until stopped or shut down, then sign out.
+    ///
+    /// A stop signal takes effect after the current request is handled.
+    pub fn run(&mut self) -> Result<(), CommunicatorError> {
+        if self.communicator.namespace().is_none() {
+            self.communicator.sign_in()?;
+        }
+        while !self.stop.is_stopped() {
+            if self.communicator.poll(POLL_INTERVAL_MS)? {
+                // Pings are answered by the Communicator and yield no message.
+                if let Some(message) = self.communicator.try_read_message()? {
+                    self.handle_message(&message);
+                }
+            }
+            if let Some(housekeeping) = &mut self.housekeeping {
+                if housekeeping.last.elapsed() >= housekeeping.interval {
+                    housekeeping.last = Instant::now();
+                    (housekeeping.task)();
+                }
+            }
+        }
+        self.communicator.sign_out()
+    }
+
+    /// Run the loop in a new thread.
+    pub fn spawn(mut self) -> (JoinHandle<Result<(), CommunicatorError>>, StopSignal) {
+        let stop = self.stop_signal();
+        (std::thread::spawn(move || self.run()), stop)
+    }
+
+    /// Answer `message`, if it is a request or a batch of requests.
+    fn handle_message(&mut self, message: &Message) {
+        let Some(content) = message.content_frame() else {
+            return;
+        };
+        let response = match serde_json::from_slice::<Value>(content) {
+            Ok(Value::Array(batch)) if batch.is_empty() => {
+                to_vec(&ErrorResponse::from_error(None, &Error::InvalidRequest))
+            }
+            Ok(Value::Array(batch)) => {
+                let responses: Vec<Value> = batch
+                    .into_iter()
+                    .map(|entry| match serde_json::from_value::<Request>(entry) {
+                        Ok(request) => self.answer(request),
+                        Err(_) => json!(ErrorResponse::from_error(None, &Error::InvalidRequest)),
+                    })
+                    .collect();
+                to_vec(&responses)
+            }
+            Ok(value) => match serde_json::from_value::<Request>(value) {
+                Ok(request) => to_vec(&self.answer(request)),
+                // Not a request, e.g. a response.
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+        let response = Message::build(
+            message.sender_frame().to_vec(),
+            self.communicator.full_name().as_bytes().to_vec(),
+            Some(message.header().conversation_id),
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(response),
+        );
+        if let Err(err) = self.communicator.send_message(response) {
+            log::warn!("Sending a response failed: {err}");
+        }
+    }
+
+    /// Call the method of `request` and return the response content.
+    fn answer(&mut self, request: Request) -> Value {
+        let result = match (
+            request.method.as_str(),
+            self.methods.get_mut(&request.method),
+        ) {
+            (_, Some(method)) => (method.handler)(request.params),
+            ("shut_down", None) => {
+                self.stop.stop();
+                Ok(Value::Null)
+            }
+            ("rpc.discover", None) => Ok(self.discover()),
+            (_, None) => Err(Error::MethodNotFound),
+        };
+        match result {
+            Ok(value) => json!(Response::build(request.id, value)),
+            Err(error) => json!(ErrorResponse::from_error(request.id, &error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Let `listener` handle `method` and return the response content.
+    fn dispatch(listener: &mut Listener, router: &zmq::Socket, method: &str) -> Vec<u8> {
+        let (request, _cid) = Message::build_request(
+            b"listener".to_vec(),
+            b"N1.comm".to_vec(),
+            5,
+            method,
+            Some(json!(2)),
+        );
+        listener.handle_message(&request);
+        let _identity = router.recv_bytes(0).unwrap();
+        let response = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(response.receiver_frame(), b"N1.comm");
+        response.content_frame().unwrap().clone()
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let ctx = zmq::Context::new();
+        let router = ctx.socket(zmq::ROUTER).unwrap();
+        router.bind("inproc://test_listener_dispatch").unwrap();
+        let comm = Communicator::build_with_endpoint(
+            "listener",
+            "inproc://test_listener_dispatch".parse().unwrap(),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        let mut listener = Listener::new(comm);
+        listener.register("double", |params| {
+            let value = params
+                .and_then(|p| p.as_i64())
+                .ok_or(Error::InvalidParams)?;
+            Ok(json!(value * 2))
+        });
+
+        let response: Response =
+            serde_json::from_slice(&dispatch(&mut listener, &router, "double")).unwrap();
+        assert_eq!((response.id, response.result), (5, json!(4)));
+
+        let response: ErrorResponse =
+            serde_json::from_slice(&dispatch(&mut listener, &router, "halve")).unwrap();
+        assert_eq!(response.error.code, Error::MethodNotFound.code());
+
+        assert!(!listener.stop.is_stopped());
+        dispatch(&mut listener, &router, "shut_down");
+        assert!(listener.stop.is_stopped());
+    }
+
+    #[test]
+    fn test_dispatch_batch() {
+        let ctx = zmq::Context::new();
+        let router = ctx.socket(zmq::ROUTER).unwrap();
+        router.bind("inproc://test_listener_batch").unwrap();
+        let comm = Communicator::build_with_endpoint(
+            "listener",
+            "inproc://test_listener_batch".parse().unwrap(),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        let mut listener = Listener::new(comm);
+        listener.register("answer", |_params| Ok(json!(42)));
+        let requests = json!([
+            Request::build(3, "answer"),
+            Request::build(4, "unknown"),
+            {"jsonrpc": "2.0"},
+        ]);
+        let request = Message::build(
+            b"listener".to_vec(),
+            b"N1.comm".to_vec(),
+            None,
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&requests)),
+        );
+        listener.handle_message(&request);
+        let _identity = router.recv_bytes(0).unwrap();
+        let response = Message::new(router.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(
+            response.header().conversation_id,
+            request.header().conversation_id
+        );
+        let responses: Vec<Value> =
+            serde_json::from_slice(response.content_frame().unwrap()).unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"], 42);
+        assert_eq!(responses[1]["id"], 4);
+        assert_eq!(responses[1]["error"]["code"], Error::MethodNotFound.code());
+        assert_eq!(responses[2]["id"], Value::Null);
+        assert_eq!(responses[2]["error"]["code"], Error::InvalidRequest.code());
+    }
+}
@@ -0,0 +1,43 @@
+//! Message type values of the LECO specification
+//!
+//! The message type is the last byte of the header and describes the content of the payload.
+
+use super::Message;
+
+/// The content type is not defined.
+pub const NOT_DEFINED: u8 = 0;
+/// The first payload frame is a JSON(-RPC) message.
+pub const JSON: u8 = 1;
+
+/// Whether the message's payload is JSON.
+pub fn is_json(message: &Message) -> bool {
+    *message.header().message_type == JSON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ContentTypes;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(NOT_DEFINED, 0);
+        assert_eq!(JSON, 1);
+    }
+
+    #[test]
+    fn test_is_json() {
+        let build = |message_type| {
+            Message::build(
+                b"rec".to_vec(),
+                b"send".to_vec(),
+                None,
+                None,
+                message_type,
+                ContentTypes::Null,
+            )
+        };
+        assert!(is_json(&build(JSON)));
+        assert!(!is_json(&build(NOT_DEFINED)));
+    }
+}
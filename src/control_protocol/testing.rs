@@ -0,0 +1,82 @@
+//! Helpers for testing code which exchanges messages
+
+use super::Message;
+
+/// Describe the differences of two messages, ignoring conversation id and message id.
+pub fn message_differences(a: &Message, b: &Message) -> Vec<String> {
+    let (frames_a, frames_b) = (a.to_frames(), b.to_frames());
+    let mut differences = Vec::new();
+    if frames_a.len() != frames_b.len() {
+        differences.push(format!(
+            "frame count differs: {} != {}",
+            frames_a.len(),
+            frames_b.len()
+        ));
+    }
+    for (index, (frame_a, frame_b)) in frames_a.iter().zip(frames_b).enumerate() {
+        let (frame_a, frame_b) = if index == 3 {
+            // Only the message type of the header is compared.
+            (&frame_a[19..], &frame_b[19..])
+        } else {
+            (&frame_a[..], &frame_b[..])
+        };
+        if frame_a != frame_b {
+            differences.push(format!(
+                "frame {index} differs: {:?} != {:?}",
+                String::from_utf8_lossy(frame_a),
+                String::from_utf8_lossy(frame_b)
+            ));
+        }
+    }
+    differences
+}
+
+/// Assert that two messages are equal except for their conversation id and message id.
+///
+/// # Panics
+///
+/// Panics with a list of the differing frames if the messages are not equivalent.
+#[track_caller]
+pub fn assert_messages_equivalent(a: &Message, b: &Message) {
+    let differences = message_differences(a, b);
+    if !differences.is_empty() {
+        panic!("Messages are not equivalent:\n{}", differences.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ContentTypes;
+
+    fn build(receiver: &[u8], content: &[u8]) -> Message {
+        Message::build(
+            receiver.to_vec(),
+            b"sender".to_vec(),
+            None,
+            None,
+            1,
+            ContentTypes::Frame(content.to_vec()),
+        )
+    }
+
+    #[test]
+    fn test_equivalent_despite_ids() {
+        assert_messages_equivalent(&build(b"rec", b"abc"), &build(b"rec", b"abc"))
+    }
+
+    #[test]
+    fn test_differences() {
+        let differences = message_differences(&build(b"rec", b"abc"), &build(b"other", b"abc"));
+        assert_eq!(
+            differences,
+            vec!["frame 1 differs: \"rec\" != \"other\"".to_string()]
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "frame 4 differs")]
+    fn test_assert_fails() {
+        assert_messages_equivalent(&build(b"rec", b"abc"), &build(b"rec", b"def"))
+    }
+}
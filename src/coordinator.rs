@@ -0,0 +1,5268 @@
+//! Route messages between different Components in a LECO network
+//!
+//! The `coordinator` binary runs a [`Coordinator`], but it may be embedded in any application:
+//! ```no_run
+//! use ruleco::coordinator::Coordinator;
+//!
+//! let mut coordinator = Coordinator::new("N1".to_string(), Some(12300), None);
+//! let stop = coordinator.stop_signal();
+//! let routing = std::thread::spawn(move || coordinator.routing());
+//! // ...
+//! stop.stop();
+//! routing.join().unwrap();
+//! ```
+//!
+//! Messages to another namespace are forwarded directly to the Coordinator of that namespace.
+//! A Coordinator delivers messages forwarded by another one only to its own Components and
+//! answers those for yet another namespace with
+//! [`NodeUnknown`](crate::control_protocol::Error::NodeUnknown), such that inconsistent node
+//! tables cannot bounce a message between Coordinators forever.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    control_protocol::{listener::StopSignal, message_types, Error, Limits, Message},
+    coordinator::admin::{AdminAnswer, AdminCommand, AdminRequest, CoordinatorAdminHandle},
+    core::ContentTypes,
+    core::{compose_full_name, is_compatible_version, validate_name, Endpoint, FullName},
+    data_protocol::DataMessage,
+    json::{self, is_request_for, is_sign_in, to_vec, ErrorResponse, Request, Requests, Response},
+    VERSION,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Methods answered by the Coordinator itself, with their descriptions
+const BUILT_IN_METHODS: [(&str, Option<&str>); 16] = [
+    (
+        "coordinator_sign_in",
+        Some("Register the sending Coordinator as a node."),
+    ),
+    (
+        "coordinator_sign_out",
+        Some("Unregister the sending Coordinator."),
+    ),
+    (
+        "send_local_components",
+        Some("List the Components signed in to this Coordinator."),
+    ),
+    (
+        "send_global_components",
+        Some("List the Components of all known Coordinators by namespace, 'refresh' to query."),
+    ),
+    (
+        "record_components",
+        Some("Take note of the 'components' of the sending Coordinator."),
+    ),
+    (
+        "send_nodes",
+        Some("List the addresses of all known Coordinators by namespace."),
+    ),
+    (
+        "send_statistics",
+        Some("Get the traffic counters, resetting them if 'reset' is true."),
+    ),
+    (
+        "component_info",
+        Some("Get the traffic and last message time of Component 'name' or of all by name."),
+    ),
+    (
+        "add_nodes",
+        Some("Connect to the Coordinators in 'nodes' (namespace: address)."),
+    ),
+    (
+        "set_nodes",
+        Some("Connect to exactly the Coordinators in 'nodes' (namespace: address)."),
+    ),
+    (
+        "coordinator_info",
+        Some("Get name, namespace, and version of the Coordinator."),
+    ),
+    ("pong", Some("Check whether the Coordinator is alive.")),
+    ("rpc.discover", Some("List the available methods.")),
+    ("shut_down", Some("Stop routing messages.")),
+    ("sign_in", Some("Register the sending Component.")),
+    ("sign_out", Some("Unregister the sending Component.")),
+];
+
+/// How long the routing loop waits for a message before checking timeouts
+const POLL_INTERVAL_MS: i64 = 50;
+
+/// How long `send_global_components` waits for the answers of other Coordinators
+const NODE_QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long a removed node's socket may try to deliver the sign-out
+const SIGN_OUT_LINGER_MS: i32 = 100;
+
+/// Where zmq sends the authentication requests (ZAP) of all sockets of a context
+const ZAP_ENDPOINT: &str = "zeromq.zap.01";
+
+/// How often the routing loop writes the buffered audit log entries
+const AUDIT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Log target of the records of [`Coordinator::set_trace`]
+pub const TRACE_TARGET: &str = "ruleco::trace";
+
+/// Combine a socket identity and a message
+struct MessageContainer<T: zmq::Sendable> {
+    identity: T,
+    message: Message,
+}
+
+/// Combine sending socket information with a message
+struct SendingContainer<T: zmq::Sendable> {
+    receiving_namespace: Vec<u8>,
+    msg_cont: MessageContainer<T>,
+}
+// TODO maybe combine with MessageContainer?
+
+/// Settings of a [`Coordinator`], see [`Coordinator::with_config`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoordinatorConfig {
+    /// Ping Components and other Coordinators, which have been silent for this duration
+    ///
+    /// A ping, which is not answered within this duration, again, makes the Component expire.
+    /// An expired Coordinator is marked unreachable and we try to sign in to it again at this
+    /// interval.
+    pub heartbeat_interval: Duration,
+    /// Keep silent Components and Coordinators at least for this duration, even if they do not
+    /// answer pings
+    pub expiry_timeout: Duration,
+    /// Reject incoming messages exceeding these limits
+    ///
+    /// Frames larger than `max_bytes` are already dropped by zmq, which disconnects the sender,
+    /// such that they are never buffered.
+    pub limits: Limits,
+    /// Reject messages whose sender namespace does not fit the connection they arrived by
+    ///
+    /// Disable it only for topologies, where messages take unusual paths.
+    pub check_sender_namespace: bool,
+    /// Publish sign-in, sign-out, expiry, and kicking of Components at this endpoint
+    pub events_endpoint: Option<Endpoint>,
+    /// Query the Components of another Coordinator again, if its last report is older
+    ///
+    /// Coordinators report changes of their Components to each other, so this is a fallback.
+    pub directory_max_age: Duration,
+    /// Reject sign-ins of new Components, while this many are signed in
+    ///
+    /// Components signing out or expiring free their slot.
+    pub max_components: usize,
+    /// Bind additionally to these endpoints, e.g. an `ipc` path for the local Components
+    pub endpoints: Vec<Endpoint>,
+    /// Encrypt all connections with CurveZMQ and, optionally, accept only known clients
+    pub curve: Option<CurveServerConfig>,
+    /// Whether senders without name may talk to the Coordinator, e.g. before signing in
+    pub anonymous_policy: AnonymousPolicy,
+    /// Queue at most this many outgoing messages per Component, 0 is unlimited
+    ///
+    /// Messages to a Component with a full queue are dropped and their sender is told so.
+    pub send_high_water_mark: i32,
+}
+
+impl Default for CoordinatorConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(10),
+            expiry_timeout: Duration::from_secs(30),
+            limits: Limits::default(),
+            check_sender_namespace: true,
+            events_endpoint: None,
+            directory_max_age: Duration::from_secs(60),
+            max_components: 1000,
+            endpoints: Vec::new(),
+            curve: None,
+            anonymous_policy: AnonymousPolicy::default(),
+            send_high_water_mark: 1000,
+        }
+    }
+}
+
+/// CurveZMQ settings of a [`Coordinator`], see [`CoordinatorConfig::curve`]
+///
+/// The Components need the Coordinator's public key, see
+/// [`CurveClientKeys`](crate::core::CurveClientKeys). It requires libzmq built with CURVE.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CurveServerConfig {
+    pub public_key: [u8; 32],
+    pub secret_key: [u8; 32],
+    /// Public keys of the clients, which may connect, or `None` for any client
+    ///
+    /// The keys are checked by the routing loop, see [`Coordinator::routing`].
+    pub authorized_keys: Option<HashSet<[u8; 32]>>,
+}
+
+impl CurveServerConfig {
+    /// Read the Z85 encoded public keys from the files in `directory`.
+    ///
+    /// A file contains either just the key or a `public-key = "..."` line, like the certificates
+    /// of czmq do.
+    pub fn load_authorized_keys(directory: &Path) -> io::Result<HashSet<[u8; 32]>> {
+        let mut keys = HashSet::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)?;
+            let encoded = content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("public-key"))
+                .and_then(|rest| rest.trim().strip_prefix('='))
+                .map_or(content.trim(), |value| value.trim().trim_matches('"'));
+            let key = zmq::z85_decode(encoded)
+                .ok()
+                .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                .ok_or_else(|| {
+                    let message = format!("No valid public key in {}.", path.display());
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+            keys.insert(key);
+        }
+        Ok(keys)
+    }
+}
+
+impl fmt::Debug for CurveServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keep the secret key out of logs.
+        f.debug_struct("CurveServerConfig")
+            .field("public_key", &self.public_key)
+            .field("authorized_keys", &self.authorized_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Setting up the sockets of the [`Coordinator`] failed, see [`Coordinator::try_with_config`]
+#[derive(Debug)]
+pub enum SetupError {
+    /// Binding to `endpoint` failed, e.g. as it is in use
+    Bind {
+        endpoint: Endpoint,
+        source: zmq::Error,
+    },
+    /// Enabling CurveZMQ failed, e.g. as libzmq is built without CURVE
+    Curve(zmq::Error),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bind { endpoint, source } => write!(f, "Could not bind to {endpoint}: {source}"),
+            Self::Curve(source) => write!(f, "Could not enable CURVE: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bind { source, .. } | Self::Curve(source) => Some(source),
+        }
+    }
+}
+
+/// Counters of the Coordinator's traffic, reported by `send_statistics`
+#[derive(Debug, Default)]
+struct Statistics {
+    /// Messages forwarded to their receiver
+    routed: u64,
+    /// Error responses by error code
+    rejected: HashMap<i16, u64>,
+    /// Messages exceeding the size limit, even if the sender is unknown
+    oversized: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    /// Connections refused, as the client's CURVE key is not authorized
+    unauthorized: u64,
+    /// Other Coordinators, which did not answer pings in time
+    nodes_expired: u64,
+    /// Expired Coordinators, which accepted our sign-in again
+    nodes_reconnected: u64,
+    /// Messages not delivered, as the local receiver or the node disconnected or its queue was full
+    dropped: u64,
+}
+
+struct Component {
+    identity: Box<[u8]>,
+    timestamp: Instant,
+    /// When we pinged the silent Component, until it sends anything
+    pinged_at: Option<Instant>,
+    message_count: u64,
+    byte_count: u64,
+    /// Conversation id of our last ping, while it is not answered; earlier pings are forgotten
+    last_ping: Option<[u8; 16]>,
+}
+impl Component {
+    fn build(identity: &[u8]) -> Self {
+        Self {
+            identity: identity.into(),
+            timestamp: Instant::now(),
+            pinged_at: None,
+            message_count: 0,
+            byte_count: 0,
+            last_ping: None,
+        }
+    }
+
+    /// Record a message received from this Component.
+    fn record(&mut self, message: &Message) {
+        self.refresh();
+        self.message_count += 1;
+        self.byte_count += message.len_bytes() as u64;
+    }
+
+    /// Take note, that the Component is alive.
+    fn refresh(&mut self) {
+        self.timestamp = Instant::now();
+        self.pinged_at = None;
+    }
+
+    /// The traffic of the Component and when we heard from it last, as Unix time.
+    fn info(&self) -> Value {
+        json!({
+            "messages": self.message_count,
+            "bytes": self.byte_count,
+            "last_seen": unix_time() - self.timestamp.elapsed().as_secs_f64(),
+        })
+    }
+}
+
+/// How to treat messages with an empty sender frame, see [`CoordinatorConfig::anonymous_policy`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnonymousPolicy {
+    /// Reject all messages from anonymous senders
+    #[default]
+    Reject,
+    /// Allow anonymous senders to send requests to this Coordinator, without signing in
+    AllowToCoordinator,
+}
+
+/// Decide, who may shut down the Coordinator via a `shut_down` request.
+///
+/// Implement it to plug in site specific rules, see [`Coordinator::with_shutdown_policy`].
+pub trait ShutdownPolicy: Send {
+    /// Whether `sender` may shut down the Coordinator, given the request's `params`.
+    ///
+    /// `local` tells, whether the sender is signed in to this Coordinator.
+    fn may_shut_down(&self, sender: &FullName, local: bool, params: Option<&Value>) -> bool;
+}
+
+/// The default [`ShutdownPolicy`]: allow local Components on the allow list and anyone
+/// presenting the admin token as `{"token": ...}` in the params.
+///
+/// By default, the list is empty and there is no token, i.e. nobody may shut down.
+#[derive(Clone, Debug, Default)]
+pub struct AllowList {
+    names: HashSet<Vec<u8>>,
+    token: Option<String>,
+}
+
+impl AllowList {
+    /// Allow the local Components with these `names`.
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            names: names
+                .into_iter()
+                .map(|name| name.as_bytes().to_vec())
+                .collect(),
+            token: None,
+        }
+    }
+
+    /// Allow, additionally, anyone presenting `token`.
+    pub fn with_token(mut self, token: impl ToString) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+}
+
+impl ShutdownPolicy for AllowList {
+    fn may_shut_down(&self, sender: &FullName, local: bool, params: Option<&Value>) -> bool {
+        let token = params
+            .and_then(|params| params.get("token"))
+            .and_then(Value::as_str);
+        (local && self.names.contains(sender.name))
+            || (self.token.is_some() && token == self.token.as_deref())
+    }
+}
+
+/// Record of every message routed, one JSON object per line, see [`Coordinator::with_audit_log`]
+struct AuditLog {
+    writer: BufWriter<Box<dyn Write + Send>>,
+    last_flush: Instant,
+}
+
+impl AuditLog {
+    /// Write the buffered entries, if the last flush is long enough ago.
+    fn flush_periodically(&mut self) {
+        if self.last_flush.elapsed() >= AUDIT_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if let Err(err) = self.writer.flush() {
+            log::error!("Could not write the audit log: {err}");
+        }
+    }
+}
+
+/// Another Coordinator, to which messages for its namespace are forwarded
+struct RemoteNode {
+    /// Connected to the other Coordinator's ROUTER socket
+    socket: zmq::Socket,
+    endpoint: Endpoint,
+    /// Conversation id of our `coordinator_sign_in` request
+    sign_in_cid: Vec<u8>,
+    /// Conversation id of our last ping
+    ping_cid: Vec<u8>,
+    /// The node's Components as last reported or queried, and when
+    components: Option<(Value, Instant)>,
+    /// When we heard from the node last
+    timestamp: Instant,
+    /// When we pinged the silent node or, if unreachable, tried to sign in again
+    pinged_at: Option<Instant>,
+    /// Whether the node answers, otherwise messages for it are rejected
+    reachable: bool,
+}
+impl RemoteNode {
+    fn connect(context: &zmq::Context, endpoint: Endpoint) -> Result<Self, zmq::Error> {
+        let socket = context.socket(zmq::DEALER)?;
+        socket.set_linger(0)?;
+        socket.connect(&endpoint.to_string())?;
+        Ok(Self {
+            socket,
+            endpoint,
+            sign_in_cid: Vec::new(),
+            ping_cid: Vec::new(),
+            components: None,
+            timestamp: Instant::now(),
+            pinged_at: None,
+            reachable: true,
+        })
+    }
+
+    /// Take note, that the node is alive.
+    fn refresh(&mut self) {
+        self.timestamp = Instant::now();
+        self.pinged_at = None;
+    }
+}
+
+/// Routes messages between the Components signed in to it
+///
+/// Dropping it closes the sockets: the bound endpoint is released right away, while pending
+/// messages are still delivered.
+pub struct Coordinator {
+    namespace: Vec<u8>,
+    full_name: Vec<u8>,
+    context: zmq::Context,
+    router: zmq::Socket,
+    /// Where the router is bound to, with the actual port
+    endpoints: Vec<Endpoint>,
+    /// Receives the commands of the [`CoordinatorAdminHandle`], if created
+    admin: Option<zmq::Socket>,
+    /// Answers the authentication requests of zmq, if CURVE keys are to be checked
+    zap: Option<zmq::Socket>,
+    /// Publishes the Component events, if configured
+    events: Option<zmq::Socket>,
+    /// Signed in Components by name, looked up via borrowed names
+    components: HashMap<Box<[u8]>, Component>,
+    /// Other Coordinators by namespace
+    nodes: HashMap<Vec<u8>, RemoteNode>,
+    /// Namespaces of the Coordinators signed in to us, by socket identity
+    node_identities: HashMap<Vec<u8>, Vec<u8>>,
+    /// Endpoint announced to other Coordinators for connecting back
+    address: Option<Endpoint>,
+    config: CoordinatorConfig,
+    shutdown_policy: Box<dyn ShutdownPolicy>,
+    audit_log: Option<AuditLog>,
+    /// Names of the Components, whose messages are logged in full, see [`Coordinator::set_trace`]
+    trace: HashSet<Vec<u8>>,
+    statistics: Statistics,
+    started: Instant,
+    stop: StopSignal,
+}
+
+impl Coordinator {
+    /// Create a new Coordinator.
+    ///
+    /// For a port number of 0, it won't bind to any port at all!
+    /// Incoming messages exceeding the `limits` (by default [`Limits::default`]) are rejected.
+    pub fn new(name: String, port: Option<u16>, limits: Option<Limits>) -> Self {
+        let port = port.unwrap_or(12300);
+        let endpoint = Endpoint::Tcp {
+            host: "*".to_string(),
+            port,
+        };
+        Self::with_endpoint(name, (port != 0).then_some(&endpoint), None, limits)
+    }
+
+    /// Create a Coordinator bound to `endpoint` (if any).
+    ///
+    /// A tcp endpoint with port 0 binds to a free port, see [`Coordinator::endpoint`].
+    /// An `inproc` endpoint requires to share the `context` with the Communicators.
+    pub fn with_endpoint(
+        name: String,
+        endpoint: Option<&Endpoint>,
+        context: Option<zmq::Context>,
+        limits: Option<Limits>,
+    ) -> Self {
+        let config = CoordinatorConfig {
+            limits: limits.unwrap_or_default(),
+            ..Default::default()
+        };
+        Self::with_config(name, endpoint, context, config)
+    }
+
+    /// Create a Coordinator bound to `endpoint` (if any) with the settings of `config`.
+    ///
+    /// # Panics
+    ///
+    /// If binding to an endpoint fails, see [`Coordinator::try_with_config`].
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use ruleco::coordinator::{Coordinator, CoordinatorConfig};
+    /// let config = CoordinatorConfig {
+    ///     heartbeat_interval: Duration::from_secs(1),
+    ///     expiry_timeout: Duration::from_secs(3),
+    ///     ..Default::default()
+    /// };
+    /// let coordinator = Coordinator::with_config("N1".to_string(), None, None, config);
+    /// ```
+    pub fn with_config(
+        name: String,
+        endpoint: Option<&Endpoint>,
+        context: Option<zmq::Context>,
+        config: CoordinatorConfig,
+    ) -> Self {
+        Self::try_with_config(name, endpoint, context, config).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Coordinator::with_config`], but fail, if setting up the sockets fails.
+    ///
+    /// The router is bound to `endpoint` and the `endpoints` of the `config`, see
+    /// [`Coordinator::endpoints`]. With CURVE authorization, the Coordinator answers the
+    /// authentication requests of zmq for its whole `context`.
+    pub fn try_with_config(
+        name: String,
+        endpoint: Option<&Endpoint>,
+        context: Option<zmq::Context>,
+        config: CoordinatorConfig,
+    ) -> Result<Self, SetupError> {
+        let ctx = context.unwrap_or_default();
+        let router = ctx.socket(zmq::ROUTER).unwrap();
+        // Let zmq drop oversized frames before they are buffered, -1 is unlimited.
+        let max_message_size = i64::try_from(config.limits.max_bytes).unwrap_or(-1);
+        router.set_maxmsgsize(max_message_size).unwrap();
+        // Fail instead of silently dropping messages to unknown or congested peers.
+        router.set_router_mandatory(true).unwrap();
+        router.set_sndhwm(config.send_high_water_mark).unwrap();
+        // The authentication handler has to be bound before any client connects.
+        let zap = match &config.curve {
+            Some(curve) if curve.authorized_keys.is_some() => {
+                let socket = ctx.socket(zmq::REP).unwrap();
+                bind(&socket, &Endpoint::Inproc(ZAP_ENDPOINT.to_string()))?;
+                Some(socket)
+            }
+            _ => None,
+        };
+        if let Some(curve) = &config.curve {
+            make_curve_server(&router, curve)?;
+        }
+        let endpoints = endpoint
+            .into_iter()
+            .chain(&config.endpoints)
+            .map(|endpoint| {
+                bind(&router, endpoint)?;
+                Ok(bound_endpoint(endpoint, &router))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let events = match &config.events_endpoint {
+            Some(endpoint) => {
+                let socket = ctx.socket(zmq::PUB).unwrap();
+                if let Some(curve) = &config.curve {
+                    make_curve_server(&socket, curve)?;
+                }
+                bind(&socket, endpoint)?;
+                Some(socket)
+            }
+            None => None,
+        };
+        let components = HashMap::new();
+        let mut full_name = name.into_bytes();
+        let name_len = full_name.len();
+        full_name.extend_from_slice(b".COORDINATOR");
+        let namespace = full_name[..name_len].to_vec();
+        Ok(Self {
+            namespace,
+            context: ctx,
+            router,
+            admin: None,
+            zap,
+            events,
+            components,
+            nodes: HashMap::new(),
+            node_identities: HashMap::new(),
+            address: endpoints.first().map(connectable),
+            endpoints,
+            full_name,
+            config,
+            shutdown_policy: Box::new(AllowList::default()),
+            audit_log: None,
+            trace: HashSet::new(),
+            statistics: Statistics::default(),
+            started: Instant::now(),
+            stop: StopSignal::default(),
+        })
+    }
+
+    /// Like [`Coordinator::with_config`], but controllable via the returned handle.
+    pub fn with_admin(
+        name: String,
+        endpoint: Option<&Endpoint>,
+        context: Option<zmq::Context>,
+        config: CoordinatorConfig,
+    ) -> (Self, CoordinatorAdminHandle) {
+        let mut coordinator = Self::with_config(name, endpoint, context, config);
+        let admin_endpoint = format!("inproc://ruleco-admin-{}", Uuid::now_v7());
+        let admin = coordinator.context.socket(zmq::PAIR).unwrap();
+        admin.bind(&admin_endpoint).unwrap();
+        coordinator.admin = Some(admin);
+        let handle =
+            CoordinatorAdminHandle::connect(&coordinator.context, &admin_endpoint).unwrap();
+        (coordinator, handle)
+    }
+
+    /// The endpoint the Coordinator is bound to, with the actual port for port 0.
+    pub fn endpoint(&self) -> Option<&Endpoint> {
+        self.endpoints.first()
+    }
+
+    /// All endpoints the Coordinator is bound to, with the actual ports for port 0.
+    ///
+    /// The first one is announced to other Coordinators, unless [`Coordinator::set_address`]
+    /// is used.
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    /// Decide with `policy`, who may shut down the Coordinator via a request.
+    ///
+    /// By default, an empty [`AllowList`] denies it to everyone.
+    pub fn with_shutdown_policy(mut self, policy: impl ShutdownPolicy + 'static) -> Self {
+        self.shutdown_policy = Box::new(policy);
+        self
+    }
+
+    /// Record every routed message to `writer`, e.g. a file, as a line of JSON.
+    ///
+    /// Each line contains timestamp, sender, receiver, conversation id, message type,
+    /// payload size, and outcome, i.e. `"delivered"` or `"error"` with the error code.
+    /// A message, which is dropped afterwards as its receiver is unreachable, gets another entry
+    /// with the error. The entries are buffered and written about every second, not blocking the routing.
+    pub fn with_audit_log(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.audit_log = Some(AuditLog {
+            writer: BufWriter::new(Box::new(writer)),
+            last_flush: Instant::now(),
+        });
+        self
+    }
+
+    /// Log every message from or to the Components `names` with [`Message::pretty`].
+    ///
+    /// The records are logged at info level with the target [`TRACE_TARGET`]. A name matches
+    /// with or without namespace. An empty set of names disables tracing.
+    pub fn set_trace<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        self.trace = names
+            .into_iter()
+            .map(|name| name.as_bytes().to_vec())
+            .collect();
+        if self.trace.is_empty() {
+            log::info!("Tracing disabled.");
+        } else {
+            let mut names: Vec<_> = self
+                .trace
+                .iter()
+                .map(|n| String::from_utf8_lossy(n))
+                .collect();
+            names.sort();
+            log::info!("Tracing {}.", names.join(", "));
+        }
+    }
+
+    /// Log `message` in full, if its sender or receiver is traced.
+    fn trace(&self, direction: &str, message: &Message) {
+        if self.trace.is_empty() {
+            return;
+        }
+        let traced = |frame: &Vec<u8>| {
+            self.trace.contains(frame)
+                || FullName::from_vec(frame).is_ok_and(|name| self.trace.contains(name.name))
+        };
+        if traced(message.sender_frame()) || traced(message.receiver_frame()) {
+            log::info!(target: TRACE_TARGET, "Trace {direction} {}", message.pretty());
+        }
+    }
+
+    /// Announce `address` to other Coordinators instead of the bound endpoint.
+    ///
+    /// Set it, if the Coordinator is bound to all interfaces or is behind NAT.
+    pub fn set_address(&mut self, address: Endpoint) {
+        self.address = Some(address);
+    }
+
+    /// Connect to the Coordinator of `namespace` at `endpoint` and sign in to it.
+    ///
+    /// Messages for that namespace are forwarded to it. An existing connection to the
+    /// namespace is replaced. The other Coordinator connects back, if it does not know us yet.
+    pub fn add_node(&mut self, namespace: &str, endpoint: Endpoint) -> Result<(), zmq::Error> {
+        let mut node = RemoteNode::connect(&self.context, endpoint)?;
+        self.send_node_sign_in(&mut node)?;
+        self.nodes.insert(namespace.as_bytes().to_vec(), node);
+        Ok(())
+    }
+
+    /// Send our `coordinator_sign_in` request to `node`.
+    fn send_node_sign_in(&self, node: &mut RemoteNode) -> Result<(), zmq::Error> {
+        let params = self
+            .address
+            .as_ref()
+            .map(|address| json!({"address": address.to_string()}));
+        let (request, cid) = Message::build_request(
+            b"COORDINATOR".to_vec(),
+            self.full_name.clone(),
+            0,
+            "coordinator_sign_in",
+            params,
+        );
+        node.sign_in_cid = cid.to_vec();
+        node.socket
+            .send_multipart(request.into_frames(), zmq::DONTWAIT)
+    }
+
+    /// Sign out from the Coordinator of `namespace` and close the connection.
+    pub fn remove_node(&mut self, namespace: &[u8]) {
+        let Some(node) = self.nodes.remove(namespace) else {
+            return;
+        };
+        let (request, _cid) = Message::build_request(
+            b"COORDINATOR".to_vec(),
+            self.full_name.clone(),
+            0,
+            "coordinator_sign_out",
+            None,
+        );
+        let _ = node.socket.set_linger(SIGN_OUT_LINGER_MS);
+        // Do not block, if the other Coordinator is gone already.
+        let _ = node
+            .socket
+            .send_multipart(request.to_frames(), zmq::DONTWAIT);
+    }
+
+    /// Start a continuous loop routing messages until shut down or stopped.
+    ///
+    /// Silent Components are pinged and, if they stay silent, removed in between.
+    /// Afterwards, the Components are notified and the other Coordinators signed out from.
+    pub fn routing(&mut self) {
+        let mut last_check = Instant::now();
+        // Check twice per ping interval, such that silent Components are pinged in time.
+        let check_interval = self.config.heartbeat_interval / 2;
+        let poll_timeout = POLL_INTERVAL_MS.min(check_interval.as_millis() as i64);
+        while !self.stop.is_stopped() {
+            self.poll_sockets(poll_timeout);
+            if last_check.elapsed() >= check_interval {
+                last_check = Instant::now();
+                self.check_timeouts();
+            }
+            if let Some(audit_log) = &mut self.audit_log {
+                audit_log.flush_periodically();
+            }
+        }
+        self.close();
+    }
+
+    /// Get a signal to stop [`Coordinator::routing`] from another thread.
+    ///
+    /// The loop stops within one poll interval, without any message being sent.
+    pub fn stop_signal(&self) -> StopSignal {
+        self.stop.clone()
+    }
+
+    /// Stop routing, like a `shut_down` request does.
+    pub fn stop(&self) {
+        self.stop.stop();
+    }
+
+    /// Notify all Components of the shut down and sign out from the other Coordinators.
+    ///
+    /// The router socket is closed when the Coordinator is dropped, but gets some time to send
+    /// the notifications.
+    fn close(&mut self) {
+        log::info!("Shutting down.");
+        let notification = to_vec(&json!({"jsonrpc": "2.0", "method": "coordinator_shutdown"}));
+        let notifications: Vec<_> = self
+            .components
+            .iter()
+            .map(|(name, component)| MessageContainer {
+                identity: component.identity.clone(),
+                message: Message::build(
+                    compose_full_name(&self.namespace, name),
+                    self.full_name.clone(),
+                    None,
+                    None,
+                    message_types::JSON,
+                    ContentTypes::Frame(notification.clone()),
+                ),
+            })
+            .collect();
+        for msg_cont in notifications {
+            self.send_local_message(msg_cont);
+        }
+        let namespaces: Vec<Vec<u8>> = self.nodes.keys().cloned().collect();
+        for namespace in namespaces {
+            self.remove_node(&namespace);
+        }
+        self.node_identities.clear();
+        if let Some(audit_log) = &mut self.audit_log {
+            audit_log.flush();
+        }
+        let _ = self.router.set_linger(SIGN_OUT_LINGER_MS);
+    }
+
+    /// Wait up to `timeout_ms` for messages at the router and node sockets and handle them.
+    fn poll_sockets(&mut self, timeout_ms: i64) {
+        let namespaces: Vec<Vec<u8>> = self.nodes.keys().cloned().collect();
+        let readable: Vec<bool> = {
+            let mut items = vec![self.router.as_poll_item(zmq::POLLIN)];
+            items.extend(
+                namespaces
+                    .iter()
+                    .map(|namespace| self.nodes[namespace].socket.as_poll_item(zmq::POLLIN)),
+            );
+            if let Some(admin) = &self.admin {
+                items.push(admin.as_poll_item(zmq::POLLIN));
+            }
+            if let Some(zap) = &self.zap {
+                items.push(zap.as_poll_item(zmq::POLLIN));
+            }
+            if zmq::poll(&mut items, timeout_ms).is_err() {
+                return;
+            }
+            items.iter().map(zmq::PollItem::is_readable).collect()
+        };
+        if readable[0] {
+            self.loop_element();
+        }
+        for (namespace, readable) in namespaces.iter().zip(&readable[1..]) {
+            if *readable {
+                self.read_node_message(namespace);
+            }
+        }
+        let mut extra = readable[namespaces.len() + 1..].iter();
+        if self.admin.is_some() && extra.next() == Some(&true) {
+            self.read_admin_command();
+        }
+        if self.zap.is_some() && extra.next() == Some(&true) {
+            self.read_zap_request();
+        }
+    }
+
+    /// Answer an authentication request of zmq, whether the client's CURVE key is authorized.
+    fn read_zap_request(&mut self) {
+        let Some(zap) = &self.zap else {
+            return;
+        };
+        let Ok(request) = zap.recv_multipart(zmq::DONTWAIT) else {
+            return;
+        };
+        // version, request id, domain, address, routing id, mechanism, and the client's key
+        let frame = |index: usize| request.get(index).map(Vec::as_slice).unwrap_or_default();
+        let authorized_keys = self
+            .config
+            .curve
+            .as_ref()
+            .and_then(|curve| curve.authorized_keys.as_ref());
+        let authorized = frame(5) == b"CURVE"
+            && <[u8; 32]>::try_from(frame(6))
+                .is_ok_and(|key| authorized_keys.is_some_and(|keys| keys.contains(&key)));
+        let (status, text): (&[u8], &[u8]) = match authorized {
+            true => (b"200", b"OK"),
+            false => {
+                log::warn!(
+                    "Refused a connection from {} with an unauthorized key.",
+                    String::from_utf8_lossy(frame(3))
+                );
+                self.statistics.unauthorized += 1;
+                (b"400", b"Unauthorized key")
+            }
+        };
+        let reply: [&[u8]; 6] = [b"1.0", frame(1), status, text, b"", b""];
+        if let Err(err) = zap.send_multipart(reply, 0) {
+            log::error!("Could not answer an authentication request: {err}");
+        }
+    }
+
+    /// Handle a command from the admin socket and send the answer.
+    fn read_admin_command(&mut self) {
+        let Some(Ok(bytes)) = self
+            .admin
+            .as_ref()
+            .map(|admin| admin.recv_bytes(zmq::DONTWAIT))
+        else {
+            return;
+        };
+        let answer = match serde_json::from_slice::<AdminRequest>(&bytes) {
+            Ok(request) => AdminAnswer {
+                id: request.id,
+                result: self.handle_admin_command(request.command),
+            },
+            Err(err) => {
+                log::warn!("Invalid admin command: {err}");
+                // The id is unknown, such that the handle does not take it for an answer.
+                AdminAnswer {
+                    id: 0,
+                    result: Err(format!("invalid command: {err}")),
+                }
+            }
+        };
+        if let Some(admin) = &self.admin {
+            if let Err(err) = admin.send(to_vec(&answer), zmq::DONTWAIT) {
+                log::warn!("Could not answer the admin command: {err}");
+            }
+        }
+    }
+
+    fn handle_admin_command(&mut self, command: AdminCommand) -> Result<Value, String> {
+        Ok(match command {
+            AdminCommand::Stop => {
+                log::info!("Stop requested via the admin socket.");
+                self.stop();
+                Value::Null
+            }
+            AdminCommand::Stats => self
+                .send_statistics(None)
+                .map_err(|err| err.message().to_string())?,
+            AdminCommand::ListComponents => self.local_components(),
+            AdminCommand::Kick(name) => json!(self.kick(name.as_bytes())),
+            AdminCommand::Trace(names) => {
+                self.set_trace(names.iter().map(String::as_str));
+                Value::Null
+            }
+        })
+    }
+
+    /// Remove the Component `name` and return whether it was signed in.
+    fn kick(&mut self, name: &[u8]) -> bool {
+        let kicked = self.components.remove(name).is_some();
+        if kicked {
+            log::info!("{} kicked.", String::from_utf8_lossy(name));
+            self.publish_event("kicked", name);
+            self.push_components();
+        }
+        kicked
+    }
+
+    /// Handle a message from the node socket of `namespace`, i.e. a response to our requests.
+    fn read_node_message(&mut self, namespace: &[u8]) {
+        if let Some(message) = self.receive_node_message(namespace, 0) {
+            self.handle_node_message(namespace, message);
+        }
+    }
+
+    /// Receive a message from the node socket of `namespace`, waiting up to `timeout_ms`.
+    fn receive_node_message(&self, namespace: &[u8], timeout_ms: i64) -> Option<Message> {
+        let node = self.nodes.get(namespace)?;
+        if node.socket.poll(zmq::POLLIN, timeout_ms).ok()? == 0 {
+            return None;
+        }
+        let frames = node.socket.recv_multipart(zmq::DONTWAIT).ok()?;
+        Message::new(frames).ok()
+    }
+
+    fn handle_node_message(&mut self, namespace: &[u8], message: Message) {
+        let Some(node) = self.nodes.get_mut(namespace) else {
+            return;
+        };
+        node.refresh();
+        let sign_in = message.header().conversation_id == node.sign_in_cid.as_slice();
+        let ping = message.header().conversation_id == node.ping_cid.as_slice();
+        let namespace_str = String::from_utf8_lossy(namespace).to_string();
+        let content = message
+            .content_frame()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if sign_in {
+            // Rejections are logged there.
+            let _ = self.handle_node_sign_in(namespace, content);
+            return;
+        }
+        match serde_json::from_slice::<json::ResponseContent>(content) {
+            Ok(json::ResponseContent::Result(_)) => {}
+            Ok(json::ResponseContent::Error(response)) if ping => {
+                // E.g. the node restarted in between and does not know us anymore.
+                log::warn!(
+                    "Node {namespace_str} rejected our ping: {}",
+                    response.error.message
+                );
+                self.expire_node(namespace);
+                self.reconnect_node(namespace);
+            }
+            Ok(json::ResponseContent::Error(response)) => {
+                log::warn!("Node {namespace_str} answered: {}", response.error.message);
+            }
+            Err(_) => log::warn!("Unexpected message from node {namespace_str}."),
+        }
+    }
+
+    /// Handle the `content` of the answer to our `coordinator_sign_in` at the node `namespace`.
+    ///
+    /// A node, which rejects us or turns out to have our namespace, is removed.
+    fn handle_node_sign_in(
+        &mut self,
+        namespace: &[u8],
+        content: &[u8],
+    ) -> Result<(), json::ErrorContent> {
+        let namespace_str = String::from_utf8_lossy(namespace).to_string();
+        match serde_json::from_slice::<json::ResponseContent>(content) {
+            Ok(json::ResponseContent::Result(response))
+                if response.result["namespace"].as_str().map(str::as_bytes)
+                    == Some(self.namespace.as_slice()) =>
+            {
+                log::error!("Node {namespace_str} has our namespace, check the node addresses!");
+                self.remove_node(namespace);
+                Err(json::ErrorContent::from(&namespace_conflict_error(
+                    &self.namespace,
+                )))
+            }
+            Ok(json::ResponseContent::Result(_)) => {
+                log::info!("Signed in to node {namespace_str}.");
+                if let Some(node) = self.nodes.get_mut(namespace) {
+                    if !node.reachable {
+                        log::info!("Reconnected to node {namespace_str}.");
+                        node.reachable = true;
+                        self.statistics.nodes_reconnected += 1;
+                    }
+                }
+                if let Some(node) = self.nodes.get(namespace) {
+                    self.push_components_to(namespace, node);
+                }
+                Ok(())
+            }
+            Ok(json::ResponseContent::Error(response)) => {
+                log::warn!(
+                    "Node {namespace_str} rejected us: {}",
+                    response.error.message
+                );
+                self.nodes.remove(namespace);
+                Err(response.error)
+            }
+            Err(_) => {
+                log::warn!("Unexpected message from node {namespace_str}.");
+                Ok(())
+            }
+        }
+    }
+
+    /// Read one message (waiting until one arrives) and route it.
+    pub fn loop_element(&mut self) {
+        let msg_cont = match self.read_message() {
+            Ok(msg_cont) => msg_cont,
+            Err(_err) => return,
+        };
+        if let Some(s_m_c) = self.route_message(msg_cont) {
+            self.send_routed_message(s_m_c)
+        }
+    }
+
+    /// Route `message` received via `identity` like [`Coordinator::loop_element`], but return
+    /// the message to be sent instead of sending it.
+    ///
+    /// Public only for benchmarking the routing without sockets.
+    #[doc(hidden)]
+    pub fn route(&mut self, identity: Vec<u8>, message: Message) -> Option<Message> {
+        self.route_message(MessageContainer { identity, message })
+            .map(|s_cont| s_cont.msg_cont.message)
+    }
+
+    fn read_message(&mut self) -> Result<MessageContainer<Vec<u8>>, io::Error> {
+        let identity = self.router.recv_bytes(0)?;
+        let frames = self.router.recv_multipart(0)?;
+        let bytes = frames.iter().map(Vec::len).sum::<usize>();
+        self.statistics.bytes_in += bytes as u64;
+        if bytes > self.config.limits.max_bytes {
+            self.statistics.oversized += 1;
+            let error = oversize_error(bytes, self.config.limits.max_bytes);
+            let err = io::Error::new(io::ErrorKind::InvalidInput, error.message().to_string());
+            if let Some(message) = self.create_rejection(&frames, error) {
+                self.send_local_message(MessageContainer { identity, message });
+            }
+            return Err(err);
+        }
+        if let Err(err) = self.config.limits.check(&frames) {
+            if let Some(message) = self.create_rejection(&frames, Error::InvalidRequest) {
+                self.send_local_message(MessageContainer { identity, message });
+            }
+            return Err(err);
+        }
+        // The layout of the other frames depends on the version, so check it first.
+        let version = frames.first().and_then(|frame| frame.first()).copied();
+        if !version.is_some_and(is_compatible_version) {
+            let error = version_error(version);
+            let err = io::Error::new(io::ErrorKind::InvalidData, error.message().to_string());
+            if let Some(message) = self.create_rejection(&frames, error) {
+                self.send_local_message(MessageContainer { identity, message });
+            }
+            return Err(err);
+        }
+        if let Err(err) = Message::check_layout(&frames) {
+            if let Some(message) = self.create_rejection(&frames, Error::InvalidRequest) {
+                self.send_local_message(MessageContainer { identity, message });
+            }
+            return Err(err);
+        }
+        let message = Message::new_limited(frames, &self.config.limits)?;
+        Ok(MessageContainer { identity, message })
+    }
+
+    /// Create an error response for frames, which are not read as message, if the sender is
+    /// known.
+    fn create_rejection(&mut self, frames: &[Vec<u8>], error: Error) -> Option<Message> {
+        let sender = frames.get(2)?;
+        let conversation_id = frames.get(3).and_then(|header| header.get(..16));
+        // The content is not parsed for the id, as it might be oversized or of another version.
+        Some(self.create_error(sender.to_vec(), error, conversation_id, None))
+    }
+
+    /// Take a MessageContainer and handle it until it is ready to be sent.
+    ///
+    /// This method does everything short of reading and sending a message.
+    fn route_message(
+        &mut self,
+        msg_cont: MessageContainer<Vec<u8>>,
+    ) -> Option<SendingContainer<Vec<u8>>> {
+        let identity = msg_cont.identity;
+        let mut message = msg_cont.message;
+        let (sender_name, mut receiver_name) = match (message.sender(), message.receiver()) {
+            (Ok(sender_name), Ok(receiver_name)) => (sender_name, receiver_name),
+            (sender, _) => {
+                // Name the malformed frame, as the parse error does not tell.
+                let (frame, name) = match sender {
+                    Err(_) => ("sender", message.sender_frame()),
+                    Ok(_) => ("receiver", message.receiver_frame()),
+                };
+                let name = String::from_utf8_lossy(name).to_string();
+                log::debug!("Malformed {frame} name '{name}'.");
+                self.audit(&message, Some(&Error::InvalidRequest));
+                let message = self.create_error_with_data(
+                    message.sender_frame().to_vec(),
+                    Error::InvalidRequest,
+                    Some(message.header().conversation_id),
+                    request_id(&message),
+                    Some(json!({ frame: name })),
+                );
+                return Some(SendingContainer {
+                    receiving_namespace: Vec::new(),
+                    msg_cont: MessageContainer { identity, message },
+                });
+            }
+        };
+        self.trace("in", &message);
+        log::trace!(
+            "Routing message {} from {} to {}",
+            Uuid::from_slice(message.header().conversation_id).unwrap_or_default(),
+            String::from_utf8_lossy(message.sender_frame()),
+            String::from_utf8_lossy(message.receiver_frame()),
+        );
+        let valid = self.check_message(&identity, &message, &sender_name, &receiver_name);
+        match valid {
+            Err(error) => {
+                self.audit(&message, Some(&error));
+                let message = self.create_error(
+                    message.sender_frame().to_vec(),
+                    error,
+                    Some(message.header().conversation_id),
+                    request_id(&message),
+                );
+                Some(SendingContainer {
+                    receiving_namespace: Vec::new(),
+                    msg_cont: MessageContainer { identity, message },
+                })
+            }
+            Ok(()) => {
+                let for_coordinator = message.is_for_coordinator(&self.namespace);
+                if for_coordinator {
+                    // Other Coordinators read the answers to their requests at their node
+                    // sockets, which may not be reachable before the handshake is complete.
+                    let local = sender_name.namespace.is_empty()
+                        || sender_name.namespace == &self.namespace[..]
+                        || sender_name.name == b"COORDINATOR";
+                    self.audit(&message, None);
+                    if message
+                        .content_frame()
+                        .is_some_and(|content| json::is_response(content))
+                    {
+                        // Responses are never answered, lest two parties exchange errors.
+                        self.handle_response(&message, &sender_name, local);
+                        return None;
+                    }
+                    message = self.handle_message_content(&message, &sender_name);
+                    if local {
+                        // Reply via the identity, as anonymous or just signed out senders are not
+                        // in the components table.
+                        return Some(SendingContainer {
+                            receiving_namespace: Vec::new(),
+                            msg_cont: MessageContainer { identity, message },
+                        });
+                    }
+                    // find somehow the routing stuff
+                    // The response is addressed to the (valid) sender of the request.
+                    receiver_name = message.receiver().ok()?;
+                } else if sender_name.namespace.is_empty() {
+                    // Complete the sender's full name with our namespace.
+                    let full_sender = compose_full_name(&self.namespace, sender_name.name);
+                    message.set_sender(full_sender);
+                    receiver_name = message.receiver().ok()?;
+                }
+                let from_node = self.node_identities.contains_key(&identity);
+                let route = match self.find_routing_information(&receiver_name) {
+                    // Messages forwarded by another Coordinator go to our Components only, lest
+                    // inconsistent node tables bounce them between Coordinators forever.
+                    Ok((namespace, _))
+                        if from_node && !for_coordinator && !namespace.is_empty() =>
+                    {
+                        Err(Error::NodeUnknown)
+                    }
+                    route => route,
+                };
+                match route {
+                    Err(error) => {
+                        self.audit(&message, Some(&error));
+                        // Tell a remote sender via our node, as Coordinators do not forward the
+                        // responses they read at their node sockets, others via their identity.
+                        let receiving_namespace = match message.sender() {
+                            Ok(sender)
+                                if from_node && self.nodes.contains_key(sender.namespace) =>
+                            {
+                                sender.namespace.to_vec()
+                            }
+                            _ => Vec::new(),
+                        };
+                        let message = self.create_error(
+                            message.sender_frame().to_vec(),
+                            error,
+                            Some(message.header().conversation_id),
+                            request_id(&message),
+                        );
+                        Some(SendingContainer {
+                            receiving_namespace,
+                            msg_cont: MessageContainer { identity, message },
+                        })
+                    }
+                    Ok((namespace, receiver_identity)) => {
+                        let receiving_namespace = namespace.to_vec();
+                        // Reuse the buffer of the sender's identity, which is of the same size
+                        // for the router's generated identities.
+                        let mut identity = identity;
+                        identity.clear();
+                        identity.extend_from_slice(receiver_identity);
+                        self.statistics.routed += 1;
+                        self.audit(&message, None);
+                        Some(SendingContainer {
+                            receiving_namespace,
+                            msg_cont: MessageContainer { identity, message },
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the correct namespace and identity of the receiver or raise an error.
+    ///
+    /// The namespace is empty for local receivers, the identity for remote ones.
+    fn find_routing_information<'a>(
+        &'a self,
+        receiver_name: &FullName<'a>,
+    ) -> Result<(&'a [u8], &'a [u8]), Error> {
+        if receiver_name.namespace == self.namespace || receiver_name.namespace.is_empty() {
+            match self.components.get(receiver_name.name) {
+                Some(comp) => Ok((&[], &comp.identity)),
+                None => Err(Error::ReceiverUnknown),
+            }
+        } else if self
+            .nodes
+            .get(receiver_name.namespace)
+            .is_some_and(|node| node.reachable)
+        {
+            Ok((receiver_name.namespace, &[]))
+        } else {
+            Err(Error::NodeUnknown)
+        }
+    }
+
+    /// Send a message once valid receiver information has been found
+    fn send_routed_message<T: zmq::Sendable>(&mut self, s_cont: SendingContainer<T>) {
+        if s_cont.receiving_namespace.is_empty() {
+            self.send_local_message(s_cont.msg_cont)
+        } else if let Some(node) = self.nodes.get(&s_cont.receiving_namespace) {
+            let message = s_cont.msg_cont.message;
+            self.trace("out", &message);
+            // Like the router, the node socket refuses the first frame without blocking, if its
+            // queue is full, e.g. as the other Coordinator is gone.
+            let version = message.to_frames()[0].as_slice();
+            if let Err(err) = node.socket.send(version, zmq::SNDMORE | zmq::DONTWAIT) {
+                self.receiver_unreachable(message, err);
+                return;
+            }
+            self.statistics.bytes_out += message.len_bytes() as u64;
+            let frames = message.into_frames().into_iter().skip(1);
+            if let Err(err) = node.socket.send_multipart(frames, zmq::DONTWAIT) {
+                log::error!("Could not forward message to {}: {err}", node.endpoint);
+            }
+        }
+    }
+
+    /// Check whether the message is from a signed_in Component or signing in.
+    fn check_message(
+        &mut self,
+        identity: &[u8],
+        message: &Message,
+        sender_name: &FullName,
+        receiver_name: &FullName,
+    ) -> Result<(), Error> {
+        if receiver_name.name.is_empty() {
+            return Err(Error::InvalidRequest);
+        }
+        let remote = !sender_name.namespace.is_empty() && sender_name.namespace != self.namespace;
+        if self.config.check_sender_namespace {
+            self.check_sender_namespace(identity, sender_name, remote)?;
+        }
+        if remote {
+            if self.node_identities.get(identity).map(Vec::as_slice) == Some(sender_name.namespace)
+            {
+                // Forwarded by a signed in Coordinator.
+                return Ok(());
+            }
+            let content = message.content_frame().map(Vec::as_slice);
+            return match (receiver_name.name, content) {
+                (b"COORDINATOR", Some(content))
+                    if is_request_for(content, "coordinator_sign_in") =>
+                {
+                    self.coordinator_sign_in(identity, sender_name.namespace, content);
+                    Ok(())
+                }
+                _ => Err(Error::NodeUnknown),
+            };
+        }
+        let content = message.content_frame().map(Vec::as_slice);
+        if sender_name.name == b"COORDINATOR"
+            && content.is_some_and(|content| is_request_for(content, "coordinator_sign_in"))
+        {
+            log::error!(
+                "A Coordinator with our namespace {} tried to sign in, check the node addresses!",
+                String::from_utf8_lossy(&self.namespace)
+            );
+            return Err(namespace_conflict_error(&self.namespace));
+        }
+        let sender = sender_name.name;
+        if sender.is_empty() {
+            return match self.config.anonymous_policy {
+                AnonymousPolicy::AllowToCoordinator
+                    if message.is_for_coordinator(&self.namespace) =>
+                {
+                    Ok(())
+                }
+                _ => Err(Error::InvalidRequest),
+            };
+        }
+        let component = self.components.get_mut(sender);
+        match component {
+            Some(component) => {
+                if *component.identity == *identity {
+                    component.record(message);
+                    Ok(())
+                } else {
+                    Err(Error::DuplicateName)
+                }
+            }
+            None => {
+                let content = message.content_frame().map(Vec::as_slice);
+                match (receiver_name.name, content) {
+                    (b"COORDINATOR", Some(content)) if is_sign_in(content) => {
+                        self.sign_in(identity, sender_name)
+                    }
+                    // Allow probing and pinging this Coordinator before signing in, but not
+                    // routing anything elsewhere.
+                    (_, Some(content))
+                        if message.is_for_coordinator(&self.namespace)
+                            && (is_request_for(content, "coordinator_info")
+                                || is_request_for(content, "pong")) =>
+                    {
+                        Ok(())
+                    }
+                    _ => Err(Error::NotSignedIn),
+                }
+            }
+        }
+    }
+
+    /// Check that the sender's namespace fits the connection, the message arrived by.
+    ///
+    /// Other Coordinators have to send with their own namespace, local Components with none
+    /// or ours.
+    fn check_sender_namespace(
+        &self,
+        identity: &[u8],
+        sender_name: &FullName,
+        remote: bool,
+    ) -> Result<(), Error> {
+        match self.node_identities.get(identity) {
+            Some(namespace) if namespace != sender_name.namespace => Err(Error::InvalidRequest),
+            None if remote
+                && self
+                    .components
+                    .values()
+                    .any(|comp| *comp.identity == *identity) =>
+            {
+                Err(Error::InvalidRequest)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Take note of a response to our requests, e.g. to a ping.
+    fn handle_response(&mut self, message: &Message, sender_name: &FullName, local: bool) {
+        let cid = message.header().conversation_id;
+        let ping = local
+            && self
+                .components
+                .get_mut(sender_name.name)
+                .and_then(|component| component.last_ping.take_if(|ping| ping == cid))
+                .is_some();
+        if ping {
+            // The timestamp is already refreshed by `check_message`.
+            log::trace!(
+                "{} answered the ping.",
+                String::from_utf8_lossy(sender_name.name)
+            );
+        } else {
+            log::warn!(
+                "Unexpected response {} from {}.",
+                Uuid::from_slice(cid).unwrap_or_default(),
+                String::from_utf8_lossy(message.sender_frame())
+            );
+        }
+    }
+
+    fn send_local_ping(&mut self, identity: &[u8], name: &[u8]) {
+        let (message, cid) =
+            Message::build_request(name.to_vec(), self.full_name.clone(), 0, "pong", None);
+        if let Some(component) = self.components.get_mut(name) {
+            component.last_ping = Some(cid);
+            component.pinged_at = Some(Instant::now());
+        }
+        let msg_cont = MessageContainer { identity, message };
+        self.send_local_message(msg_cont);
+    }
+
+    /// Remove Components, which did not answer a ping in time, and ping the silent ones.
+    ///
+    /// Any message from a Component counts as an answer.
+    fn check_timeouts(&mut self) {
+        let heartbeat = self.config.heartbeat_interval;
+        let expiry = self.config.expiry_timeout;
+        let mut expired = Vec::new();
+        self.components.retain(|name, comp: &mut Component| {
+            let unanswered = comp
+                .pinged_at
+                .is_some_and(|pinged_at| pinged_at.elapsed() >= heartbeat);
+            let alive = !unanswered || comp.timestamp.elapsed() < expiry;
+            if !alive {
+                log::info!("{} timed out.", String::from_utf8_lossy(name));
+                expired.push(name.clone());
+            }
+            alive
+        });
+        for name in &expired {
+            self.publish_event("expired", name);
+        }
+        if !expired.is_empty() {
+            self.push_components();
+        }
+        self.check_node_timeouts();
+        // Ping again, if the last ping is unanswered for a heartbeat interval.
+        let silent: Vec<_> = self
+            .components
+            .iter()
+            .filter(|(_, comp)| {
+                comp.timestamp.elapsed() >= heartbeat
+                    && comp
+                        .pinged_at
+                        .is_none_or(|pinged_at| pinged_at.elapsed() >= heartbeat)
+            })
+            .map(|(name, comp)| (comp.identity.clone(), name.clone()))
+            .collect();
+        for (identity, name) in silent {
+            self.send_local_ping(&identity, &name);
+        }
+    }
+
+    /// Like [`Coordinator::check_timeouts`] for the nodes, but expired nodes are kept as
+    /// unreachable and we try to sign in to them again every heartbeat interval.
+    fn check_node_timeouts(&mut self) {
+        let heartbeat = self.config.heartbeat_interval;
+        let expiry = self.config.expiry_timeout;
+        let mut expired = Vec::new();
+        let mut silent = Vec::new();
+        let mut unreachable = Vec::new();
+        for (namespace, node) in self.nodes.iter() {
+            let unanswered = node
+                .pinged_at
+                .is_none_or(|pinged_at| pinged_at.elapsed() >= heartbeat);
+            if !node.reachable {
+                if unanswered {
+                    unreachable.push(namespace.clone());
+                }
+            } else if node.pinged_at.is_some() && unanswered && node.timestamp.elapsed() >= expiry {
+                expired.push(namespace.clone());
+            } else if unanswered && node.timestamp.elapsed() >= heartbeat {
+                silent.push(namespace.clone());
+            }
+        }
+        for namespace in &expired {
+            self.expire_node(namespace);
+        }
+        for namespace in &silent {
+            self.send_node_ping(namespace);
+        }
+        for namespace in &unreachable {
+            self.reconnect_node(namespace);
+        }
+    }
+
+    fn send_node_ping(&mut self, namespace: &[u8]) {
+        let (request, cid) = Message::build_request(
+            compose_full_name(namespace, b"COORDINATOR"),
+            self.full_name.clone(),
+            0,
+            "pong",
+            None,
+        );
+        let Some(node) = self.nodes.get_mut(namespace) else {
+            return;
+        };
+        node.ping_cid = cid.to_vec();
+        node.pinged_at = Some(Instant::now());
+        // Do not block, if the node is gone.
+        if let Err(err) = node
+            .socket
+            .send_multipart(request.into_frames(), zmq::DONTWAIT)
+        {
+            log::debug!("Could not ping node {}: {err}", node.endpoint);
+        }
+    }
+
+    /// Mark the node of `namespace` unreachable, until it accepts our sign-in again.
+    fn expire_node(&mut self, namespace: &[u8]) {
+        let Some(node) = self.nodes.get_mut(namespace) else {
+            return;
+        };
+        log::warn!("Node {} timed out.", String::from_utf8_lossy(namespace));
+        node.reachable = false;
+        node.components = None;
+        node.pinged_at = Some(Instant::now());
+        self.statistics.nodes_expired += 1;
+    }
+
+    /// Sign in to the unreachable node of `namespace` again, via a new connection.
+    fn reconnect_node(&mut self, namespace: &[u8]) {
+        let Some(node) = self.nodes.get(namespace) else {
+            return;
+        };
+        log::debug!(
+            "Trying to reconnect to node {} at {}.",
+            String::from_utf8_lossy(namespace),
+            node.endpoint
+        );
+        let result =
+            RemoteNode::connect(&self.context, node.endpoint.clone()).and_then(|mut fresh| {
+                fresh.reachable = false;
+                fresh.pinged_at = Some(Instant::now());
+                self.send_node_sign_in(&mut fresh)?;
+                Ok(fresh)
+            });
+        let Some(node) = self.nodes.get_mut(namespace) else {
+            return;
+        };
+        match result {
+            // Dropping the old socket discards the messages queued for the node.
+            Ok(fresh) => *node = fresh,
+            Err(err) => {
+                node.pinged_at = Some(Instant::now());
+                log::warn!("Could not reconnect to node {}: {err}", node.endpoint);
+            }
+        }
+    }
+
+    /// Record the outcome of routing `message` in the audit log, if any.
+    fn audit(&mut self, message: &Message, error: Option<&Error>) {
+        let Some(audit_log) = &mut self.audit_log else {
+            return;
+        };
+        let header = message.header();
+        let mut entry = json!({
+            "timestamp": unix_time(),
+            "sender": String::from_utf8_lossy(message.sender_frame()),
+            "receiver": String::from_utf8_lossy(message.receiver_frame()),
+            "conversation_id": Uuid::from_slice(header.conversation_id).unwrap_or_default().to_string(),
+            "message_type": header.message_type,
+            "payload_size": message.payload().iter().map(Vec::len).sum::<usize>(),
+            "outcome": if error.is_some() { "error" } else { "delivered" },
+        });
+        if let Some(error) = error {
+            entry["error"] = json!(error.code());
+        }
+        let mut line = to_vec(&entry);
+        line.push(b'\n');
+        if let Err(err) = audit_log.writer.write_all(&line) {
+            log::error!("Could not write the audit log: {err}");
+        }
+    }
+
+    /// Publish that `event` happened to the Component `name`, if an events socket is configured.
+    fn publish_event(&self, event: &str, name: &[u8]) {
+        let Some(socket) = &self.events else {
+            return;
+        };
+        let content = json!({
+            "event": event,
+            "name": String::from_utf8_lossy(name),
+            "timestamp": unix_time(),
+        });
+        let message = DataMessage::new(
+            &String::from_utf8_lossy(&self.full_name),
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&content)),
+        );
+        if let Err(err) = socket.send_multipart(message.into_frames(), zmq::DONTWAIT) {
+            log::warn!("Could not publish the {event} event: {err}");
+        }
+    }
+
+    /// Create an error response to the request with `id`, which is null if unknown.
+    fn create_error(
+        &mut self,
+        receiver: Vec<u8>,
+        error: Error,
+        conversation_id: Option<&[u8]>,
+        id: Option<u16>,
+    ) -> Message {
+        self.create_error_with_data(receiver, error, conversation_id, id, None)
+    }
+
+    /// Like [`Coordinator::create_error`], but explaining the error with `data`.
+    fn create_error_with_data(
+        &mut self,
+        receiver: Vec<u8>,
+        error: Error,
+        conversation_id: Option<&[u8]>,
+        id: Option<u16>,
+        data: Option<Value>,
+    ) -> Message {
+        log::debug!(
+            "Sending error {} to {}",
+            error.code(),
+            String::from_utf8_lossy(&receiver)
+        );
+        self.record_error(&error);
+        let data = data.or_else(|| error.data().cloned());
+        let response = ErrorResponse::build(id, error.code(), error.message(), data);
+        Message::build(
+            receiver,
+            self.full_name.clone(),
+            conversation_id,
+            None,
+            message_types::JSON,
+            ContentTypes::Frame(to_vec(&response)),
+        )
+    }
+
+    /// Count an error response in the statistics.
+    fn record_error(&mut self, error: &Error) {
+        *self.statistics.rejected.entry(error.code()).or_default() += 1;
+    }
+
+    fn create_response(
+        &self,
+        receiver: Vec<u8>,
+        id: u16,
+        conversation_id: Option<&[u8]>,
+        result: impl Serialize,
+    ) -> Message {
+        Message::build_response(
+            receiver,
+            self.full_name.clone(),
+            conversation_id,
+            id,
+            result,
+        )
+    }
+
+    /// Send a message to a peer of the router without blocking.
+    ///
+    /// A message, which the receiver cannot take, is dropped, see
+    /// [`Coordinator::receiver_unreachable`].
+    fn send_local_message<T: zmq::Sendable>(&mut self, msg_cont: MessageContainer<T>) {
+        let message = msg_cont.message;
+        self.trace("out", &message);
+        // The router refuses the first frame, if the receiver is gone or its queue is full.
+        if let Err(err) = self
+            .router
+            .send(msg_cont.identity, zmq::SNDMORE | zmq::DONTWAIT)
+        {
+            self.receiver_unreachable(message, err);
+            return;
+        }
+        self.statistics.bytes_out += message.len_bytes() as u64;
+        // Moving the frames hands them to zmq without copying.
+        if let Err(err) = self
+            .router
+            .send_multipart(message.into_frames(), zmq::DONTWAIT)
+        {
+            log::error!("Could not send the message frames: {err}");
+        }
+    }
+
+    /// Count the undeliverable `message`, audit it, and tell its sender.
+    ///
+    /// Neither responses nor our own messages are answered, lest errors bounce back and forth.
+    fn receiver_unreachable(&mut self, message: Message, err: zmq::Error) {
+        self.statistics.dropped += 1;
+        let receiver = String::from_utf8_lossy(message.receiver_frame()).to_string();
+        log::warn!("Dropped a message to {receiver}: {err}");
+        if *message.sender_frame() == self.full_name {
+            return;
+        }
+        let error = receiver_unreachable_error(&receiver);
+        // The routed message is audited as delivered already.
+        self.audit(&message, Some(&error));
+        if message
+            .content_frame()
+            .is_some_and(|content| json::is_response(content))
+        {
+            return;
+        }
+        let reply = self.create_error(
+            message.sender_frame().to_vec(),
+            error,
+            Some(message.header().conversation_id),
+            request_id(&message),
+        );
+        let Ok(sender_name) = reply.receiver() else {
+            return;
+        };
+        let Ok((namespace, identity)) = self.find_routing_information(&sender_name) else {
+            return;
+        };
+        let (receiving_namespace, identity) = (namespace.to_vec(), identity.to_vec());
+        self.send_routed_message(SendingContainer {
+            receiving_namespace,
+            msg_cont: MessageContainer {
+                identity,
+                message: reply,
+            },
+        });
+    }
+
+    /// Handle the content of a message which is directed to this Coordinator itself.
+    fn handle_message_content(&mut self, message: &Message, sender_name: &FullName) -> Message {
+        let receiver = message.sender_frame().to_vec();
+        let conversation_id: Option<&[u8]> = Some(message.header().conversation_id);
+        let content = match message.content_frame() {
+            Some(content) => content,
+            None => return self.create_error(receiver, Error::ParseError, conversation_id, None),
+        };
+        let requests = match json::parse_requests(content) {
+            Ok(requests) => requests,
+            Err(error) => {
+                let id = json::extract_id(content);
+                return self.create_error(receiver, error, conversation_id, id);
+            }
+        };
+        match requests {
+            Requests::Single(request) => {
+                let id = request.id;
+                match self.answer_request(request, sender_name) {
+                    Ok(result) => self.create_response(receiver, id, conversation_id, result),
+                    Err(error) => self.create_error(receiver, error, conversation_id, Some(id)),
+                }
+            }
+            Requests::Batch(batch) => {
+                let responses: Vec<Value> = batch
+                    .into_iter()
+                    .map(|entry| {
+                        let (id, error) = match entry {
+                            Ok(request) => {
+                                let id = request.id;
+                                match self.answer_request(request, sender_name) {
+                                    Ok(result) => return json!(Response::build(id, result)),
+                                    Err(error) => (Some(id), error),
+                                }
+                            }
+                            Err(id) => (id, Error::InvalidRequest),
+                        };
+                        self.record_error(&error);
+                        json!(ErrorResponse::from_error(id, &error))
+                    })
+                    .collect();
+                Message::build(
+                    receiver,
+                    self.full_name.clone(),
+                    conversation_id,
+                    None,
+                    message_types::JSON,
+                    ContentTypes::Frame(to_vec(&responses)),
+                )
+            }
+        }
+    }
+
+    /// Call the built-in method of `request` on behalf of `sender_name`.
+    fn answer_request(&mut self, request: Request, sender_name: &FullName) -> Result<Value, Error> {
+        match &request.method[..] {
+            "sign_in" => Ok(Value::Null), // already handled during check_message
+            "sign_out" => self.sign_out(sender_name),
+            "pong" => Ok(Value::Null),
+            "coordinator_info" => Ok(self.info()),
+            // Already registered during check_message
+            "coordinator_sign_in" => Ok(json!({
+                "namespace": String::from_utf8_lossy(&self.namespace),
+            })),
+            "coordinator_sign_out" => {
+                self.coordinator_sign_out(sender_name.namespace);
+                Ok(Value::Null)
+            }
+            "send_local_components" => Ok(self.local_components()),
+            "send_global_components" => self.global_components(request.params.as_ref()),
+            "record_components" => self.record_components(sender_name, request.params),
+            "send_nodes" => Ok(Value::Object(self.node_directory())),
+            "send_statistics" => self.send_statistics(request.params.as_ref()),
+            "component_info" => self.component_info(request.params.as_ref()),
+            "add_nodes" => self.add_nodes(request.params, false),
+            "set_nodes" => self.add_nodes(request.params, true),
+            "shut_down" => self.shut_down(sender_name, request.params.as_ref()),
+            "rpc.discover" => Ok(json::discover_document(
+                &String::from_utf8_lossy(&self.full_name),
+                BUILT_IN_METHODS,
+            )),
+            _ => Err(Error::MethodNotFound),
+        }
+    }
+
+    /// Report the traffic counters and reset them, if the params contain `"reset": true`.
+    fn send_statistics(&mut self, params: Option<&Value>) -> Result<Value, Error> {
+        let reset = bool_param(params, "reset")?;
+        let rejected: serde_json::Map<String, Value> = self
+            .statistics
+            .rejected
+            .iter()
+            .map(|(code, count)| (code.to_string(), json!(count)))
+            .collect();
+        let report = json!({
+            "routed": self.statistics.routed,
+            "rejected": rejected,
+            "oversized": self.statistics.oversized,
+            "unauthorized": self.statistics.unauthorized,
+            "bytes_in": self.statistics.bytes_in,
+            "bytes_out": self.statistics.bytes_out,
+            "nodes_expired": self.statistics.nodes_expired,
+            "nodes_reconnected": self.statistics.nodes_reconnected,
+            "dropped": self.statistics.dropped,
+            "components": self.components.len(),
+            "max_components": self.config.max_components,
+            "nodes": self.nodes.len(),
+            "uptime": self.started.elapsed().as_secs_f64(),
+        });
+        if reset {
+            self.statistics = Statistics::default();
+        }
+        Ok(report)
+    }
+
+    /// The info of the Component given by the `name` param or, without, of all by name.
+    fn component_info(&self, params: Option<&Value>) -> Result<Value, Error> {
+        match params.and_then(|params| params.get("name")) {
+            None => Ok(Value::Object(
+                self.components
+                    .iter()
+                    .map(|(name, component)| {
+                        (String::from_utf8_lossy(name).to_string(), component.info())
+                    })
+                    .collect(),
+            )),
+            Some(Value::String(name)) => self
+                .components
+                .get(name.as_bytes())
+                .map(Component::info)
+                .ok_or(Error::InvalidParams),
+            Some(_) => Err(Error::InvalidParams),
+        }
+    }
+
+    /// The names of the Components signed in to us, sorted.
+    fn local_components(&self) -> Value {
+        let mut names: Vec<String> = self
+            .components
+            .keys()
+            .map(|name| String::from_utf8_lossy(name).to_string())
+            .collect();
+        names.sort();
+        json!(names)
+    }
+
+    /// The Components of us and all nodes by namespace.
+    ///
+    /// The lists of the nodes are cached, as nodes report their changes. Nodes are queried only,
+    /// if their list is older than `directory_max_age` or if the `refresh` param is true.
+    /// Nodes without list, which do not answer within [`NODE_QUERY_TIMEOUT`], are left out.
+    /// Unreachable nodes are not queried, but listed with `null`.
+    fn global_components(&mut self, params: Option<&Value>) -> Result<Value, Error> {
+        let refresh = bool_param(params, "refresh")?;
+        let max_age = self.config.directory_max_age;
+        let deadline = Instant::now() + NODE_QUERY_TIMEOUT;
+        let mut queries = Vec::new();
+        let stale = self.nodes.iter().filter(|(_, node)| {
+            node.reachable
+                && (refresh
+                    || node
+                        .components
+                        .as_ref()
+                        .is_none_or(|(_, updated)| updated.elapsed() > max_age))
+        });
+        for (namespace, node) in stale {
+            let (request, cid) = Message::build_request(
+                compose_full_name(namespace, b"COORDINATOR"),
+                self.full_name.clone(),
+                0,
+                "send_local_components",
+                None,
+            );
+            if node
+                .socket
+                .send_multipart(request.to_frames(), zmq::DONTWAIT)
+                .is_ok()
+            {
+                queries.push((namespace.clone(), cid.to_vec()));
+            }
+        }
+        for (namespace, cid) in queries {
+            let response = self.await_node_response(&namespace, &cid, deadline);
+            if let Some(components) = response.as_ref().and_then(response_result) {
+                if let Some(node) = self.nodes.get_mut(&namespace) {
+                    node.components = Some((components, Instant::now()));
+                }
+            }
+        }
+        let mut directory: serde_json::Map<String, Value> = self
+            .nodes
+            .iter()
+            .filter_map(|(namespace, node)| {
+                let components = match &node.components {
+                    _ if !node.reachable => Value::Null,
+                    Some((components, _)) => components.clone(),
+                    None => return None,
+                };
+                Some((String::from_utf8_lossy(namespace).to_string(), components))
+            })
+            .collect();
+        let namespace = String::from_utf8_lossy(&self.namespace).to_string();
+        directory.insert(namespace, self.local_components());
+        Ok(Value::Object(directory))
+    }
+
+    /// Cache the `components` param, which the node `sender_name` reports.
+    fn record_components(
+        &mut self,
+        sender_name: &FullName,
+        params: Option<Value>,
+    ) -> Result<Value, Error> {
+        let components = match params {
+            Some(Value::Object(mut params)) => params.remove("components"),
+            _ => None,
+        };
+        let Some(components @ Value::Array(_)) = components else {
+            return Err(Error::InvalidParams);
+        };
+        let node = self
+            .nodes
+            .get_mut(sender_name.namespace)
+            .ok_or(Error::NodeUnknown)?;
+        node.components = Some((components, Instant::now()));
+        Ok(Value::Null)
+    }
+
+    /// Report our Components to all nodes, e.g. after a change.
+    fn push_components(&self) {
+        // Unreachable nodes get the list, once they accept our sign-in again.
+        for (namespace, node) in self.nodes.iter().filter(|(_, node)| node.reachable) {
+            self.push_components_to(namespace, node);
+        }
+    }
+
+    /// Report our Components to the `node` of `namespace`.
+    fn push_components_to(&self, namespace: &[u8], node: &RemoteNode) {
+        let params = json!({ "components": self.local_components() });
+        let (request, _cid) = Message::build_request(
+            compose_full_name(namespace, b"COORDINATOR"),
+            self.full_name.clone(),
+            0,
+            "record_components",
+            Some(params),
+        );
+        if let Err(err) = node
+            .socket
+            .send_multipart(request.into_frames(), zmq::DONTWAIT)
+        {
+            log::warn!(
+                "Could not report the Components to {}: {err}",
+                node.endpoint
+            );
+        }
+    }
+
+    /// Wait until `deadline` for the answer to the request `cid` to the node of `namespace`.
+    ///
+    /// Other messages of that node are handled meanwhile.
+    fn await_node_response(
+        &mut self,
+        namespace: &[u8],
+        cid: &[u8],
+        deadline: Instant,
+    ) -> Option<Message> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = self.receive_node_message(namespace, remaining.as_millis() as i64)?;
+            if message.header().conversation_id == cid {
+                return Some(message);
+            }
+            self.handle_node_message(namespace, message);
+        }
+    }
+
+    /// Connect to the Coordinators in the `nodes` parameter, mapping namespaces to addresses.
+    ///
+    /// With `exclusive`, disconnect from all other Coordinators. Newly added nodes are announced
+    /// to all known Coordinators. The result maps each namespace to `null` or its error.
+    /// Rejections of the new nodes arriving within [`NODE_QUERY_TIMEOUT`], e.g. as a node has
+    /// our namespace, are reported as well.
+    fn add_nodes(&mut self, params: Option<Value>, exclusive: bool) -> Result<Value, Error> {
+        let Some(Value::Object(nodes)) =
+            params.and_then(|mut params| params.get_mut("nodes").map(Value::take))
+        else {
+            return Err(Error::InvalidParams);
+        };
+        if exclusive {
+            let obsolete: Vec<Vec<u8>> = self
+                .nodes
+                .keys()
+                .filter(|namespace| !nodes.contains_key(&*String::from_utf8_lossy(namespace)))
+                .cloned()
+                .collect();
+            for namespace in obsolete {
+                self.remove_node(&namespace);
+            }
+        }
+        let mut results = serde_json::Map::new();
+        let mut added = Vec::new();
+        for (namespace, address) in nodes {
+            let own = namespace.as_bytes() == self.namespace.as_slice();
+            let known = self.nodes.contains_key(namespace.as_bytes());
+            let result = match address.as_str().and_then(parse_node_address) {
+                _ if own => json!(json::ErrorContent::from(&namespace_conflict_error(
+                    &self.namespace
+                ))),
+                _ if known => Value::Null,
+                None => json!(json::ErrorContent {
+                    code: Error::InvalidParams.code(),
+                    message: format!("Invalid address {address}."),
+                    data: None,
+                }),
+                Some(endpoint) => match self.add_node(&namespace, endpoint) {
+                    Ok(()) => {
+                        added.push(namespace.clone());
+                        Value::Null
+                    }
+                    Err(err) => json!(json::ErrorContent {
+                        code: Error::InvalidParams.code(),
+                        message: format!("Connecting to {address} failed: {err}."),
+                        data: None,
+                    }),
+                },
+            };
+            results.insert(namespace, result);
+        }
+        let deadline = Instant::now() + NODE_QUERY_TIMEOUT;
+        for namespace in &added {
+            let Some(cid) = self
+                .nodes
+                .get(namespace.as_bytes())
+                .map(|node| node.sign_in_cid.clone())
+            else {
+                continue;
+            };
+            let Some(response) = self.await_node_response(namespace.as_bytes(), &cid, deadline)
+            else {
+                continue;
+            };
+            let content = response
+                .content_frame()
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if let Err(error) = self.handle_node_sign_in(namespace.as_bytes(), content) {
+                results.insert(namespace.clone(), json!(error));
+            }
+        }
+        if !added.is_empty() {
+            self.announce_nodes();
+        }
+        Ok(Value::Object(results))
+    }
+
+    /// The addresses of all known Coordinators (including us) by namespace.
+    fn node_directory(&self) -> serde_json::Map<String, Value> {
+        let mut directory: serde_json::Map<String, Value> = self
+            .nodes
+            .iter()
+            .map(|(namespace, node)| {
+                let namespace = String::from_utf8_lossy(namespace).to_string();
+                (namespace, Value::from(node_address(&node.endpoint)))
+            })
+            .collect();
+        if let Some(address) = &self.address {
+            let namespace = String::from_utf8_lossy(&self.namespace).to_string();
+            directory.insert(namespace, Value::from(node_address(address)));
+        }
+        directory
+    }
+
+    /// Send the known Coordinators (including us) to all nodes via `add_nodes`.
+    fn announce_nodes(&self) {
+        let directory = self.node_directory();
+        for (namespace, node) in self.nodes.iter() {
+            // A node's own namespace would be a conflict for it.
+            let mut nodes = directory.clone();
+            nodes.remove(&*String::from_utf8_lossy(namespace));
+            let (request, _cid) = Message::build_request(
+                compose_full_name(namespace, b"COORDINATOR"),
+                self.full_name.clone(),
+                0,
+                "add_nodes",
+                Some(json!({ "nodes": nodes })),
+            );
+            if let Err(err) = node
+                .socket
+                .send_multipart(request.to_frames(), zmq::DONTWAIT)
+            {
+                log::warn!("Could not announce nodes to {}: {err}", node.endpoint);
+            }
+        }
+    }
+
+    /// Describe this Coordinator for `coordinator_info`.
+    fn info(&self) -> Value {
+        json!({
+            "name": String::from_utf8_lossy(&self.full_name),
+            "namespace": String::from_utf8_lossy(&self.namespace),
+            "version": env!("CARGO_PKG_VERSION"),
+        })
+    }
+
+    /// Register the Coordinator of `namespace`, connected via `identity`.
+    ///
+    /// Connect back to its advertised address, if we have no connection yet.
+    fn coordinator_sign_in(&mut self, identity: &[u8], namespace: &[u8], content: &[u8]) {
+        log::info!("Node {} signed in.", String::from_utf8_lossy(namespace));
+        self.node_identities
+            .insert(identity.to_vec(), namespace.to_vec());
+        if let Some(node) = self.nodes.get(namespace) {
+            // It is back, e.g. after a restart, so do not wait for the next attempt.
+            if !node.reachable {
+                self.reconnect_node(namespace);
+            }
+            return;
+        }
+        let address = serde_json::from_slice::<Request>(content)
+            .ok()
+            .and_then(|request| request.params)
+            .and_then(|params| params["address"].as_str().map(str::parse::<Endpoint>));
+        match (std::str::from_utf8(namespace), address) {
+            (Ok(namespace), Some(Ok(address))) => {
+                if let Err(err) = self.add_node(namespace, address) {
+                    log::error!("Could not connect back to node {namespace}: {err}");
+                }
+            }
+            _ => log::warn!("Node {namespace:?} signed in without valid address."),
+        }
+    }
+
+    /// Forget the Coordinator of `namespace` and sign out from it as well.
+    fn coordinator_sign_out(&mut self, namespace: &[u8]) {
+        log::info!("Node {} signed out.", String::from_utf8_lossy(namespace));
+        self.node_identities
+            .retain(|_, node_namespace| node_namespace != namespace);
+        self.remove_node(namespace);
+    }
+
+    /// Register the Component `sender_name` connected via `identity`.
+    ///
+    /// Signing in again via the same identity refreshes the registration, while another identity
+    /// may not take over the name. Invalid or reserved names are rejected.
+    fn sign_in(&mut self, identity: &[u8], sender_name: &FullName) -> Result<(), Error> {
+        if let Err(reason) = validate_name(sender_name.name) {
+            log::debug!("Sign-in rejected: {reason}");
+            return Err(Error::InvalidParams);
+        }
+        let count = self.components.len();
+        // Look up by the borrowed name, as only a new Component needs an owned key.
+        match self.components.get_mut(sender_name.name) {
+            Some(component) if *component.identity != *identity => Err(Error::DuplicateName),
+            Some(component) => {
+                component.refresh();
+                Ok(())
+            }
+            None if count >= self.config.max_components => {
+                log::warn!(
+                    "Sign-in of {} rejected, as {count} Components are signed in.",
+                    String::from_utf8_lossy(sender_name.name),
+                );
+                Err(too_many_components_error(self.config.max_components))
+            }
+            None => {
+                log::info!("{} signed in.", String::from_utf8_lossy(sender_name.name));
+                self.components
+                    .insert(sender_name.name.into(), Component::build(identity));
+                self.publish_event("sign_in", sender_name.name);
+                self.push_components();
+                Ok(())
+            }
+        }
+    }
+
+    fn sign_out(&mut self, sender_name: &FullName) -> Result<Value, Error> {
+        if self.components.remove(sender_name.name).is_some() {
+            log::info!("{} signed out.", String::from_utf8_lossy(sender_name.name));
+            self.publish_event("sign_out", sender_name.name);
+            self.push_components();
+        }
+        Ok(Value::Null)
+    }
+
+    /// Stop the coordinator's routing action, if the shutdown policy allows it to `sender_name`.
+    fn shut_down(
+        &mut self,
+        sender_name: &FullName,
+        params: Option<&Value>,
+    ) -> Result<Value, Error> {
+        let local = sender_name.namespace.is_empty() || sender_name.namespace == self.namespace;
+        if !self
+            .shutdown_policy
+            .may_shut_down(sender_name, local, params)
+        {
+            return Err(Error::Unauthorized);
+        }
+        self.stop.stop();
+        Ok(Value::Null)
+    }
+}
+
+/// Error for a message of the LECO protocol `version`, which we do not understand.
+fn version_error(version: Option<u8>) -> Error {
+    let received = version.map_or("none".to_string(), |version| version.to_string());
+    Error::custom(
+        -32095,
+        format!("Incompatible LECO version {received}, expected {VERSION}."),
+        Some(json!({"expected": VERSION, "received": version})),
+    )
+    .expect("The code is in the server error range.")
+}
+
+/// Get the optional boolean parameter `key`, which is false by default.
+fn bool_param(params: Option<&Value>, key: &str) -> Result<bool, Error> {
+    match params.and_then(|params| params.get(key)) {
+        None => Ok(false),
+        Some(value) => value.as_bool().ok_or(Error::InvalidParams),
+    }
+}
+
+/// Error for another Coordinator with our `namespace`.
+fn namespace_conflict_error(namespace: &[u8]) -> Error {
+    let namespace = String::from_utf8_lossy(namespace);
+    Error::custom(
+        -32098,
+        format!("Namespace conflict, {namespace} is the namespace of this Coordinator."),
+        Some(json!({ "namespace": namespace })),
+    )
+    .expect("The code is in the server error range.")
+}
+
+/// The result of the response `message`, if it is a successful one.
+fn response_result(message: &Message) -> Option<Value> {
+    match serde_json::from_slice::<json::ResponseContent>(message.content_frame()?) {
+        Ok(json::ResponseContent::Result(response)) => Some(response.result),
+        _ => None,
+    }
+}
+
+/// Error for a message of `bytes` exceeding the limit of `max_bytes`.
+fn oversize_error(bytes: usize, max_bytes: usize) -> Error {
+    Error::custom(
+        -32097,
+        format!("Message too large, at most {max_bytes} bytes are allowed."),
+        Some(json!({"max_bytes": max_bytes, "received": bytes})),
+    )
+    .expect("The code is in the server error range.")
+}
+
+/// Error for a message, which could not be delivered to the local `receiver`.
+fn receiver_unreachable_error(receiver: &str) -> Error {
+    Error::custom(
+        -32099,
+        format!("Receiver {receiver} is unreachable."),
+        Some(json!({ "receiver": receiver })),
+    )
+    .expect("The code is in the server error range.")
+}
+
+/// Error for a sign-in exceeding the maximum number of Components.
+fn too_many_components_error(max_components: usize) -> Error {
+    Error::custom(
+        -32096,
+        format!("Too many Components, at most {max_components} may sign in."),
+        Some(json!({"max_components": max_components})),
+    )
+    .expect("The code is in the server error range.")
+}
+
+/// The endpoint `router` is bound to via `endpoint`, resolving port 0 to the actual port.
+fn bound_endpoint(endpoint: &Endpoint, router: &zmq::Socket) -> Endpoint {
+    match endpoint {
+        Endpoint::Tcp { host, port: 0 } => {
+            let port = router
+                .get_last_endpoint()
+                .ok()
+                .and_then(Result::ok)
+                .and_then(|last| last.rsplit_once(':')?.1.parse().ok())
+                .unwrap_or_default();
+            Endpoint::Tcp {
+                host: host.clone(),
+                port,
+            }
+        }
+        _ => endpoint.clone(),
+    }
+}
+
+/// Bind `socket` to `endpoint`.
+fn bind(socket: &zmq::Socket, endpoint: &Endpoint) -> Result<(), SetupError> {
+    socket
+        .bind(&endpoint.to_string())
+        .map_err(|source| SetupError::Bind {
+            endpoint: endpoint.clone(),
+            source,
+        })
+}
+
+/// Make `socket` a CURVE server with the keys of `curve`, which has to happen before binding.
+fn make_curve_server(socket: &zmq::Socket, curve: &CurveServerConfig) -> Result<(), SetupError> {
+    socket
+        .set_curve_server(true)
+        .and_then(|()| socket.set_curve_secretkey(&curve.secret_key))
+        .map_err(SetupError::Curve)
+}
+
+/// Seconds since the Unix epoch.
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// The id of the request in `message`, as far as it can be extracted.
+fn request_id(message: &Message) -> Option<u16> {
+    message
+        .content_frame()
+        .and_then(|content| json::extract_id(content))
+}
+
+/// Parse a node address, either `host:port` or a full endpoint like `ipc://path`.
+pub fn parse_node_address(address: &str) -> Option<Endpoint> {
+    match address.contains("://") {
+        true => address.parse().ok(),
+        false => format!("tcp://{address}").parse().ok(),
+    }
+}
+
+/// Format `endpoint` as node address, i.e. `host:port` for tcp.
+fn node_address(endpoint: &Endpoint) -> String {
+    match endpoint {
+        Endpoint::Tcp { host, port } => format!("{host}:{port}"),
+        endpoint => endpoint.to_string(),
+    }
+}
+
+/// The endpoint to connect to a socket bound to `endpoint`, i.e. with `localhost` for `*`.
+fn connectable(endpoint: &Endpoint) -> Endpoint {
+    match endpoint {
+        Endpoint::Tcp { host, port } if host == "*" => Endpoint::Tcp {
+            host: "localhost".to_string(),
+            port: *port,
+        },
+        endpoint => endpoint.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        control_protocol::{
+            actor::Actor,
+            communicator::{
+                Communicator, CommunicatorError, CommunicatorOptions, CommunicatorState,
+            },
+            listener::Listener,
+            testing::assert_messages_equivalent,
+        },
+        coordinator::admin::AdminError,
+        core::CurveClientKeys,
+        json::{to_vec, ErrorResponse, Request, Response},
+    };
+
+    /// Add the Components `com_A` and `com_B` to `c`.
+    fn with_components(mut c: Coordinator) -> Coordinator {
+        c.components
+            .insert(b"com_A".as_slice().into(), Component::build(b"id_A"));
+        c.components
+            .insert(b"com_B".as_slice().into(), Component::build(b"id_B"));
+        c
+    }
+
+    /// The port a Coordinator is bound to.
+    fn bound_port(c: &Coordinator) -> u16 {
+        match c.endpoint() {
+            Some(Endpoint::Tcp { port, .. }) => *port,
+            endpoint => panic!("Not bound to tcp: {endpoint:?}"),
+        }
+    }
+
+    /// Make a Coordinator bound to a free port and return it with that port.
+    fn make_tcp_coordinator(namespace: &str) -> (Coordinator, u16) {
+        let endpoint = Endpoint::tcp("*", 0).unwrap();
+        let c = Coordinator::with_endpoint(namespace.to_string(), Some(&endpoint), None, None);
+        let port = bound_port(&c);
+        (c, port)
+    }
+
+    /// Make a Coordinator bound to a free port and return it with that port.
+    fn make_live_coordinator() -> (Coordinator, u16) {
+        let (c, port) = make_tcp_coordinator("N1");
+        (with_components(c), port)
+    }
+
+    /// Make a Coordinator without binding to a port lest the port is already bound
+    fn make_coordinator() -> Coordinator {
+        with_components(Coordinator::new("N1".to_string(), Some(0), None))
+    }
+
+    fn make_message() -> Message {
+        Message::build(
+            b"receiver".to_vec(),
+            b"sender".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        )
+    }
+
+    #[test]
+    fn test_create_rejection() {
+        let mut c = make_coordinator();
+        let message = make_message();
+        let rejection = c
+            .create_rejection(message.to_frames(), Error::InvalidRequest)
+            .unwrap();
+        assert_eq!(rejection.receiver_frame(), b"sender");
+        assert_eq!(
+            rejection.header().conversation_id,
+            message.header().conversation_id
+        );
+        let content: Value = serde_json::from_slice(rejection.content_frame().unwrap()).unwrap();
+        assert_eq!(content["error"]["code"], Error::InvalidRequest.code());
+    }
+
+    #[test]
+    fn test_oversized_message() {
+        let endpoint = Endpoint::tcp("*", 0).unwrap();
+        let config = CoordinatorConfig {
+            limits: Limits {
+                max_bytes: 4096,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut c = Coordinator::with_config("N1".to_string(), Some(&endpoint), None, config);
+        let peer = c.context.socket(zmq::DEALER).unwrap();
+        peer.connect(&format!("tcp://localhost:{}", bound_port(&c)))
+            .unwrap();
+        let message = |payload: Vec<Vec<u8>>| {
+            Message::build(
+                b"COORDINATOR".to_vec(),
+                b"big".to_vec(),
+                None,
+                None,
+                message_types::JSON,
+                ContentTypes::Frames(payload),
+            )
+        };
+        // Every frame is small enough for zmq, but not the whole message.
+        let request = message(vec![vec![b' '; 3000], vec![b' '; 3000]]);
+        peer.send_multipart(request.to_frames(), 0).unwrap();
+        c.poll_sockets(1000);
+        let response = Message::new(peer.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(response.receiver_frame(), b"big");
+        let response: ErrorResponse =
+            serde_json::from_slice(response.content_frame().unwrap()).unwrap();
+        assert_eq!(response.error.code, -32097);
+        let received = request.len_bytes();
+        assert_eq!(
+            response.error.data,
+            Some(json!({"max_bytes": 4096, "received": received}))
+        );
+        assert_eq!(c.statistics.oversized, 1);
+
+        // zmq drops an oversized frame without passing it on.
+        peer.send_multipart(message(vec![vec![b' '; 8000]]).to_frames(), 0)
+            .unwrap();
+        c.poll_sockets(200);
+        assert_eq!(peer.poll(zmq::POLLIN, 100).unwrap(), 0);
+        assert_eq!(c.statistics.oversized, 1);
+        drop(peer);
+    }
+
+    #[test]
+    fn test_incompatible_version() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_incompatible_version".to_string());
+        let mut c = Coordinator::with_config(
+            "N1".to_string(),
+            Some(&endpoint),
+            Some(ctx.clone()),
+            CoordinatorConfig::default(),
+        );
+        let peer = ctx.socket(zmq::DEALER).unwrap();
+        peer.connect(&endpoint.to_string()).unwrap();
+        let cid = [7u8; 16];
+        let frames = vec![
+            vec![9],
+            b"COORDINATOR".to_vec(),
+            b"future".to_vec(),
+            [cid.as_slice(), b"header of the future"].concat(),
+            b"anything".to_vec(),
+        ];
+        peer.send_multipart(frames, 0).unwrap();
+        c.loop_element();
+        let response = Message::new(peer.recv_multipart(0).unwrap()).unwrap();
+        assert_eq!(response.receiver_frame(), b"future");
+        assert_eq!(response.header().conversation_id, cid);
+        let response: ErrorResponse =
+            serde_json::from_slice(response.content_frame().unwrap()).unwrap();
+        assert_eq!(response.id, None);
+        assert_eq!(
+            response.error.message,
+            format!("Incompatible LECO version 9, expected {VERSION}.")
+        );
+        assert_eq!(
+            response.error.data,
+            Some(json!({"expected": VERSION, "received": 9}))
+        );
+        assert_eq!(c.statistics.rejected[&response.error.code], 1);
+        drop(peer);
+    }
+
+    #[test]
+    fn test_create_rejection_without_sender() {
+        let mut c = make_coordinator();
+        assert!(c
+            .create_rejection(&[vec![0], vec![1]], Error::InvalidRequest)
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_routing_local_without_namespace() {
+        let c = make_coordinator();
+        let r = c.find_routing_information(&FullName::from_slice(b"com_A").unwrap());
+        assert_eq!(r, Ok((&b""[..], &b"id_A"[..])))
+    }
+
+    #[test]
+    fn test_find_routing_local_without_namespace_fails() {
+        let c = make_coordinator();
+        let r = c.find_routing_information(&FullName::from_slice(b"com_X").unwrap());
+        assert_eq!(r, Err(Error::ReceiverUnknown))
+    }
+
+    #[test]
+    fn test_find_routing_local_with_namespace() {
+        let c = make_coordinator();
+        let r = c.find_routing_information(&FullName::from_slice(b"N1.com_B").unwrap());
+        assert_eq!(r, Ok((&b""[..], &b"id_B"[..])))
+    }
+
+    #[test]
+    fn test_find_routing_local_with_namespace_fails() {
+        let c = make_coordinator();
+        let r = c.find_routing_information(&FullName::from_slice(b"N1.com_X").unwrap());
+        assert_eq!(r, Err(Error::ReceiverUnknown))
+    }
+
+    #[test]
+    fn test_find_routing_unknown_namespace_fails() {
+        let c = make_coordinator();
+        let r = c.find_routing_information(&FullName::from_slice(b"NX.com_B").unwrap());
+        assert_eq!(r, Err(Error::NodeUnknown))
+    }
+
+    #[test]
+    fn test_route_message() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"com_B".to_vec(),
+            b"com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message: message.clone(),
+            })
+            .unwrap();
+        let mut expected = message.clone();
+        expected.set_sender(b"N1.com_A".to_vec());
+        assert_messages_equivalent(&scm.msg_cont.message, &expected);
+        assert_eq!(scm.msg_cont.identity, b"id_B")
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let path = std::env::temp_dir().join(format!("ruleco-audit-{}.jsonl", Uuid::now_v7()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut c = make_coordinator().with_audit_log(file);
+        let route = |c: &mut Coordinator, receiver: &[u8], m_type| {
+            let message = Message::build(
+                receiver.to_vec(),
+                b"com_A".to_vec(),
+                None,
+                None,
+                m_type,
+                crate::core::ContentTypes::Frame(b"12345".to_vec()),
+            );
+            let cid = Uuid::from_slice(message.header().conversation_id).unwrap();
+            let routed = c.route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            });
+            // None of the Components is connected, our own replies are not audited.
+            if let Some(routed) = routed {
+                c.send_routed_message(routed);
+            }
+            cid.to_string()
+        };
+        let cid = route(&mut c, b"com_B", 1);
+        route(&mut c, b"nobody", 2);
+        route(&mut c, b"N1.COORDINATOR", 3);
+        drop(c);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 4);
+        assert!(entries[0]["timestamp"].as_f64().unwrap() > 0.0);
+        assert_eq!(entries[0]["sender"], "N1.com_A");
+        assert_eq!(entries[0]["receiver"], "com_B");
+        assert_eq!(entries[0]["conversation_id"], cid);
+        assert_eq!(entries[0]["message_type"], 1);
+        assert_eq!(entries[0]["payload_size"], 5);
+        assert_eq!(entries[0]["outcome"], "delivered");
+        assert_eq!(entries[0].get("error"), None);
+        // com_B is not connected, such that the message is dropped.
+        assert_eq!(entries[1]["conversation_id"], cid);
+        assert_eq!(entries[1]["outcome"], "error");
+        assert_eq!(entries[1]["error"], -32099);
+        assert_eq!(entries[2]["outcome"], "error");
+        assert_eq!(entries[2]["error"], Error::ReceiverUnknown.code());
+        assert_eq!(entries[3]["receiver"], "N1.COORDINATOR");
+        assert_eq!(entries[3]["outcome"], "delivered");
+    }
+
+    #[test]
+    fn test_route_message_with_params() {
+        let mut c = make_coordinator();
+        let (message, _) = Message::build_request(
+            b"com_B".to_vec(),
+            b"N1.com_A".to_vec(),
+            4,
+            "set_parameters",
+            Some(serde_json::json!({"speed": 5, "names": ["a", "b"]})),
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message: message.clone(),
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.identity, b"id_B");
+        assert_eq!(scm.msg_cont.message, message);
+    }
+
+    #[test]
+    fn test_route_message_adds_namespace_to_sender() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"com_B".to_vec(),
+            b"com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.message.sender_frame(), b"N1.com_A");
+    }
+
+    #[test]
+    fn test_route_message_keeps_full_sender_name() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"com_B".to_vec(),
+            b"N1.com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.message.sender_frame(), b"N1.com_A");
+    }
+
+    #[test]
+    fn test_route_message_ping() {
+        let mut c = make_coordinator();
+        let request = Request::build(1, "pong");
+        let response = Response::build(1, None::<()>);
+        let message = Message::build(
+            b"COORDINATOR".to_vec(),
+            b"N1.com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Frame(to_vec(&request)),
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.receiving_namespace, b"".to_vec());
+        assert_eq!(scm.msg_cont.identity, b"id_A".to_vec());
+        let m2 = scm.msg_cont.message;
+        assert_eq!(m2.content_frame().unwrap(), &to_vec(&response));
+        let expected = Message::build_response(
+            b"N1.com_A".to_vec(),
+            c.full_name.clone(),
+            None,
+            1,
+            None::<()>,
+        );
+        assert_messages_equivalent(&m2, &expected);
+    }
+
+    #[test]
+    fn test_route_message_invalid_receiver() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"a.b.c".to_vec(),
+            b"com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.identity, b"id_A");
+        let content: Value =
+            serde_json::from_slice(scm.msg_cont.message.content_frame().unwrap()).unwrap();
+        assert_eq!(content["error"]["code"], Error::InvalidRequest.code());
+        assert_eq!(content["error"]["data"], json!({"receiver": "a.b.c"}));
+    }
+
+    #[test]
+    fn test_route_message_pathological_names() {
+        let mut c = make_coordinator();
+        let long_name = vec![b'x'; 10_000];
+        let names: [&[u8]; 12] = [
+            b"",
+            b".",
+            b"..",
+            b"a.b.c",
+            b"N1.",
+            b".com_B",
+            b"N1..com_B",
+            b"N1.COORDINATOR.x",
+            b"\xff\xfe.\x00",
+            b"N2.com_B",
+            b"N1.com_B.",
+            &long_name,
+        ];
+        for name in names {
+            for (receiver, sender) in [(name, b"com_A".as_slice()), (b"com_B", name)] {
+                let message = Message::build(
+                    receiver.to_vec(),
+                    sender.to_vec(),
+                    None,
+                    None,
+                    message_types::JSON,
+                    crate::core::ContentTypes::Frame(to_vec(&Request::build(1, "pong"))),
+                );
+                let scm = c.route_message(MessageContainer {
+                    identity: b"id_A".to_vec(),
+                    message,
+                });
+                // Each message is either routed or answered with an error.
+                let scm = scm.unwrap();
+                if scm.msg_cont.identity == b"id_A" {
+                    let response: ErrorResponse =
+                        serde_json::from_slice(scm.msg_cont.message.content_frame().unwrap())
+                            .unwrap();
+                    assert!(Error::from_code(response.error.code).is_some());
+                }
+            }
+        }
+        // Frames with a short or empty header are not read as message, but rejected.
+        for header in [vec![1, 2, 3], Vec::new()] {
+            let frames = vec![vec![VERSION], b"com_B".to_vec(), b"com_A".to_vec(), header];
+            let err = Message::new(frames.clone()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            let rejection = c.create_rejection(&frames, Error::InvalidRequest).unwrap();
+            assert_eq!(rejection.receiver_frame(), b"com_A");
+        }
+        // The Coordinator still routes messages.
+        let message = Message::build(
+            b"com_B".to_vec(),
+            b"com_A".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.identity, b"id_B");
+    }
+
+    #[test]
+    fn test_route_message_unknown_receiver() {
+        let mut c = make_coordinator();
+        let (message, cid) =
+            Message::build_request(b"com_X".to_vec(), b"com_A".to_vec(), 6, "pong", None);
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert!(scm.receiving_namespace.is_empty());
+        assert_eq!(scm.msg_cont.identity, b"id_A");
+        let message = scm.msg_cont.message;
+        assert_eq!(message.receiver_frame(), b"N1.com_A");
+        assert_eq!(message.sender_frame(), b"N1.COORDINATOR");
+        assert_eq!(message.header().conversation_id, &cid[..]);
+        let response: ErrorResponse =
+            serde_json::from_slice(message.content_frame().unwrap()).unwrap();
+        assert_eq!(response.id, Some(6));
+        assert_eq!(response.error.code, Error::ReceiverUnknown.code());
+    }
+
+    #[test]
+    fn test_check_message() -> Result<(), Error> {
+        let mut c = make_coordinator();
+        let identity = b"id_A".to_vec();
+        let message = make_message();
+        let sender_name = FullName {
+            namespace: b"",
+            name: b"com_A",
+        };
+        let receiver_name = FullName {
+            namespace: b"",
+            name: b"com_B",
+        };
+        c.check_message(&identity, &message, &sender_name, &receiver_name)
+    }
+    #[test]
+    fn test_sign_in() {
+        let mut c = make_coordinator();
+        let name = FullName::from_slice(b"com_C").unwrap();
+        assert_eq!(c.sign_in(b"id_C", &name), Ok(()));
+        assert_eq!(*c.components[b"com_C".as_slice()].identity, *b"id_C");
+    }
+
+    #[test]
+    fn test_sign_in_beyond_max_components() {
+        let config = CoordinatorConfig {
+            max_components: 3,
+            ..Default::default()
+        };
+        let mut c = with_components(Coordinator::with_config(
+            "N1".to_string(),
+            None,
+            None,
+            config,
+        ));
+        let com_c = FullName::from_slice(b"com_C").unwrap();
+        let com_d = FullName::from_slice(b"com_D").unwrap();
+        assert_eq!(c.sign_in(b"id_C", &com_c), Ok(()));
+        let error = c.sign_in(b"id_D", &com_d).unwrap_err();
+        assert_eq!(error.code(), -32096);
+        assert!(!c.components.contains_key(b"com_D".as_slice()));
+        // Signing in again does not need a new slot.
+        assert_eq!(c.sign_in(b"id_C", &com_c), Ok(()));
+        let statistics = c.send_statistics(None).unwrap();
+        assert_eq!(statistics["components"], 3);
+        assert_eq!(statistics["max_components"], 3);
+
+        c.sign_out(&com_c).unwrap();
+        assert_eq!(c.sign_in(b"id_D", &com_d), Ok(()));
+    }
+
+    #[test]
+    fn test_publish_events() {
+        let ctx = zmq::Context::new();
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::ZERO,
+            expiry_timeout: Duration::ZERO,
+            events_endpoint: Some(Endpoint::Inproc("test_publish_events".to_string())),
+            ..Default::default()
+        };
+        let mut c = Coordinator::with_config("N1".to_string(), None, Some(ctx.clone()), config);
+        let subscriber = ctx.socket(zmq::SUB).unwrap();
+        subscriber.connect("inproc://test_publish_events").unwrap();
+        subscriber.set_subscribe(b"N1.COORDINATOR").unwrap();
+        // Give the subscription time to reach the publisher.
+        std::thread::sleep(Duration::from_millis(50));
+        let receive = || {
+            assert_eq!(subscriber.poll(zmq::POLLIN, 1000).unwrap(), 1);
+            let frames = subscriber.recv_multipart(0).unwrap();
+            assert_eq!(frames[0], b"N1.COORDINATOR");
+            assert_eq!(frames[1][16], message_types::JSON);
+            let content: Value = serde_json::from_slice(&frames[2]).unwrap();
+            assert!(content["timestamp"].as_f64().unwrap() > 0.0);
+            (content["event"].clone(), content["name"].clone())
+        };
+
+        let name = FullName::from_slice(b"com_C").unwrap();
+        c.sign_in(b"id_C", &name).unwrap();
+        assert_eq!(receive(), (json!("sign_in"), json!("com_C")));
+        c.sign_out(&name).unwrap();
+        assert_eq!(receive(), (json!("sign_out"), json!("com_C")));
+        c.sign_in(b"id_C", &name).unwrap();
+        receive();
+        // Ping first, then expire.
+        c.check_timeouts();
+        c.check_timeouts();
+        assert_eq!(receive(), (json!("expired"), json!("com_C")));
+    }
+
+    /// Logger keeping the records of all tests.
+    struct CaptureLogger(std::sync::Mutex<Vec<(log::Level, String)>>);
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let entry = (record.level(), record.args().to_string());
+            self.0.lock().unwrap().push(entry);
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger(std::sync::Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_sign_in_logs_info() {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Info);
+        }
+        let mut c = make_coordinator();
+        let sender_name = FullName {
+            namespace: b"",
+            name: b"com_logged",
+        };
+        c.sign_in(b"id_L", &sender_name).unwrap();
+        let records = LOGGER.0.lock().unwrap();
+        assert!(records.contains(&(log::Level::Info, "com_logged signed in.".to_string())));
+    }
+
+    #[test]
+    fn test_sign_in_invalid_names() {
+        let mut c = make_coordinator();
+        for name in [b"COORDINATOR".as_slice(), b"com.X", b""] {
+            let sender_name = FullName {
+                namespace: b"",
+                name,
+            };
+            assert_eq!(c.sign_in(b"id_X", &sender_name), Err(Error::InvalidParams));
+        }
+        assert_eq!(c.components.len(), 2);
+        assert_eq!(
+            check_addresses(&mut c, b"COORDINATOR", b"COORDINATOR"),
+            Err(Error::InvalidParams)
+        );
+        assert!(!c.components.contains_key(b"COORDINATOR".as_slice()));
+    }
+
+    #[test]
+    fn test_sign_in_again_refreshes() {
+        let mut c = make_coordinator();
+        let name = FullName::from_slice(b"com_A").unwrap();
+        let before = c.components[b"com_A".as_slice()].timestamp;
+        assert_eq!(c.sign_in(b"id_A", &name), Ok(()));
+        assert!(c.components[b"com_A".as_slice()].timestamp > before);
+    }
+
+    #[test]
+    fn test_sign_in_duplicate_name() {
+        let mut c = make_coordinator();
+        let name = FullName::from_slice(b"com_A").unwrap();
+        assert_eq!(c.sign_in(b"id_X", &name), Err(Error::DuplicateName));
+        assert_eq!(*c.components[b"com_A".as_slice()].identity, *b"id_A");
+    }
+
+    #[test]
+    fn test_check_message_counts_traffic() {
+        let mut c = make_coordinator();
+        let message = make_message();
+        let sender_name = FullName::from_slice(b"com_A").unwrap();
+        let receiver_name = FullName::from_slice(b"com_B").unwrap();
+        for _ in 0..2 {
+            c.check_message(b"id_A", &message, &sender_name, &receiver_name)
+                .unwrap();
+        }
+        let component = c.components.get(b"com_A".as_slice()).unwrap();
+        assert_eq!(component.message_count, 2);
+        assert_eq!(component.byte_count, 2 * message.len_bytes() as u64);
+    }
+
+    #[test]
+    fn test_check_message_not_signed_in() {
+        let mut c = make_coordinator();
+        let identity = b"id_A".to_vec();
+        let message = make_message();
+        let sender_name = FullName {
+            namespace: b"",
+            name: b"com_C",
+        };
+        let receiver_name = FullName {
+            namespace: b"",
+            name: b"com_B",
+        };
+        let result = c.check_message(&identity, &message, &sender_name, &receiver_name);
+        assert![result.is_err_and(|err| err == Error::NotSignedIn)]
+    }
+
+    #[test]
+    fn test_route_message_unknown_sender_without_content() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"COORDINATOR".to_vec(),
+            b"com_C".to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        assert_eq!(message.to_frames().len(), 4);
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_C".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.identity, b"id_C");
+        let response: ErrorResponse =
+            serde_json::from_slice(scm.msg_cont.message.content_frame().unwrap()).unwrap();
+        assert_eq!(response.error.code, Error::NotSignedIn.code());
+        // The Coordinator still handles requests.
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, to_vec(&Request::build(3, "unknown")));
+        assert_eq!(response.id, Some(3));
+    }
+
+    /// Check a message from `sender` to `com_B`, which arrived via `identity`.
+    fn check_sender(c: &mut Coordinator, identity: &[u8], sender: &[u8]) -> Result<(), Error> {
+        let message = Message::build(
+            b"com_B".to_vec(),
+            sender.to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Null,
+        );
+        c.check_message(
+            identity,
+            &message,
+            &message.sender().unwrap(),
+            &message.receiver().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_check_message_local_spoofing_foreign_namespace() {
+        let mut c = make_coordinator();
+        assert_eq!(check_sender(&mut c, b"id_A", b"N1.com_A"), Ok(()));
+        assert_eq!(
+            check_sender(&mut c, b"id_A", b"N2.com_A"),
+            Err(Error::InvalidRequest)
+        );
+        let mut c = Coordinator::with_config(
+            "N1".to_string(),
+            None,
+            None,
+            CoordinatorConfig {
+                check_sender_namespace: false,
+                ..Default::default()
+            },
+        );
+        c.components
+            .insert(b"com_A".as_slice().into(), Component::build(b"id_A"));
+        assert_eq!(
+            check_sender(&mut c, b"id_A", b"N2.com_A"),
+            Err(Error::NodeUnknown)
+        );
+    }
+
+    #[test]
+    fn test_check_message_from_remote_coordinator() {
+        let mut c = make_coordinator();
+        c.node_identities.insert(b"id_N2".to_vec(), b"N2".to_vec());
+        assert_eq!(check_sender(&mut c, b"id_N2", b"N2.com_X"), Ok(()));
+        assert_eq!(
+            check_sender(&mut c, b"id_N2", b"N3.com_X"),
+            Err(Error::InvalidRequest)
+        );
+        assert_eq!(
+            check_sender(&mut c, b"id_N2", b"N1.com_A"),
+            Err(Error::InvalidRequest)
+        );
+        assert_eq!(
+            check_sender(&mut c, b"id_N2", b"com_A"),
+            Err(Error::InvalidRequest)
+        );
+    }
+
+    fn check_addresses(c: &mut Coordinator, receiver: &[u8], sender: &[u8]) -> Result<(), Error> {
+        let message = Message::build(
+            receiver.to_vec(),
+            sender.to_vec(),
+            None,
+            None,
+            1,
+            crate::core::ContentTypes::Frame(to_vec(&Request::build(1, "sign_in"))),
+        );
+        c.check_message(
+            b"id_X",
+            &message,
+            &message.sender().unwrap(),
+            &message.receiver().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_check_message_empty_sender() {
+        let mut c = make_coordinator();
+        assert_eq!(
+            check_addresses(&mut c, b"COORDINATOR", b""),
+            Err(Error::InvalidRequest)
+        );
+        assert!(!c.components.contains_key(b"".as_slice()));
+    }
+
+    #[test]
+    fn test_check_message_empty_receiver() {
+        let mut c = make_coordinator();
+        assert_eq!(
+            check_addresses(&mut c, b"", b"com_A"),
+            Err(Error::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn test_check_message_empty_sender_and_receiver() {
+        let mut c = make_coordinator();
+        assert_eq!(
+            check_addresses(&mut c, b"", b""),
+            Err(Error::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn test_check_message_anonymous_allowed_to_coordinator() {
+        let mut c = make_coordinator();
+        c.config.anonymous_policy = AnonymousPolicy::AllowToCoordinator;
+        assert_eq!(check_addresses(&mut c, b"COORDINATOR", b""), Ok(()));
+        assert_eq!(
+            check_addresses(&mut c, b"com_B", b""),
+            Err(Error::InvalidRequest)
+        );
+        assert!(!c.components.contains_key(b"".as_slice()));
+    }
+
+    #[test]
+    fn test_route_message_anonymous_response() {
+        let mut c = make_coordinator();
+        c.config.anonymous_policy = AnonymousPolicy::AllowToCoordinator;
+        let (message, _cid) =
+            Message::build_request(b"COORDINATOR".to_vec(), b"".to_vec(), 3, "pong", None);
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_X".to_vec(),
+                message,
+            })
+            .unwrap();
+        assert_eq!(scm.msg_cont.identity, b"id_X");
+        assert_eq!(
+            scm.msg_cont.message.content_frame().unwrap(),
+            &to_vec(&Response::build(3, None::<()>))
+        );
+    }
+
+    /// Route a message with `content` from `com_A` to the Coordinator and return the response.
+    fn route_to_coordinator<T: serde::de::DeserializeOwned>(
+        c: &mut Coordinator,
+        content: Vec<u8>,
+    ) -> T {
+        let message = Message::build(
+            b"COORDINATOR".to_vec(),
+            b"com_A".to_vec(),
+            None,
+            None,
+            crate::control_protocol::message_types::JSON,
+            crate::core::ContentTypes::Frame(content),
+        );
+        let scm = c
+            .route_message(MessageContainer {
+                identity: b"id_A".to_vec(),
+                message,
+            })
+            .unwrap();
+        serde_json::from_slice(scm.msg_cont.message.content_frame().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_batch_request() {
+        let mut c = make_coordinator();
+        let batch = serde_json::json!([Request::build(1, "pong"), Request::build(2, "unknown")]);
+        let responses: Vec<Value> = route_to_coordinator(&mut c, to_vec(&batch));
+        assert_eq!(
+            responses,
+            [
+                serde_json::json!(Response::build(1, Value::Null)),
+                serde_json::json!(ErrorResponse::from_error(2, &Error::MethodNotFound)),
+            ]
+        );
+        let response: ErrorResponse = route_to_coordinator(&mut c, b"[]".to_vec());
+        assert_eq!(response.id, None);
+        assert_eq!(response.error.code, Error::InvalidRequest.code());
+    }
+
+    #[test]
+    fn test_error_echoes_request_id() {
+        let mut c = make_coordinator();
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, to_vec(&Request::build(42, "unknown")));
+        assert_eq!(response.id, Some(42));
+        assert_eq!(response.error.code, Error::MethodNotFound.code());
+    }
+
+    #[test]
+    fn test_request_error_codes() {
+        let mut c = make_coordinator();
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, to_vec(&Request::build(1, "unknown")));
+        assert_eq!(response.error.code, Error::MethodNotFound.code());
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, br#"{"jsonrpc": "2.0", "id": 2}"#.to_vec());
+        assert_eq!(response.id, Some(2));
+        assert_eq!(response.error.code, Error::InvalidRequest.code());
+        let response: ErrorResponse = route_to_coordinator(&mut c, b"no json".to_vec());
+        assert_eq!(response.id, None);
+        assert_eq!(response.error.code, Error::ParseError.code());
+    }
+
+    #[test]
+    fn test_error_id_of_invalid_requests() {
+        let mut c = make_coordinator();
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, br#"{"id": 7, "method": 5}"#.to_vec());
+        assert_eq!(response.id, Some(7));
+        let response: ErrorResponse = route_to_coordinator(&mut c, b"{\"id\": 7".to_vec());
+        assert_eq!(response.id, None);
+        assert_eq!(response.error.code, Error::ParseError.code());
+    }
+
+    /// Let anyone shut down the Coordinator.
+    struct AllowAll;
+
+    impl ShutdownPolicy for AllowAll {
+        fn may_shut_down(&self, _sender: &FullName, _local: bool, _params: Option<&Value>) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_shut_down_denied_by_default() {
+        let mut c = make_coordinator();
+        let response: ErrorResponse =
+            route_to_coordinator(&mut c, to_vec(&Request::build(4, "shut_down")));
+        assert_eq!(response.id, Some(4));
+        assert_eq!(response.error.code, Error::Unauthorized.code());
+        assert!(!c.stop.is_stopped());
+    }
+
+    #[test]
+    fn test_shut_down_allow_list() {
+        let policy = AllowList::new(["admin"]).with_token("secret");
+        let mut c = make_coordinator().with_shutdown_policy(policy);
+        let sender = |namespace, name| FullName { namespace, name };
+        let token = serde_json::json!({"token": "secret"});
+        let wrong_token = serde_json::json!({"token": "guess"});
+        assert_eq!(
+            c.shut_down(&sender(b"", b"com_A"), None),
+            Err(Error::Unauthorized)
+        );
+        assert_eq!(
+            c.shut_down(&sender(b"N2", b"admin"), None),
+            Err(Error::Unauthorized)
+        );
+        assert_eq!(
+            c.shut_down(&sender(b"N2", b"admin"), Some(&wrong_token)),
+            Err(Error::Unauthorized)
+        );
+        assert!(!c.stop.is_stopped());
+        assert_eq!(c.shut_down(&sender(b"N1", b"admin"), None), Ok(Value::Null));
+        assert!(c.stop.is_stopped());
+        let mut c =
+            make_coordinator().with_shutdown_policy(AllowList::default().with_token("secret"));
+        assert_eq!(
+            c.shut_down(&sender(b"N2", b"com_A"), Some(&token)),
+            Ok(Value::Null)
+        );
+        assert!(c.stop.is_stopped());
+    }
+
+    #[test]
+    fn test_with_communicator() {
+        let (mut coor, port) = make_live_coordinator();
+        let comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.send_rpc_message("COORDINATOR".to_string(), "sign_in")
+            .unwrap();
+        println!("start loop");
+        coor.loop_element();
+        println!("loop stopped");
+        if comm.poll(300).unwrap() {
+            let result = comm.read_rpc_message().unwrap();
+            assert_eq!(result, Value::Null);
+        } else {
+            panic!("No response!")
+        }
+    }
+
+    #[test]
+    fn test_concurrent_live_coordinators() {
+        let coordinators: Vec<_> = ["N1", "N2"]
+            .into_iter()
+            .map(|namespace| {
+                let (mut coor, port) = make_tcp_coordinator(namespace);
+                let handle = std::thread::spawn(move || {
+                    coor.loop_element();
+                    coor
+                });
+                (handle, port)
+            })
+            .collect();
+        assert_ne!(coordinators[0].1, coordinators[1].1);
+        let mut names = Vec::new();
+        for (handle, port) in coordinators {
+            let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+            comm.sign_in().unwrap();
+            names.push(comm.full_name().to_string());
+            let coor = handle.join().unwrap();
+            assert!(coor.components.contains_key(b"comm".as_slice()));
+        }
+        assert_eq!(names, ["N1.comm", "N2.comm"]);
+    }
+
+    #[test]
+    fn test_drop_releases_endpoint() {
+        let (c, port) = make_tcp_coordinator("N1");
+        drop(c);
+        let c = Coordinator::new("N1".to_string(), Some(port), None);
+        assert_eq!(bound_port(&c), port);
+    }
+
+    #[test]
+    fn test_multiple_endpoints() {
+        let ipc = std::env::temp_dir().join(format!("ruleco-{}.ipc", Uuid::now_v7()));
+        let config = CoordinatorConfig {
+            endpoints: vec![Endpoint::Ipc(ipc.to_string_lossy().to_string())],
+            ..Default::default()
+        };
+        let tcp = Endpoint::tcp("127.0.0.1", 0).unwrap();
+        let mut coor =
+            Coordinator::try_with_config("N1".to_string(), Some(&tcp), None, config).unwrap();
+        let endpoints = coor.endpoints().to_vec();
+        assert_eq!(endpoints.len(), 2);
+        let port = bound_port(&coor);
+        assert_eq!(endpoints[0], Endpoint::tcp("127.0.0.1", port).unwrap());
+        let stop = coor.stop_signal();
+        let routing = std::thread::spawn(move || coor.routing());
+        let build = |name, endpoint: &Endpoint| {
+            let mut comm =
+                Communicator::build_with_endpoint(name, endpoint.clone(), None, None).unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let (via_tcp, via_ipc) = (
+            build("via_tcp", &endpoints[0]),
+            build("via_ipc", &endpoints[1]),
+        );
+        via_tcp
+            .send_message_to(
+                "via_ipc",
+                crate::core::ContentTypes::Frame(b"{}".to_vec()),
+                message_types::JSON,
+            )
+            .unwrap();
+        assert!(via_ipc.poll(1000).unwrap());
+        assert_eq!(
+            via_ipc.read_message().unwrap().sender_frame(),
+            b"N1.via_tcp"
+        );
+
+        // The port is taken already.
+        let taken = Endpoint::tcp("127.0.0.1", port).unwrap();
+        let config = CoordinatorConfig {
+            endpoints: vec![taken.clone()],
+            ..Default::default()
+        };
+        let result = Coordinator::try_with_config("N2".to_string(), None, None, config);
+        let err = result.err().unwrap();
+        assert!(matches!(&err, SetupError::Bind { endpoint, .. } if *endpoint == taken));
+        assert!(err
+            .to_string()
+            .starts_with(&format!("Could not bind to {taken}: ")));
+        drop((via_tcp, via_ipc));
+        stop.stop();
+        routing.join().unwrap();
+        let _ = std::fs::remove_file(ipc);
+    }
+
+    #[test]
+    fn test_read_zap_request() {
+        let mut c = make_coordinator();
+        let (authorized, unknown) = ([1u8; 32], [2u8; 32]);
+        c.config.curve = Some(CurveServerConfig {
+            public_key: [3; 32],
+            secret_key: [4; 32],
+            authorized_keys: Some(HashSet::from([authorized])),
+        });
+        // Stand in for zmq, which asks the handler during the CURVE handshake.
+        let zap = c.context.socket(zmq::REP).unwrap();
+        zap.bind("inproc://test_read_zap_request").unwrap();
+        c.zap = Some(zap);
+        let client = c.context.socket(zmq::REQ).unwrap();
+        client.connect("inproc://test_read_zap_request").unwrap();
+        let mut authenticate = |mechanism: &[u8], key: &[u8]| {
+            let request: [&[u8]; 7] = [b"1.0", b"7", b"", b"127.0.0.1", b"", mechanism, key];
+            client.send_multipart(request, 0).unwrap();
+            c.zap.as_ref().unwrap().poll(zmq::POLLIN, 1000).unwrap();
+            c.read_zap_request();
+            let reply = client.recv_multipart(0).unwrap();
+            assert_eq!(reply[..2], [b"1.0".to_vec(), b"7".to_vec()]);
+            reply[2].clone()
+        };
+        assert_eq!(authenticate(b"CURVE", &authorized), b"200");
+        assert_eq!(authenticate(b"CURVE", &unknown), b"400");
+        assert_eq!(authenticate(b"PLAIN", &authorized), b"400");
+        assert_eq!(authenticate(b"CURVE", b"short"), b"400");
+        assert_eq!(c.statistics.unauthorized, 3);
+    }
+
+    #[test]
+    fn test_curve() {
+        // The handshake needs libzmq built with CURVE, the authentication is tested above.
+        if !zmq::has("curve").unwrap_or_default() {
+            return;
+        }
+        let server = zmq::CurveKeyPair::new().unwrap();
+        let (client, intruder) = (
+            zmq::CurveKeyPair::new().unwrap(),
+            zmq::CurveKeyPair::new().unwrap(),
+        );
+        let ctx = zmq::Context::new();
+        let config = CoordinatorConfig {
+            curve: Some(CurveServerConfig {
+                public_key: server.public_key,
+                secret_key: server.secret_key,
+                authorized_keys: Some(HashSet::from([client.public_key])),
+            }),
+            ..Default::default()
+        };
+        let tcp = Endpoint::tcp("127.0.0.1", 0).unwrap();
+        let mut coor =
+            Coordinator::try_with_config("N1".to_string(), Some(&tcp), Some(ctx), config).unwrap();
+        let endpoint = coor.endpoint().unwrap().clone();
+        let stop = coor.stop_signal();
+        let routing = std::thread::spawn(move || {
+            coor.routing();
+            coor
+        });
+        let build = |name, keys: &zmq::CurveKeyPair| {
+            let options = CommunicatorOptions::new(name)
+                .endpoint(endpoint.clone())
+                .timeout(Duration::from_millis(300))
+                .curve_keys(CurveClientKeys {
+                    server_public_key: server.public_key,
+                    public_key: keys.public_key,
+                    secret_key: keys.secret_key,
+                });
+            Communicator::build_with(options).unwrap()
+        };
+        let mut comm = build("comm", &client);
+        comm.sign_in().unwrap();
+        let mut intruder = build("intruder", &intruder);
+        assert!(matches!(
+            intruder.sign_in(),
+            Err(CommunicatorError::Timeout)
+        ));
+        drop((comm, intruder));
+        stop.stop();
+        let coor = routing.join().unwrap();
+        assert!(coor.statistics.unauthorized >= 1);
+    }
+
+    #[test]
+    fn test_load_authorized_keys() {
+        let directory = std::env::temp_dir().join(format!("ruleco-keys-{}", Uuid::now_v7()));
+        fs::create_dir(&directory).unwrap();
+        let (key_a, key_b) = ([1u8; 32], [2u8; 32]);
+        let encoded_a = zmq::z85_encode(&key_a).unwrap();
+        let encoded_b = zmq::z85_encode(&key_b).unwrap();
+        fs::write(directory.join("a.key"), format!("{encoded_a}\n")).unwrap();
+        let certificate = format!("metadata\ncurve\n    public-key = \"{encoded_b}\"\n");
+        fs::write(directory.join("b.key"), certificate).unwrap();
+        let keys = CurveServerConfig::load_authorized_keys(&directory).unwrap();
+        assert_eq!(keys, HashSet::from([key_a, key_b]));
+        fs::write(directory.join("c.key"), "no key").unwrap();
+        let err = CurveServerConfig::load_authorized_keys(&directory).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("c.key"));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_ask_with_interleaved_message() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let mut comm_a = Communicator::build("comm_a", None, Some(port), None).unwrap();
+        let comm_b = Communicator::build("comm_b", None, Some(port), None).unwrap();
+        for comm in [&comm_a, &comm_b] {
+            comm.send_rpc_message("COORDINATOR".to_string(), "sign_in")
+                .unwrap();
+            coor.loop_element();
+            assert!(comm.poll(300).unwrap());
+            comm.read_rpc_message().unwrap();
+        }
+        // An unrelated message for comm_a is queued before the response.
+        comm_b
+            .send_rpc_message("comm_a".to_string(), "get_speed")
+            .unwrap();
+        coor.loop_element();
+        let handle = std::thread::spawn(move || {
+            coor.loop_element();
+        });
+        let result = comm_a.ask("COORDINATOR", "pong", None, Duration::from_secs(1));
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), Value::Null);
+        let buffered = comm_a.read_buffered().unwrap();
+        assert_eq!(buffered.sender_frame(), b"N1.comm_b");
+    }
+
+    #[test]
+    fn test_communicator_uses_full_name_after_sign_in() {
+        let (coor, port) = make_tcp_coordinator("N1");
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+            coor
+        });
+        comm.sign_in().unwrap();
+        let mut coor = handle.join().unwrap();
+        comm.send_rpc_message("N1.other".to_string(), "pong")
+            .unwrap();
+        let msg_cont = coor.read_message().unwrap();
+        assert_eq!(msg_cont.message.sender_frame(), b"N1.comm");
+    }
+
+    #[test]
+    fn test_communicator_sign_in_duplicate_name() {
+        let (coor, port) = make_tcp_coordinator("N1");
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            // One sign-in each for the first and the rejected, two for the renaming one.
+            for _ in 0..4 {
+                coor.loop_element();
+            }
+            coor
+        });
+        let mut first = Communicator::build("comm", None, Some(port), None).unwrap();
+        first.sign_in().unwrap();
+        let mut renaming = Communicator::build("comm", None, Some(port), None)
+            .unwrap()
+            .with_rename_attempts(3);
+        renaming.sign_in().unwrap();
+        assert_eq!(renaming.name(), "comm2");
+        assert_eq!(renaming.full_name(), "N1.comm2");
+        let mut rejected = Communicator::build("comm", None, Some(port), None).unwrap();
+        let result = rejected.sign_in();
+        let coor = handle.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::DuplicateName))
+        ));
+        let mut names: Vec<_> = coor.components.keys().cloned().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![b"comm".as_slice().into(), b"comm2".as_slice().into()]
+        );
+    }
+
+    #[test]
+    fn test_communicator_signs_out_on_drop() {
+        let (coor, port) = make_tcp_coordinator("N1");
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+            assert!(coor.components.contains_key(b"comm".as_slice()));
+            coor.loop_element();
+            coor
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        drop(comm);
+        let coor = handle.join().unwrap();
+        assert!(coor.components.is_empty());
+    }
+
+    #[test]
+    fn test_communicator_close() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_communicator_close".to_string());
+        let coor =
+            Coordinator::with_endpoint("N1".to_string(), Some(&endpoint), Some(ctx.clone()), None);
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+            coor.loop_element();
+            coor
+        });
+        let mut comm =
+            Communicator::build_with_endpoint("comm", endpoint, Some(ctx), None).unwrap();
+        comm.sign_in().unwrap();
+        comm.close().unwrap();
+        let coor = handle.join().unwrap();
+        assert!(coor.components.is_empty());
+    }
+
+    #[test]
+    fn test_communicator_reconnect() {
+        let (coor, port) = make_tcp_coordinator("N1");
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        // The first Coordinator goes away.
+        handle.join().unwrap();
+
+        let (coor, port) = make_tcp_coordinator("N2");
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+            coor.loop_element();
+        });
+        comm.reconnect(None, Some(port)).unwrap();
+        assert_eq!(
+            comm.endpoint().to_string(),
+            format!("tcp://localhost:{port}")
+        );
+        let result = comm.ask("COORDINATOR", "pong", None, Duration::from_secs(1));
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_communicator_names_after_sign_out() {
+        let (coor, port) = make_tcp_coordinator("N1");
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            coor.loop_element();
+            coor.loop_element();
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        assert_eq!(comm.full_name(), "N1.comm");
+        assert_eq!(comm.namespace(), Some("N1"));
+        comm.sign_out().unwrap();
+        handle.join().unwrap();
+        assert_eq!(comm.full_name(), "comm");
+        assert_eq!(comm.namespace(), None);
+    }
+
+    #[test]
+    fn test_communicator_signs_in_after_coordinator_restart() {
+        // The restarted Coordinator binds to the same port, which, unlike a free port, is not
+        // taken by other tests in the meantime.
+        let port = 12332;
+        let coordinator = std::thread::spawn(move || {
+            let mut coor = Coordinator::new("N1".to_string(), Some(port), None);
+            coor.loop_element();
+            coor.loop_element();
+        });
+        let timeout = Duration::from_secs(2);
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        comm.ask("COORDINATOR", "pong", None, timeout).unwrap();
+        coordinator.join().unwrap();
+
+        // The dropped Coordinator released the port.
+        let mut coor = Coordinator::new("N1".to_string(), Some(port), None);
+        let coordinator = std::thread::spawn(move || {
+            // The rejected request, the sign-in, and the repeated request.
+            for _ in 0..3 {
+                coor.loop_element();
+            }
+            coor
+        });
+        let result = comm.ask("COORDINATOR", "send_local_components", None, timeout);
+        let coor = coordinator.join().unwrap();
+        assert_eq!(result.unwrap(), json!(["comm"]));
+        assert!(coor.components.contains_key(b"comm".as_slice()));
+    }
+
+    #[test]
+    fn test_communicator_state() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Sign in, sign out, and sign in again.
+            for _ in 0..3 {
+                coor.loop_element();
+            }
+            coor.components.clear();
+            coor.loop_element();
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None)
+            .unwrap()
+            .with_auto_sign_in(false);
+        assert_eq!(comm.state(), CommunicatorState::Connected);
+        comm.sign_in().unwrap();
+        let signed_in = CommunicatorState::SignedIn {
+            namespace: "N1".to_string(),
+        };
+        assert_eq!(comm.state(), signed_in);
+        assert!(comm.is_signed_in());
+        comm.sign_out().unwrap();
+        assert_eq!(comm.state(), CommunicatorState::Connected);
+        comm.sign_in().unwrap();
+        assert_eq!(comm.state(), signed_in);
+        // The Coordinator forgot us.
+        let timeout = Duration::from_secs(1);
+        let result = comm.ask("COORDINATOR", "send_local_components", None, timeout);
+        coordinator.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::NotSignedIn))
+        ));
+        assert_eq!(comm.state(), CommunicatorState::Connected);
+        assert!(!comm.is_signed_in());
+        let too_long = Endpoint::Ipc("x".repeat(200));
+        assert!(comm.reconnect_to(too_long).is_err());
+        assert_eq!(comm.state(), CommunicatorState::Disconnected);
+    }
+
+    #[test]
+    fn test_communicator_probe() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Probe before and after signing in.
+            for _ in 0..5 {
+                coor.loop_element();
+            }
+            coor
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        let timeout = Duration::from_secs(1);
+        let info = comm.probe(timeout).unwrap();
+        assert_eq!(info.name.as_deref(), Some("N1.COORDINATOR"));
+        assert_eq!(info.namespace.as_deref(), Some("N1"));
+        assert_eq!(info.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert!(info.round_trip < timeout);
+        assert!(!comm.is_signed_in());
+        comm.sign_in().unwrap();
+        assert_eq!(
+            comm.probe(timeout).unwrap().namespace.as_deref(),
+            Some("N1")
+        );
+        let coordinator = coordinator.join().unwrap();
+        // Probing does not sign in.
+        assert_eq!(coordinator.components.len(), 1);
+    }
+
+    #[test]
+    fn test_pong_before_sign_in() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            for _ in 0..3 {
+                coor.loop_element();
+            }
+            coor
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        let timeout = Duration::from_secs(1);
+        for receiver in ["COORDINATOR", "N1.COORDINATOR"] {
+            let result = comm.ask(receiver, "pong", None, timeout);
+            assert_eq!(result.unwrap(), Value::Null);
+        }
+        // Other Coordinators are not reachable without signing in.
+        let result = comm.ask("N2.COORDINATOR", "pong", None, timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::NotSignedIn))
+        ));
+        let coordinator = coordinator.join().unwrap();
+        assert!(coordinator.components.is_empty());
+    }
+
+    #[test]
+    fn test_communicator_next_request() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Two sign-ins and two requests with their responses.
+            for _ in 0..6 {
+                coor.loop_element();
+            }
+        });
+        let mut director = Communicator::build("director", None, Some(port), None).unwrap();
+        let mut actor = Communicator::build("actor", None, Some(port), None).unwrap();
+        director.sign_in().unwrap();
+        actor.sign_in().unwrap();
+        let actor = std::thread::spawn(move || {
+            let timeout = Duration::from_secs(1);
+            let request = actor.next_request(timeout).unwrap();
+            assert_eq!(request.method(), "add");
+            assert_eq!(request.sender(), "N1.director");
+            let params = request.params().unwrap().as_array().unwrap();
+            let sum: i64 = params.iter().filter_map(Value::as_i64).sum();
+            request.respond(sum).unwrap();
+            let request = actor.next_request(timeout).unwrap();
+            request.respond_error(&Error::MethodNotFound).unwrap();
+            actor
+        });
+        let timeout = Duration::from_secs(1);
+        let params = Some(serde_json::json!([2, 3]));
+        assert_eq!(director.ask("actor", "add", params, timeout).unwrap(), 5);
+        let result = director.ask("actor", "subtract", None, timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::MethodNotFound))
+        ));
+        let _actor = actor.join().unwrap();
+        coordinator.join().unwrap();
+    }
+
+    #[test]
+    fn test_communicator_sign_in_with_retries() {
+        // The Coordinator starts after the component, so its port has to be known beforehand.
+        // A free port might be taken by another test in the meantime, unlike a fixed one
+        // outside of the ephemeral range.
+        let handle = std::thread::spawn(|| {
+            std::thread::sleep(Duration::from_millis(300));
+            let mut coor = Coordinator::new("N1".to_string(), Some(12336), None);
+            coor.loop_element();
+        });
+        let mut comm = Communicator::build("comm", None, Some(12336), None).unwrap();
+        comm.sign_in_with_retries(20, Duration::from_millis(100))
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(comm.full_name(), "N1.comm");
+    }
+
+    #[test]
+    fn test_actor() {
+        struct Stage {
+            position: f64,
+            ticks: u32,
+        }
+
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Two sign-ins, five requests to the actor with responses, and its sign-out.
+            for _ in 0..13 {
+                coor.loop_element();
+            }
+        });
+        let mut comm = Communicator::build("stage", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        let mut actor = Actor::new(
+            comm,
+            Stage {
+                position: 0.0,
+                ticks: 0,
+            },
+        );
+        actor.add_getter("position", |stage| Value::from(stage.position));
+        actor.add_setter("position", |stage, value| {
+            stage.position = value.as_f64().ok_or(Error::InvalidParams)?;
+            Ok(())
+        });
+        actor.add_action("move_by", |stage, args, _kwargs| {
+            stage.position += args.first().and_then(Value::as_f64).unwrap_or_default();
+            Ok(Value::from(stage.position))
+        });
+        actor.set_housekeeping(Duration::from_millis(1), |stage| stage.ticks += 1);
+        let device = actor.device();
+        let (handle, _stop) = actor.spawn();
+
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        let position = |comm: &mut Communicator| {
+            comm.get_properties("stage", &["position"]).unwrap()["position"].clone()
+        };
+        assert_eq!(position(&mut comm), 0.0);
+        comm.set_properties(
+            "stage",
+            HashMap::from([("position".to_string(), Value::from(2.5))]),
+        )
+        .unwrap();
+        let moved = comm.call_action("stage", "move_by", &[Value::from(1)], HashMap::new());
+        assert_eq!(moved.unwrap(), 3.5);
+        assert_eq!(position(&mut comm), 3.5);
+        comm.ask("stage", "shut_down", None, Duration::from_secs(1))
+            .unwrap();
+        handle.join().unwrap().unwrap();
+        coordinator.join().unwrap();
+        assert!(device.lock().unwrap().ticks > 0);
+    }
+
+    /// Make a Coordinator with short intervals, whose Component `com_A` is silent for long.
+    fn make_silent_component() -> Coordinator {
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::from_millis(100),
+            expiry_timeout: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let mut c = Coordinator::with_config("N1".to_string(), None, None, config);
+        let mut component = Component::build(b"id_A");
+        component.timestamp -= Duration::from_secs(1);
+        c.components.insert(b"com_A".as_slice().into(), component);
+        c
+    }
+
+    #[test]
+    fn test_answered_ping_keeps_component() {
+        let mut c = make_silent_component();
+        c.check_timeouts();
+        let component = c.components.get_mut(b"com_A".as_slice()).unwrap();
+        assert!(component.pinged_at.is_some());
+        // The answer arrives just before the cutoff.
+        component.pinged_at = Some(Instant::now() - Duration::from_millis(99));
+        component.record(&make_message());
+        assert_eq!(component.pinged_at, None);
+        std::thread::sleep(Duration::from_millis(10));
+        c.check_timeouts();
+        assert!(c.components.contains_key(b"com_A".as_slice()));
+    }
+
+    #[test]
+    fn test_unanswered_ping_removes_component() {
+        let mut c = make_silent_component();
+        c.check_timeouts();
+        // Silent for longer than the expiry timeout, but pinged just now.
+        assert!(c.components.contains_key(b"com_A".as_slice()));
+        let pinged_at = c.components[b"com_A".as_slice()].pinged_at;
+        c.check_timeouts();
+        assert_eq!(c.components[b"com_A".as_slice()].pinged_at, pinged_at);
+        std::thread::sleep(Duration::from_millis(100));
+        c.check_timeouts();
+        assert!(!c.components.contains_key(b"com_A".as_slice()));
+    }
+
+    #[test]
+    fn test_ping_response() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_ping_response".to_string());
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut c =
+            Coordinator::with_config("N1".to_string(), Some(&endpoint), Some(ctx.clone()), config);
+        let component = ctx.socket(zmq::DEALER).unwrap();
+        component.connect(&endpoint.to_string()).unwrap();
+        let send = |receiver: &[u8], cid: Option<&[u8]>, content: Vec<u8>| {
+            let message = Message::build(
+                receiver.to_vec(),
+                b"comp".to_vec(),
+                cid,
+                None,
+                message_types::JSON,
+                crate::core::ContentTypes::Frame(content),
+            );
+            component.send_multipart(message.to_frames(), 0).unwrap();
+        };
+        let receive = || {
+            if component.poll(zmq::POLLIN, 100).unwrap() == 0 {
+                return None;
+            }
+            Some(Message::new(component.recv_multipart(0).unwrap()).unwrap())
+        };
+        send(b"COORDINATOR", None, to_vec(&Request::build(1, "sign_in")));
+        c.loop_element();
+        receive().unwrap();
+
+        c.check_timeouts();
+        let ping = receive().unwrap();
+        let request: Request = serde_json::from_slice(ping.content_frame().unwrap()).unwrap();
+        assert_eq!(request.method, "pong");
+        let cid = ping.header().conversation_id;
+        assert_eq!(c.components[b"comp".as_slice()].last_ping.unwrap(), cid);
+        send(
+            b"N1.COORDINATOR",
+            Some(cid),
+            to_vec(&Response::build(request.id, Value::Null)),
+        );
+        c.loop_element();
+        assert_eq!(c.components[b"comp".as_slice()].last_ping, None);
+        // Neither the answer nor an unexpected response is answered.
+        send(
+            b"N1.COORDINATOR",
+            None,
+            to_vec(&Response::build(request.id, Value::Null)),
+        );
+        c.loop_element();
+        assert!(receive().is_none());
+        assert!(c.statistics.rejected.is_empty());
+        drop(component);
+    }
+
+    #[test]
+    fn test_communicator_heartbeat() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_communicator_heartbeat".to_string());
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::from_millis(300),
+            expiry_timeout: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let coor =
+            Coordinator::with_config("N1".to_string(), Some(&endpoint), Some(ctx.clone()), config);
+        let (signed_in, wait_for_sign_in) = std::sync::mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || {
+            let mut coor = coor;
+            let mut start = None;
+            while start.is_none_or(|start: Instant| start.elapsed() < Duration::from_millis(800)) {
+                if coor.router.poll(zmq::POLLIN, 10).unwrap() == 1 {
+                    coor.loop_element();
+                }
+                coor.check_timeouts();
+                if start.is_none() && wait_for_sign_in.try_recv().is_ok() {
+                    start = Some(Instant::now());
+                }
+            }
+            coor
+        });
+        let mut beating = Communicator::build_with_endpoint(
+            "beating",
+            endpoint.clone(),
+            Some(ctx.clone()),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap();
+        beating.sign_in().unwrap();
+        beating.start_heartbeat(Duration::from_millis(100));
+        let mut silent =
+            Communicator::build_with_endpoint("silent", endpoint, Some(ctx), None).unwrap();
+        silent.sign_in().unwrap();
+        signed_in.send(()).unwrap();
+        let coor = handle.join().unwrap();
+        assert!(coor.components.contains_key(b"beating".as_slice()));
+        assert!(!coor.components.contains_key(b"silent".as_slice()));
+        beating.stop_heartbeat();
+        // Late responses to the heartbeat are skipped.
+        assert!(matches!(
+            beating.read_message(),
+            Err(CommunicatorError::Timeout)
+        ));
+        // Sign out while the Coordinator's socket is still open.
+        drop(silent);
+        drop(beating);
+    }
+
+    #[test]
+    fn test_heartbeat_notices_lost_sign_in() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            coor.loop_element();
+            coor.components.clear();
+            // The rejected heartbeat
+            coor.loop_element();
+        });
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        comm.start_heartbeat(Duration::from_millis(20));
+        coordinator.join().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while comm.is_signed_in() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!comm.is_signed_in());
+        comm.stop_heartbeat();
+    }
+
+    #[test]
+    fn test_routing_removes_silent_component() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_routing_silent".to_string());
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::from_millis(100),
+            expiry_timeout: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let mut coor =
+            Coordinator::with_config("N1".to_string(), Some(&endpoint), Some(ctx.clone()), config)
+                .with_shutdown_policy(AllowList::new(["admin"]));
+        let handle = std::thread::spawn(move || {
+            coor.routing();
+            coor
+        });
+        let mut silent =
+            Communicator::build_with_endpoint("silent", endpoint.clone(), Some(ctx.clone()), None)
+                .unwrap();
+        silent.sign_in().unwrap();
+        // Neither reading pings nor sending anything.
+        std::thread::sleep(Duration::from_millis(600));
+        // It has been pinged before being removed.
+        assert!(silent.poll(0).unwrap());
+        let mut admin =
+            Communicator::build_with_endpoint("admin", endpoint, Some(ctx), None).unwrap();
+        admin.sign_in().unwrap();
+        admin
+            .ask("COORDINATOR", "shut_down", None, Duration::from_secs(1))
+            .unwrap();
+        let coor = handle.join().unwrap();
+        assert!(!coor.components.contains_key(b"silent".as_slice()));
+        assert!(coor.components.contains_key(b"admin".as_slice()));
+        // Sign out while the Coordinator's socket is still open.
+        drop(silent);
+        drop(admin);
+    }
+
+    /// Start a Coordinator of `namespace` at `inproc://{namespace}_{test}` in a routing thread,
+    /// after `setup`.
+    fn spawn_coordinator(
+        ctx: &zmq::Context,
+        namespace: &str,
+        test: &str,
+        setup: impl FnOnce(&mut Coordinator),
+    ) -> std::thread::JoinHandle<Coordinator> {
+        let endpoint = Endpoint::Inproc(format!("{namespace}_{test}"));
+        let mut coor = Coordinator::with_endpoint(
+            namespace.to_string(),
+            Some(&endpoint),
+            Some(ctx.clone()),
+            None,
+        )
+        .with_shutdown_policy(AllowAll);
+        setup(&mut coor);
+        std::thread::spawn(move || {
+            coor.routing();
+            coor
+        })
+    }
+
+    /// Let the `coordinators` handle their messages for a while.
+    fn step(coordinators: &mut [&mut Coordinator]) {
+        for _ in 0..10 {
+            for coordinator in coordinators.iter_mut() {
+                coordinator.poll_sockets(5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_coordinator_sign_in() {
+        let ctx = zmq::Context::new();
+        let make = |namespace: &str| {
+            let endpoint = Endpoint::Inproc(format!("{namespace}_handshake"));
+            Coordinator::with_endpoint(
+                namespace.to_string(),
+                Some(&endpoint),
+                Some(ctx.clone()),
+                None,
+            )
+        };
+        let (mut n1, mut n2, mut n3) = (make("N1"), make("N2"), make("N3"));
+        n1.add_node("N2", Endpoint::Inproc("N2_handshake".to_string()))
+            .unwrap();
+        n3.add_node("N2", Endpoint::Inproc("N2_handshake".to_string()))
+            .unwrap();
+        step(&mut [&mut n1, &mut n2, &mut n3]);
+        // N2 connected back to both.
+        assert!(n2.nodes.contains_key(b"N1".as_slice()));
+        assert!(n2.nodes.contains_key(b"N3".as_slice()));
+        let mut namespaces: Vec<_> = n2.node_identities.values().cloned().collect();
+        namespaces.sort();
+        assert_eq!(namespaces, [b"N1".to_vec(), b"N3".to_vec()]);
+        assert!(n1.node_identities.values().any(|ns| ns == b"N2"));
+        // Nodes are no Components.
+        assert!(n1.components.is_empty() && n2.components.is_empty());
+
+        n1.remove_node(b"N2");
+        step(&mut [&mut n1, &mut n2, &mut n3]);
+        assert!(n1.nodes.is_empty() && n1.node_identities.is_empty());
+        assert!(!n2.nodes.contains_key(b"N1".as_slice()));
+        assert!(!n2.node_identities.values().any(|ns| ns == b"N1"));
+        assert!(n2.nodes.contains_key(b"N3".as_slice()));
+    }
+
+    #[test]
+    fn test_components_are_pushed_to_nodes() {
+        let ctx = zmq::Context::new();
+        let make = |namespace: &str| {
+            let endpoint = Endpoint::Inproc(format!("{namespace}_push"));
+            Coordinator::with_endpoint(
+                namespace.to_string(),
+                Some(&endpoint),
+                Some(ctx.clone()),
+                None,
+            )
+        };
+        let (mut n1, mut n2) = (make("N1"), make("N2"));
+        n1.add_node("N2", Endpoint::Inproc("N2_push".to_string()))
+            .unwrap();
+        step(&mut [&mut n1, &mut n2]);
+        let cached = |c: &Coordinator, namespace: &[u8]| {
+            c.nodes[namespace]
+                .components
+                .as_ref()
+                .map(|(list, _)| list.clone())
+        };
+        assert_eq!(cached(&n1, b"N2"), Some(json!([])));
+        assert_eq!(cached(&n2, b"N1"), Some(json!([])));
+
+        let name = FullName::from_slice(b"comm").unwrap();
+        n2.sign_in(b"id_comm", &name).unwrap();
+        step(&mut [&mut n1, &mut n2]);
+        assert_eq!(cached(&n1, b"N2"), Some(json!(["comm"])));
+        // Served from the cache, as N2 does not answer meanwhile.
+        assert_eq!(
+            n1.global_components(None).unwrap(),
+            json!({"N1": [], "N2": ["comm"]})
+        );
+
+        n2.sign_out(&name).unwrap();
+        step(&mut [&mut n1, &mut n2]);
+        assert_eq!(cached(&n1, b"N2"), Some(json!([])));
+    }
+
+    #[test]
+    fn test_global_components_refresh() {
+        let ctx = zmq::Context::new();
+        let make = |namespace: &str| {
+            let endpoint = Endpoint::Inproc(format!("{namespace}_refresh"));
+            Coordinator::with_endpoint(
+                namespace.to_string(),
+                Some(&endpoint),
+                Some(ctx.clone()),
+                None,
+            )
+        };
+        let mut n2 = make("N2");
+        let stop = n2.stop_signal();
+        let n2 = std::thread::spawn(move || {
+            n2.routing();
+            n2
+        });
+        let mut n1 = make("N1");
+        n1.add_node("N2", Endpoint::Inproc("N2_refresh".to_string()))
+            .unwrap();
+        step(&mut [&mut n1]);
+        let stale = Some((json!(["old"]), Instant::now()));
+        n1.nodes.get_mut(b"N2".as_slice()).unwrap().components = stale;
+        assert_eq!(n1.global_components(None).unwrap()["N2"], json!(["old"]));
+        let params = json!({"refresh": true});
+        let refreshed = n1.global_components(Some(&params)).unwrap();
+        assert_eq!(refreshed["N2"], json!([]));
+        let params = json!({"refresh": "yes"});
+        let result = n1.global_components(Some(&params));
+        assert_eq!(result, Err(Error::InvalidParams));
+        stop.stop();
+        let n2 = n2.join().unwrap();
+        drop((n1, n2));
+    }
+
+    #[test]
+    fn test_namespace_conflict() {
+        let ctx = zmq::Context::new();
+        let spawn = |namespace: &str, address: &str| {
+            let endpoint = Endpoint::Inproc(address.to_string());
+            let mut coor = Coordinator::with_endpoint(
+                namespace.to_string(),
+                Some(&endpoint),
+                Some(ctx.clone()),
+                None,
+            );
+            let stop = coor.stop_signal();
+            let handle = std::thread::spawn(move || {
+                coor.routing();
+                coor
+            });
+            (handle, stop)
+        };
+        let twin = spawn("N1", "twin_conflict");
+        let other = spawn("N2", "N2_conflict");
+        let mut c = Coordinator::with_endpoint("N1".to_string(), None, Some(ctx.clone()), None);
+        let params = json!({"nodes": {
+            "N1": "inproc://N1_conflict",
+            "N2": "inproc://N2_conflict",
+            "N3": "inproc://twin_conflict",
+        }});
+        let result = c.add_nodes(Some(params), false).unwrap();
+        assert_eq!(result["N1"]["code"], -32098);
+        assert_eq!(result["N2"], Value::Null);
+        // The twin rejects our sign-in.
+        assert_eq!(result["N3"]["code"], -32098);
+        assert_eq!(result["N3"]["data"], json!({"namespace": "N1"}));
+        let namespaces: Vec<_> = c.nodes.keys().cloned().collect();
+        assert_eq!(namespaces, [b"N2".to_vec()]);
+        drop(c);
+        for (handle, stop) in [twin, other] {
+            stop.stop();
+            let coor = handle.join().unwrap();
+            drop(coor);
+        }
+    }
+
+    #[test]
+    fn test_node_expiry_and_reconnect() {
+        let ctx = zmq::Context::new();
+        let config = CoordinatorConfig {
+            heartbeat_interval: Duration::from_millis(20),
+            expiry_timeout: Duration::from_millis(60),
+            ..Default::default()
+        };
+        let make = |namespace: &str| {
+            let endpoint = Endpoint::Inproc(format!("{namespace}_expiry"));
+            Coordinator::with_config(
+                namespace.to_string(),
+                Some(&endpoint),
+                Some(ctx.clone()),
+                config.clone(),
+            )
+        };
+        let reachable = |coordinator: &Coordinator| coordinator.nodes[b"N2".as_slice()].reachable;
+        let mut n1 = make("N1");
+        let mut n2 = make("N2");
+        n1.add_node("N2", Endpoint::Inproc("N2_expiry".to_string()))
+            .unwrap();
+        // Answered pings keep the silent node alive.
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(200) {
+            step(&mut [&mut n1, &mut n2]);
+            n1.check_timeouts();
+            n2.check_timeouts();
+        }
+        assert!(reachable(&n1));
+        assert_eq!(n1.statistics.nodes_expired, 0);
+        drop(n2);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reachable(&n1) && Instant::now() < deadline {
+            n1.poll_sockets(5);
+            n1.check_timeouts();
+        }
+        assert!(!reachable(&n1));
+        assert_eq!(n1.statistics.nodes_expired, 1);
+        // The directory does not wait for the unreachable node.
+        let start = Instant::now();
+        let directory = n1.global_components(None).unwrap();
+        assert!(start.elapsed() < NODE_QUERY_TIMEOUT);
+        assert_eq!(directory["N2"], Value::Null);
+        let (request, _cid) =
+            Message::build_request(b"N2.comm".to_vec(), b"N1.comm".to_vec(), 1, "pong", None);
+        let receiver = request.receiver().unwrap();
+        assert_eq!(
+            n1.find_routing_information(&receiver),
+            Err(Error::NodeUnknown)
+        );
+
+        let mut n2 = make("N2");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !reachable(&n1) && Instant::now() < deadline {
+            step(&mut [&mut n1, &mut n2]);
+            n1.check_timeouts();
+        }
+        assert!(reachable(&n1));
+        assert_eq!(n1.statistics.nodes_reconnected, 1);
+        step(&mut [&mut n1, &mut n2]);
+        // The returned node connected back to us.
+        assert!(n2.nodes.contains_key(b"N1".as_slice()));
+        assert_eq!(n1.global_components(None).unwrap()["N2"], json!([]));
+    }
+
+    #[test]
+    fn test_coordinator_sign_in_unknown_node() {
+        let mut coor = make_coordinator();
+        let (request, _cid) =
+            Message::build_request(b"N1.comm".to_vec(), b"N2.comm".to_vec(), 1, "pong", None);
+        let sender = request.sender().unwrap();
+        let receiver = request.receiver().unwrap();
+        let result = coor.check_message(b"id_X", &request, &sender, &receiver);
+        assert_eq!(result, Err(Error::NodeUnknown));
+    }
+
+    #[test]
+    fn test_send_local_components() {
+        let c = make_coordinator();
+        assert_eq!(c.local_components(), serde_json::json!(["com_A", "com_B"]));
+    }
+
+    #[test]
+    fn test_admin_handle() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_admin_handle".to_string());
+        let (mut coordinator, admin) = Coordinator::with_admin(
+            "N1".to_string(),
+            Some(&endpoint),
+            Some(ctx.clone()),
+            CoordinatorConfig::default(),
+        );
+        let routing = std::thread::spawn(move || {
+            coordinator.routing();
+            coordinator
+        });
+        let mut comm =
+            Communicator::build_with_endpoint("comm", endpoint, Some(ctx.clone()), None).unwrap();
+        comm.sign_in().unwrap();
+        assert_eq!(admin.list_components().unwrap(), ["comm"]);
+        assert_eq!(admin.stats().unwrap()["components"], 1);
+        assert!(admin.kick("comm").unwrap());
+        assert!(!admin.kick("comm").unwrap());
+        assert!(admin.list_components().unwrap().is_empty());
+
+        admin.stop().unwrap();
+        let coordinator = routing.join().unwrap();
+        assert!(coordinator.stop_signal().is_stopped());
+        drop(comm);
+    }
+
+    #[test]
+    fn test_admin_handle_discards_late_answers() {
+        let (mut coordinator, mut admin) =
+            Coordinator::with_admin("N1".to_string(), None, None, CoordinatorConfig::default());
+        admin.set_timeout(Duration::from_millis(100));
+        assert!(matches!(admin.stats(), Err(AdminError::Timeout)));
+        let routing = std::thread::spawn(move || coordinator.routing());
+        // The late answer to `stats` is not taken for the answer to this command.
+        assert_eq!(admin.list_components().unwrap(), Vec::<String>::new());
+        admin.stop().unwrap();
+        routing.join().unwrap();
+    }
+
+    #[test]
+    fn test_trace() {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Info);
+        }
+        let traces = || -> Vec<String> {
+            let records = LOGGER.0.lock().unwrap();
+            let traces = records.iter().map(|(_, text)| text);
+            traces
+                .filter(|text| text.starts_with("Trace "))
+                .cloned()
+                .collect()
+        };
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_trace".to_string());
+        let (mut coordinator, admin) = Coordinator::with_admin(
+            "N1".to_string(),
+            Some(&endpoint),
+            Some(ctx.clone()),
+            CoordinatorConfig::default(),
+        );
+        let routing = std::thread::spawn(move || coordinator.routing());
+        let build = |name| {
+            Communicator::build_with_endpoint(name, endpoint.clone(), Some(ctx.clone()), None)
+                .unwrap()
+        };
+        admin.trace(["com_T"]).unwrap();
+        let (mut traced, mut untraced) = (build("com_T"), build("com_U"));
+        traced.sign_in().unwrap();
+        untraced.sign_in().unwrap();
+        let records = traces();
+        assert_eq!(records.len(), 2, "{records:?}");
+        assert!(records[0].starts_with("Trace in com_T -> COORDINATOR"));
+        assert!(records[0].contains(r#""method":"sign_in""#));
+        assert!(records[1].starts_with("Trace out N1.COORDINATOR -> "));
+        assert!(records.iter().all(|record| !record.contains("com_U")));
+
+        admin.trace([]).unwrap();
+        traced.sign_out().unwrap();
+        assert_eq!(traces().len(), 2);
+        admin.stop().unwrap();
+        routing.join().unwrap();
+    }
+
+    #[test]
+    fn test_receiver_gone() {
+        let mut c = make_coordinator();
+        let message = Message::build(
+            b"N1.com_B".to_vec(),
+            b"N1.com_A".to_vec(),
+            None,
+            None,
+            message_types::JSON,
+            crate::core::ContentTypes::Frame(b"{}".to_vec()),
+        );
+        let identity = b"id_B".to_vec();
+        c.send_local_message(MessageContainer { identity, message });
+        // Neither com_B nor com_A, which is told about it, are connected.
+        assert_eq!(c.statistics.dropped, 2);
+        assert_eq!(c.statistics.rejected[&-32099], 1);
+    }
+
+    #[test]
+    fn test_unreachable_node_does_not_block() {
+        let mut c = make_coordinator();
+        // Nobody listens, such that the messages queue up to the high water mark.
+        c.add_node("N2", Endpoint::tcp("localhost", 12399).unwrap())
+            .unwrap();
+        for _ in 0..1100 {
+            let message = Message::build(
+                b"N2.com_B".to_vec(),
+                b"N1.com_A".to_vec(),
+                None,
+                None,
+                message_types::JSON,
+                crate::core::ContentTypes::Frame(b"{}".to_vec()),
+            );
+            c.send_routed_message(SendingContainer {
+                receiving_namespace: b"N2".to_vec(),
+                msg_cont: MessageContainer {
+                    identity: Vec::new(),
+                    message,
+                },
+            });
+        }
+        // com_A is told about the dropped messages.
+        assert!(c.statistics.dropped >= 1);
+        assert!(c.statistics.rejected[&-32099] >= 1);
+    }
+
+    #[test]
+    fn test_full_receiver_queue() {
+        let ctx = zmq::Context::new();
+        let endpoint = Endpoint::Inproc("test_full_receiver_queue".to_string());
+        let config = CoordinatorConfig {
+            send_high_water_mark: 1,
+            ..Default::default()
+        };
+        let mut coor =
+            Coordinator::with_config("N1".to_string(), Some(&endpoint), Some(ctx.clone()), config);
+        let stop = coor.stop_signal();
+        let routing = std::thread::spawn(move || {
+            coor.routing();
+            coor
+        });
+        let build = |name| {
+            let mut comm =
+                Communicator::build_with_endpoint(name, endpoint.clone(), Some(ctx.clone()), None)
+                    .unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let (sender, silent) = (build("sender"), build("silent"));
+        let content = || crate::core::ContentTypes::Frame(b"{}".to_vec());
+        // The queue holds the high water marks of both ends, i.e. also the 1000 of the receiver.
+        for _ in 0..1500 {
+            sender
+                .send_message_to("silent", content(), message_types::JSON)
+                .unwrap();
+        }
+        assert!(sender.poll(1000).unwrap());
+        let rejection = sender.read_message().unwrap();
+        let response: ErrorResponse =
+            serde_json::from_slice(rejection.content_frame().unwrap()).unwrap();
+        assert_eq!(response.error.code, -32099);
+        assert_eq!(response.error.data, Some(json!({"receiver": "N1.silent"})));
+
+        drop((sender, silent));
+        stop.stop();
+        let coor = routing.join().unwrap();
+        assert!(coor.statistics.dropped >= 1);
+    }
+
+    #[test]
+    fn test_send_statistics() {
+        let ctx = zmq::Context::new();
+        let test = "statistics";
+        let n1 = spawn_coordinator(&ctx, "N1", test, |_| {});
+        let build = |name| {
+            let mut comm = Communicator::build_with_endpoint(
+                name,
+                Endpoint::Inproc(format!("N1_{test}")),
+                Some(ctx.clone()),
+                None,
+            )
+            .unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let (mut comm_1, comm_2) = (build("comm_1"), build("comm_2"));
+        let content = || crate::core::ContentTypes::Frame(b"{}".to_vec());
+        comm_1
+            .send_message_to("comm_2", content(), message_types::JSON)
+            .unwrap();
+        assert!(comm_2.poll(1000).unwrap());
+        assert_eq!(
+            comm_2.read_message().unwrap().content_frame().unwrap(),
+            b"{}"
+        );
+        comm_1
+            .send_message_to("nobody", content(), message_types::JSON)
+            .unwrap();
+        assert!(comm_1.poll(1000).unwrap());
+        comm_1.read_message().unwrap();
+
+        let timeout = Duration::from_secs(1);
+        let params = serde_json::json!({"reset": true});
+        let statistics = comm_1
+            .ask("COORDINATOR", "send_statistics", Some(params), timeout)
+            .unwrap();
+        assert_eq!(statistics["routed"], 1);
+        assert_eq!(
+            statistics["rejected"],
+            serde_json::json!({Error::ReceiverUnknown.code().to_string(): 1})
+        );
+        assert!(statistics["bytes_in"].as_u64().unwrap() > 0);
+        assert!(statistics["bytes_out"].as_u64().unwrap() > 0);
+        assert_eq!(statistics["components"], 2);
+        assert_eq!(statistics["nodes"], 0);
+        assert_eq!(statistics["dropped"], 0);
+        assert!(statistics["uptime"].as_f64().unwrap() > 0.0);
+
+        let statistics = comm_1
+            .ask("COORDINATOR", "send_statistics", None, timeout)
+            .unwrap();
+        assert_eq!(statistics["routed"], 0);
+        assert_eq!(statistics["rejected"], serde_json::json!({}));
+        let params = serde_json::json!({"reset": 1});
+        let result = comm_1.ask("COORDINATOR", "send_statistics", Some(params), timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::InvalidParams))
+        ));
+        comm_1
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        let coordinator = n1.join().unwrap();
+        drop((comm_1, comm_2));
+        drop(coordinator);
+    }
+
+    #[test]
+    fn test_component_info() {
+        let ctx = zmq::Context::new();
+        let test = "component_info";
+        let n1 = spawn_coordinator(&ctx, "N1", test, |_| {});
+        let build = |name| {
+            let mut comm = Communicator::build_with_endpoint(
+                name,
+                Endpoint::Inproc(format!("N1_{test}")),
+                Some(ctx.clone()),
+                None,
+            )
+            .unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let (mut comm_1, comm_2) = (build("comm_1"), build("comm_2"));
+        let start = unix_time();
+        for _ in 0..2 {
+            comm_1
+                .send_message_to(
+                    "comm_2",
+                    crate::core::ContentTypes::Frame(b"{}".to_vec()),
+                    message_types::JSON,
+                )
+                .unwrap();
+            assert!(comm_2.poll(1000).unwrap());
+        }
+        let timeout = Duration::from_secs(1);
+        let params = serde_json::json!({"name": "comm_1"});
+        let info = comm_1
+            .ask("COORDINATOR", "component_info", Some(params), timeout)
+            .unwrap();
+        // The request itself counts as well.
+        assert_eq!(info["messages"], 3);
+        assert!(info["bytes"].as_u64().unwrap() > 0);
+        let last_seen = info["last_seen"].as_f64().unwrap();
+        assert!(last_seen >= start - 0.01 && last_seen <= unix_time());
+
+        let all = comm_1
+            .ask("COORDINATOR", "component_info", None, timeout)
+            .unwrap();
+        assert_eq!(all["comm_1"]["messages"], 4);
+        assert_eq!(all["comm_2"]["messages"], 0);
+        assert_eq!(all["comm_2"]["bytes"], 0);
+        let params = serde_json::json!({"name": "nobody"});
+        let result = comm_1.ask("COORDINATOR", "component_info", Some(params), timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::InvalidParams))
+        ));
+        comm_1
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        let coordinator = n1.join().unwrap();
+        drop((comm_1, comm_2));
+        drop(coordinator);
+    }
+
+    #[test]
+    fn test_send_global_components() {
+        let ctx = zmq::Context::new();
+        let test = "global_components";
+        let endpoint = |namespace| Endpoint::Inproc(format!("{namespace}_{test}"));
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| {
+            coor.add_node("N2", endpoint("N2")).unwrap()
+        });
+        let n2 = spawn_coordinator(&ctx, "N2", test, |_| {});
+        let build = |name, namespace| {
+            let mut comm = Communicator::build_with_endpoint(
+                name,
+                endpoint(namespace),
+                Some(ctx.clone()),
+                None,
+            )
+            .unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let mut comm_a = build("comm_a", "N1");
+        let comm_c = build("comm_c", "N1");
+        let mut comm_b = build("comm_b", "N2");
+        let timeout = Duration::from_secs(1);
+        let local = comm_a
+            .ask("COORDINATOR", "send_local_components", None, timeout)
+            .unwrap();
+        assert_eq!(local, serde_json::json!(["comm_a", "comm_c"]));
+        let global = comm_a
+            .ask("COORDINATOR", "send_global_components", None, timeout)
+            .unwrap();
+        assert_eq!(
+            global,
+            serde_json::json!({"N1": ["comm_a", "comm_c"], "N2": ["comm_b"]})
+        );
+        comm_a
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        comm_b
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        let coordinators = (n1.join().unwrap(), n2.join().unwrap());
+        // Sign out while the Coordinators' sockets are still open.
+        drop((comm_a, comm_b, comm_c));
+        drop(coordinators);
+    }
+
+    #[test]
+    fn test_add_nodes() {
+        let ctx = zmq::Context::new();
+        let test = "add_nodes";
+        let address = |namespace| format!("inproc://{namespace}_{test}");
+        let n1 = spawn_coordinator(&ctx, "N1", test, |_| {});
+        let n2 = spawn_coordinator(&ctx, "N2", test, |_| {});
+        let n3 = spawn_coordinator(&ctx, "N3", test, |_| {});
+        let mut comm = Communicator::build_with_endpoint(
+            "comm",
+            address("N1").parse().unwrap(),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        comm.sign_in().unwrap();
+        let timeout = Duration::from_secs(1);
+        let params = serde_json::json!({"nodes": {"N2": address("N2"), "N4": "no address"}});
+        let result = comm
+            .ask("COORDINATOR", "add_nodes", Some(params), timeout)
+            .unwrap();
+        assert_eq!(result["N2"], Value::Null);
+        assert_eq!(result["N4"]["code"], Error::InvalidParams.code());
+        std::thread::sleep(Duration::from_millis(100));
+        // N2 learns about N3 from N1.
+        let params = serde_json::json!({"nodes": {"N3": address("N3")}});
+        comm.ask("COORDINATOR", "add_nodes", Some(params), timeout)
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        // Requests to N2 and N3 pass only if they know N1's identity and can answer via N1.
+        for receiver in ["N2.COORDINATOR", "N3.COORDINATOR", "COORDINATOR"] {
+            let nodes = comm.ask(receiver, "send_nodes", None, timeout).unwrap();
+            for namespace in ["N1", "N2", "N3"] {
+                assert!(nodes.get(namespace).is_some(), "{receiver}: {nodes}");
+            }
+        }
+        for receiver in ["N2.COORDINATOR", "N3.COORDINATOR", "COORDINATOR"] {
+            comm.ask(receiver, "shut_down", None, timeout).unwrap();
+        }
+        let coordinators = [n1, n2, n3].map(|handle| handle.join().unwrap());
+        drop(comm);
+        drop(coordinators);
+    }
+
+    #[test]
+    fn test_send_nodes() {
+        let ctx = zmq::Context::new();
+        let test = "send_nodes";
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| {
+            coor.set_address(Endpoint::tcp("lab.example.com", 12300).unwrap())
+        });
+        let mut comm = Communicator::build_with_endpoint(
+            "comm",
+            Endpoint::Inproc(format!("N1_{test}")),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        comm.sign_in().unwrap();
+        let timeout = Duration::from_secs(1);
+        let params = serde_json::json!({"nodes": {"N2": "localhost:12398"}});
+        comm.ask("COORDINATOR", "add_nodes", Some(params), timeout)
+            .unwrap();
+        let nodes = comm
+            .ask("COORDINATOR", "send_nodes", None, timeout)
+            .unwrap();
+        assert_eq!(
+            nodes,
+            serde_json::json!({"N1": "lab.example.com:12300", "N2": "localhost:12398"})
+        );
+        comm.ask("COORDINATOR", "shut_down", None, timeout).unwrap();
+        let coordinator = n1.join().unwrap();
+        drop(comm);
+        drop(coordinator);
+    }
+
+    #[test]
+    fn test_advertised_address_defaults_to_bound_endpoint() {
+        let (c, port) = make_tcp_coordinator("N1");
+        let directory = c.node_directory();
+        assert_eq!(directory["N1"], format!("localhost:{port}"));
+    }
+
+    #[test]
+    fn test_set_nodes_removes_others() {
+        let ctx = zmq::Context::new();
+        let mut coor = Coordinator::with_endpoint(
+            "N1".to_string(),
+            Some(&Endpoint::Inproc("N1_set_nodes".to_string())),
+            Some(ctx),
+            None,
+        );
+        coor.add_node("N2", Endpoint::Inproc("N2_set_nodes".to_string()))
+            .unwrap();
+        let params = serde_json::json!({"nodes": {"N3": "inproc://N3_set_nodes"}});
+        let result = coor.add_nodes(Some(params), true).unwrap();
+        assert_eq!(result, serde_json::json!({"N3": null}));
+        assert!(!coor.nodes.contains_key(b"N2".as_slice()));
+        assert!(coor.nodes.contains_key(b"N3".as_slice()));
+        assert_eq!(coor.add_nodes(None, false), Err(Error::InvalidParams));
+    }
+
+    #[test]
+    fn test_stop_notifies_components() {
+        let ctx = zmq::Context::new();
+        let test = "stop";
+        let mut stop = None;
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| stop = Some(coor.stop_signal()));
+        let n2 = spawn_coordinator(&ctx, "N2", test, |coor| {
+            coor.add_node("N1", Endpoint::Inproc(format!("N1_{test}")))
+                .unwrap()
+        });
+        let mut comm = Communicator::build_with_endpoint(
+            "comm",
+            Endpoint::Inproc(format!("N1_{test}")),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        comm.sign_in().unwrap();
+        let timeout = Duration::from_secs(1);
+        let nodes = comm
+            .ask("COORDINATOR", "send_nodes", None, timeout)
+            .unwrap();
+        assert!(nodes.get("N2").is_some());
+
+        stop.unwrap().stop();
+        let n1 = n1.join().unwrap();
+        assert!(comm.poll(1000).unwrap());
+        let notification = comm.read_message().unwrap();
+        assert_eq!(notification.sender_frame(), b"N1.COORDINATOR");
+        let content: Value = serde_json::from_slice(notification.content_frame().unwrap()).unwrap();
+        assert_eq!(content["method"], "coordinator_shutdown");
+        assert!(n1.nodes.is_empty());
+
+        // N2 received the sign-out.
+        let mut comm_2 = Communicator::build_with_endpoint(
+            "comm_2",
+            Endpoint::Inproc(format!("N2_{test}")),
+            Some(ctx.clone()),
+            None,
+        )
+        .unwrap();
+        comm_2.sign_in().unwrap();
+        let nodes = comm_2
+            .ask("COORDINATOR", "send_nodes", None, timeout)
+            .unwrap();
+        assert!(nodes.get("N1").is_none());
+        comm_2
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        let n2 = n2.join().unwrap();
+        drop((comm, comm_2));
+        drop((n1, n2));
+    }
+
+    #[test]
+    fn test_remote_forwarding_adds_namespace_to_sender() {
+        let ctx = zmq::Context::new();
+        let test = "remote_stamp";
+        let endpoint = |namespace| Endpoint::Inproc(format!("{namespace}_{test}"));
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| {
+            coor.add_node("N2", endpoint("N2")).unwrap()
+        });
+        let n2 = spawn_coordinator(&ctx, "N2", test, |_| {});
+        let mut comm_b =
+            Communicator::build_with_endpoint("comm_b", endpoint("N2"), Some(ctx.clone()), None)
+                .unwrap();
+        comm_b.sign_in().unwrap();
+        // A Component sending with its bare name.
+        let raw = ctx.socket(zmq::DEALER).unwrap();
+        raw.set_linger(0).unwrap();
+        raw.connect(&endpoint("N1").to_string()).unwrap();
+        let (sign_in, _cid) =
+            Message::build_request(b"COORDINATOR".to_vec(), b"raw".to_vec(), 1, "sign_in", None);
+        raw.send_multipart(sign_in.to_frames(), 0).unwrap();
+        raw.recv_multipart(0).unwrap();
+        let message = Message::build(
+            b"N2.comm_b".to_vec(),
+            b"raw".to_vec(),
+            None,
+            None,
+            message_types::JSON,
+            crate::core::ContentTypes::Frame(b"{}".to_vec()),
+        );
+        raw.send_multipart(message.to_frames(), 0).unwrap();
+        assert!(comm_b.poll(1000).unwrap());
+        let received = comm_b.read_message().unwrap();
+        assert_eq!(received.sender_frame(), b"N1.raw");
+        assert_eq!(received.receiver_frame(), b"N2.comm_b");
+
+        let timeout = Duration::from_secs(1);
+        for receiver in ["N1.COORDINATOR", "COORDINATOR"] {
+            comm_b.ask(receiver, "shut_down", None, timeout).unwrap();
+        }
+        let coordinators = (n1.join().unwrap(), n2.join().unwrap());
+        drop((comm_b, raw));
+        drop(coordinators);
+    }
+
+    #[test]
+    fn test_routing_between_coordinators() {
+        let ctx = zmq::Context::new();
+        let test = "remote_routing";
+        let endpoint = |namespace| Endpoint::Inproc(format!("{namespace}_{test}"));
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| {
+            coor.add_node("N2", endpoint("N2")).unwrap()
+        });
+        let n2 = spawn_coordinator(&ctx, "N2", test, |coor| {
+            coor.add_node("N1", endpoint("N1")).unwrap()
+        });
+        let build = |name, namespace| {
+            let mut comm = Communicator::build_with_endpoint(
+                name,
+                endpoint(namespace),
+                Some(ctx.clone()),
+                None,
+            )
+            .unwrap();
+            comm.sign_in().unwrap();
+            comm
+        };
+        let mut comm_a = build("comm_a", "N1");
+        let mut comm_b = build("comm_b", "N2");
+        let timeout = Duration::from_secs(1);
+        let responder = std::thread::spawn(move || {
+            let request = comm_b.next_request(timeout).unwrap();
+            assert_eq!(request.sender(), "N1.comm_a");
+            let params = request.params().cloned();
+            request.respond(params).unwrap();
+            comm_b
+        });
+        let result = comm_a.ask("N2.comm_b", "echo", Some(serde_json::json!(7)), timeout);
+        let mut comm_b = responder.join().unwrap();
+        assert_eq!(result.unwrap(), 7);
+        let result = comm_a.ask("N3.comm_c", "echo", None, timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::NodeUnknown))
+        ));
+        comm_a
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        comm_b
+            .ask("COORDINATOR", "shut_down", None, timeout)
+            .unwrap();
+        let coordinators = (n1.join().unwrap(), n2.join().unwrap());
+        // Sign out while the Coordinators' sockets are still open.
+        drop((comm_a, comm_b));
+        drop(coordinators);
+    }
+
+    #[test]
+    fn test_routing_loop_is_prevented() {
+        let ctx = zmq::Context::new();
+        let test = "routing_loop";
+        let endpoint = |namespace| Endpoint::Inproc(format!("{namespace}_{test}"));
+        // Both believe N3 to be behind the other one.
+        let n1 = spawn_coordinator(&ctx, "N1", test, |coor| {
+            coor.add_node("N3", endpoint("N2")).unwrap()
+        });
+        let mut n2_stop = None;
+        let n2 = spawn_coordinator(&ctx, "N2", test, |coor| {
+            n2_stop = Some(coor.stop_signal());
+            coor.add_node("N3", endpoint("N1")).unwrap()
+        });
+        let mut comm =
+            Communicator::build_with_endpoint("comm", endpoint("N1"), Some(ctx.clone()), None)
+                .unwrap();
+        comm.sign_in().unwrap();
+        let timeout = Duration::from_secs(1);
+        let result = comm.ask("N3.comm_c", "echo", None, timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::NodeUnknown))
+        ));
+        comm.ask("N2.COORDINATOR", "pong", None, timeout).unwrap();
+        // N2 signs out via both of its connections, which could overtake an answer to us.
+        n2_stop.unwrap().stop();
+        let n2 = n2.join().unwrap();
+        comm.ask("COORDINATOR", "shut_down", None, timeout).unwrap();
+        let n1 = n1.join().unwrap();
+        // Besides the request, the Components reported to "N3" end at the other Coordinator.
+        assert!(n2.statistics.rejected[&Error::NodeUnknown.code()] >= 1);
+        // The request and the ping of N2, and their answers back.
+        assert_eq!(n1.statistics.routed, 4);
+        drop(comm);
+        drop((n1, n2));
+    }
+
+    #[test]
+    fn test_listener() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Two sign-ins, four requests to the listener with responses, one request to the
+            // Coordinator, and the sign-out of the listener.
+            for _ in 0..12 {
+                coor.loop_element();
+            }
+        });
+        let mut comm = Communicator::build("thermometer", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        let mut listener = Listener::new(comm);
+        listener.register("get_temperature", |_params| Ok(Value::from(21.5)));
+        listener.register_with_description("set_unit", "Set the temperature unit.", |_params| {
+            Ok(Value::Null)
+        });
+        let (handle, _stop) = listener.spawn();
+
+        let mut comm = Communicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().unwrap();
+        let timeout = Duration::from_secs(1);
+        let temperature = comm.ask("N1.thermometer", "get_temperature", None, timeout);
+        assert_eq!(temperature.unwrap(), 21.5);
+        let result = comm.ask("N1.thermometer", "get_pressure", None, timeout);
+        assert!(matches!(
+            result,
+            Err(CommunicatorError::Rpc(Error::MethodNotFound))
+        ));
+        let document = comm
+            .ask("N1.thermometer", "rpc.discover", None, timeout)
+            .unwrap();
+        let methods = document["methods"].as_array().unwrap();
+        assert!(methods.contains(&serde_json::json!({"name": "get_temperature"})));
+        assert!(methods.contains(&serde_json::json!({
+            "name": "set_unit",
+            "description": "Set the temperature unit."
+        })));
+        let document = comm
+            .ask("COORDINATOR", "rpc.discover", None, timeout)
+            .unwrap();
+        assert_eq!(document["info"]["title"], "N1.COORDINATOR");
+        assert_eq!(
+            document["methods"].as_array().unwrap().len(),
+            BUILT_IN_METHODS.len()
+        );
+        comm.ask("N1.thermometer", "shut_down", None, timeout)
+            .unwrap();
+        handle.join().unwrap().unwrap();
+        coordinator.join().unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_communicator() {
+        use crate::control_protocol::async_communicator::AsyncCommunicator;
+
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let handle = std::thread::spawn(move || {
+            coor.loop_element();
+            coor.loop_element();
+        });
+        let comm = AsyncCommunicator::build("comm", None, Some(port), None).unwrap();
+        comm.sign_in().await.unwrap();
+        assert_eq!(comm.full_name().await, "N1.comm");
+        let result = comm
+            .ask(
+                "COORDINATOR".to_string(),
+                "pong".to_string(),
+                None,
+                Duration::from_secs(1),
+            )
+            .await;
+        handle.join().unwrap();
+        assert_eq!(result.unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_communicator_ask_with_binary() {
+        let (mut coor, port) = make_tcp_coordinator("N1");
+        let coordinator = std::thread::spawn(move || {
+            // Two sign-ins, the request and the response.
+            for _ in 0..4 {
+                coor.loop_element();
+            }
+        });
+        let (signed_in, wait_for_sign_in) = std::sync::mpsc::channel();
+        let echo = std::thread::spawn(move || {
+            let timeout = Some(Duration::from_secs(5));
+            let mut comm = Communicator::build("echo", None, Some(port), timeout).unwrap();
+            comm.sign_in().unwrap();
+            signed_in.send(()).unwrap();
+            let request = comm.read_message().unwrap();
+            let content: Request = serde_json::from_slice(request.json_frame().unwrap()).unwrap();
+            let mut frames = vec![to_vec(&Response::build(content.id, content.params))];
+            frames.extend(request.binary_frames().iter().cloned());
+            let response = Message::build(
+                request.sender_frame().to_vec(),
+                b"N1.echo".to_vec(),
+                Some(request.header().conversation_id),
+                None,
+                crate::control_protocol::message_types::JSON,
+                crate::core::ContentTypes::Frames(frames),
+            );
+            comm.send_message(response).unwrap();
+        });
+        let timeout = Some(Duration::from_secs(5));
+        let mut comm = Communicator::build("comm", None, Some(port), timeout).unwrap();
+        comm.sign_in().unwrap();
+        wait_for_sign_in.recv().unwrap();
+        let blob: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        let (result, blobs) = comm
+            .ask_with_binary(
+                "echo",
+                "store",
+                Some(Value::from(7)),
+                vec![blob.clone(), vec![1]],
+            )
+            .unwrap();
+        echo.join().unwrap();
+        coordinator.join().unwrap();
+        assert_eq!(result, 7);
+        assert_eq!(blobs.len(), 2);
+        assert!(blobs[0] == blob);
+        assert_eq!(blobs[1], [1]);
+    }
+}
+
+pub mod admin;
@@ -0,0 +1,172 @@
+//! Control a running [`Coordinator`](super::Coordinator) from within the same process.
+//!
+//! The commands do not pass the network path, such that the owner of the Coordinator does not
+//! have to sign in as a Component.
+
+use std::{
+    cell::Cell,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::json::to_vec;
+
+/// How long the handle waits by default for the Coordinator to answer a command
+const ADMIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A command for the admin socket of a Coordinator
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminCommand {
+    /// Stop routing, like [`Coordinator::stop`](super::Coordinator::stop)
+    Stop,
+    /// Get the traffic counters, like `send_statistics`
+    Stats,
+    /// List the names of the local Components
+    ListComponents,
+    /// Remove the local Component with this name
+    Kick(String),
+    /// Trace the messages of these Components, like
+    /// [`Coordinator::set_trace`](super::Coordinator::set_trace)
+    Trace(Vec<String>),
+}
+
+/// A command as sent over the admin socket, with an id to match the answer
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct AdminRequest {
+    pub id: u64,
+    pub command: AdminCommand,
+}
+
+/// The answer to the [`AdminRequest`] with the same id
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct AdminAnswer {
+    pub id: u64,
+    pub result: Result<Value, String>,
+}
+
+/// Errors which may occur while controlling a Coordinator
+#[derive(Debug)]
+pub enum AdminError {
+    /// Sending or receiving failed
+    Transport(zmq::Error),
+    /// The Coordinator did not answer in time, e.g. as it is not routing
+    Timeout,
+    /// The answer could not be interpreted
+    Json(serde_json::Error),
+    /// The Coordinator could not execute the command
+    Failed(String),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "socket error: {err}"),
+            Self::Timeout => write!(f, "no answer in time"),
+            Self::Json(err) => write!(f, "invalid answer: {err}"),
+            Self::Failed(message) => write!(f, "command failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+/// Control a Coordinator created with
+/// [`Coordinator::with_admin`](super::Coordinator::with_admin), e.g. from another thread.
+///
+/// The commands are handled by the routing loop, see
+/// [`Coordinator::routing`](super::Coordinator::routing).
+/// ```no_run
+/// # use ruleco::coordinator::{Coordinator, CoordinatorConfig};
+/// let (mut coordinator, admin) =
+///     Coordinator::with_admin("N1".to_string(), None, None, CoordinatorConfig::default());
+/// let routing = std::thread::spawn(move || coordinator.routing());
+/// println!("{:?}", admin.list_components().unwrap());
+/// admin.stop().unwrap();
+/// routing.join().unwrap();
+/// ```
+pub struct CoordinatorAdminHandle {
+    socket: zmq::Socket,
+    timeout: Duration,
+    /// Id of the last command sent
+    last_id: Cell<u64>,
+}
+
+impl CoordinatorAdminHandle {
+    /// Connect to the admin socket bound at `endpoint`.
+    pub(super) fn connect(context: &zmq::Context, endpoint: &str) -> Result<Self, zmq::Error> {
+        let socket = context.socket(zmq::PAIR)?;
+        socket.set_sndtimeo(ADMIN_TIMEOUT.as_millis() as i32)?;
+        socket.connect(endpoint)?;
+        Ok(Self {
+            socket,
+            timeout: ADMIN_TIMEOUT,
+            last_id: Cell::new(0),
+        })
+    }
+
+    /// Wait at most `timeout` for the answer to a command, 5 s by default.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Send `command` and return the answer of the Coordinator.
+    ///
+    /// Late answers to previous commands, which timed out, are discarded.
+    pub fn send(&self, command: &AdminCommand) -> Result<Value, AdminError> {
+        let map_err = |err| match err {
+            zmq::Error::EAGAIN => AdminError::Timeout,
+            err => AdminError::Transport(err),
+        };
+        let id = self.last_id.get() + 1;
+        self.last_id.set(id);
+        let request = AdminRequest {
+            id,
+            command: command.clone(),
+        };
+        self.socket.send(to_vec(&request), 0).map_err(map_err)?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let timeout_ms = remaining.as_micros().div_ceil(1000) as i64;
+            if self.socket.poll(zmq::POLLIN, timeout_ms).map_err(map_err)? == 0 {
+                return Err(AdminError::Timeout);
+            }
+            let bytes = self.socket.recv_bytes(0).map_err(map_err)?;
+            let answer: AdminAnswer = serde_json::from_slice(&bytes).map_err(AdminError::Json)?;
+            if answer.id == id {
+                return answer.result.map_err(AdminError::Failed);
+            }
+            log::debug!("Discarded the late answer to admin command {}.", answer.id);
+        }
+    }
+
+    /// Stop the routing loop.
+    pub fn stop(&self) -> Result<(), AdminError> {
+        self.send(&AdminCommand::Stop).map(|_| ())
+    }
+
+    /// Get the traffic counters, as reported by `send_statistics`.
+    pub fn stats(&self) -> Result<Value, AdminError> {
+        self.send(&AdminCommand::Stats)
+    }
+
+    /// The names of the local Components.
+    pub fn list_components(&self) -> Result<Vec<String>, AdminError> {
+        serde_json::from_value(self.send(&AdminCommand::ListComponents)?).map_err(AdminError::Json)
+    }
+
+    /// Remove the local Component `name` and return whether it was signed in.
+    pub fn kick(&self, name: &str) -> Result<bool, AdminError> {
+        let answer = self.send(&AdminCommand::Kick(name.to_string()))?;
+        serde_json::from_value(answer).map_err(AdminError::Json)
+    }
+
+    /// Log the messages of the Components `names` in full, or nothing for no names.
+    pub fn trace<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Result<(), AdminError> {
+        let names = names.into_iter().map(str::to_string).collect();
+        self.send(&AdminCommand::Trace(names)).map(|_| ())
+    }
+}
@@ -1,4 +1,7 @@
-use super::core::{create_conversation_id, ContentTypes};
+use super::{
+    control_protocol::message_types,
+    core::{create_conversation_id, ContentTypes, CurveClientKeys},
+};
 /// A message in the data protocol
 #[derive(Clone)]
 pub struct DataMessage {
@@ -16,11 +19,11 @@ impl DataMessage {
         let content = match content {
             ContentTypes::Frame(c) => vec![c],
             ContentTypes::Frames(c) => c,
-            ContentTypes::Null => vec![vec![]],
+            ContentTypes::Null => vec![],
         };
         Self {
             topic: topic.as_bytes().to_vec(),
-            header: header,
+            header,
             payload: content,
         }
     }
@@ -33,7 +36,17 @@ impl DataMessage {
         self.header[16]
     }
 
-    fn to_frames(self) -> Vec<Vec<u8>> {
+    /// Number of bytes the message occupies on the wire, i.e. the sum of all frame lengths.
+    pub fn len_bytes(&self) -> usize {
+        self.topic.len() + self.header.len() + self.payload.iter().map(|f| f.len()).sum::<usize>()
+    }
+
+    /// Number of frames of the message.
+    pub fn frame_count(&self) -> usize {
+        2 + self.payload.len()
+    }
+
+    pub(crate) fn into_frames(self) -> Vec<Vec<u8>> {
         let header = self.header.to_vec();
         let mut frames: Vec<Vec<u8>> = vec![self.topic, header];
         for frame in self.payload {
@@ -59,20 +72,34 @@ pub struct DataPublisher {
 
 impl DataPublisher {
     pub fn new(name: String, addr: &str, port: u16) -> Self {
+        Self::connect(name, addr, port, None)
+    }
+
+    /// Like [`DataPublisher::new`], but encrypt the connection with CurveZMQ.
+    pub fn with_curve_keys(name: String, addr: &str, port: u16, keys: &CurveClientKeys) -> Self {
+        Self::connect(name, addr, port, Some(keys))
+    }
+
+    fn connect(name: String, addr: &str, port: u16, keys: Option<&CurveClientKeys>) -> Self {
         let ctx = zmq::Context::new();
         let socket = ctx.socket(zmq::PUB).unwrap();
+        if let Some(keys) = keys {
+            keys.apply(&socket).unwrap();
+        }
         socket.connect(&format!("tcp://{addr}:{port}")).unwrap();
-        let publisher = Self {
-            name,
-            socket: socket,
-        };
-        publisher
+        Self { name, socket }
     }
 
     /// Send a data message with some content
     pub fn send_message(&self, content: Vec<u8>) {
-        let message = DataMessage::new(&self.name, 1, ContentTypes::Frame(content));
-        self.socket.send_multipart(message.to_frames(), 0).unwrap()
+        let message = DataMessage::new(
+            &self.name,
+            message_types::JSON,
+            ContentTypes::Frame(content),
+        );
+        self.socket
+            .send_multipart(message.into_frames(), 0)
+            .unwrap()
     }
 }
 
@@ -86,6 +113,21 @@ mod tests {
         assert_eq!(dm.message_type(), 5)
     }
 
+    #[test]
+    fn check_len_bytes_and_frame_count() {
+        let dm = DataMessage::new("abc", 5, ContentTypes::Frames(vec![vec![1, 2], vec![3]]));
+        assert_eq!(dm.len_bytes(), 3 + 17 + 3);
+        assert_eq!(dm.frame_count(), 4);
+        assert_eq!(dm.frame_count(), dm.clone().into_frames().len());
+    }
+
+    #[test]
+    fn check_null_has_no_payload() {
+        let dm = DataMessage::new("abc", 5, ContentTypes::Null);
+        assert!(dm.payload.is_empty());
+        assert_eq!(dm.into_frames().len(), 2)
+    }
+
     #[test]
     fn check_conversation_id() {
         let dm = DataMessage::new("abc", 5, ContentTypes::Frame(vec![1, 2]));
@@ -1,13 +1,17 @@
 //! Do some json interpreting
 //! Replace later with proper crate, e.g. jsonrpsee
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+
+use crate::control_protocol::Error;
 
 #[derive(Serialize, Deserialize)]
 pub struct Request {
     jsonrpc: String,
     pub id: u16,
     pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
 }
 impl Request {
     pub fn build<T: ToString>(id: u16, method: T) -> Self {
@@ -15,8 +19,15 @@ impl Request {
             jsonrpc: "2.0".to_string(),
             id,
             method: method.to_string(),
+            params: None,
         }
     }
+
+    /// Add `params` to the request.
+    pub fn with_params(mut self, params: Option<Value>) -> Self {
+        self.params = params;
+        self
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -37,38 +48,150 @@ impl Response {
 
 #[derive(Serialize, Deserialize)]
 pub struct ErrorContent {
-    code: i16,
-    message: String,
+    pub code: i16,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
+/// JSON-RPC error response, whose `id` is null if the request's id could not be determined.
 #[derive(Serialize, Deserialize)]
 pub struct ErrorResponse {
     jsonrpc: String,
-    pub id: u16,
+    pub id: Option<u16>,
     pub error: ErrorContent,
 }
 impl ErrorResponse {
-    pub fn build(id: u16, code: i16, message: &str) -> Self {
+    pub fn build(
+        id: impl Into<Option<u16>>,
+        code: i16,
+        message: &str,
+        data: Option<Value>,
+    ) -> Self {
         let error = ErrorContent {
             code,
             message: message.to_string(),
+            data,
         };
         Self {
             jsonrpc: "2.0".to_string(),
-            id,
+            id: id.into(),
             error,
         }
     }
+
+    /// Create an error response from a LECO error.
+    pub fn from_error(id: impl Into<Option<u16>>, error: &Error) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: id.into(),
+            error: error.into(),
+        }
+    }
+}
+
+/// Content of a JSON-RPC response, which contains either a result or an error.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseContent {
+    Result(Response),
+    Error(ErrorResponse),
+}
+
+impl From<&Error> for ErrorContent {
+    fn from(error: &Error) -> Self {
+        Self {
+            code: error.code(),
+            message: error.message().to_string(),
+            data: error.data().cloned(),
+        }
+    }
 }
 
 pub fn to_vec(obj: &impl Serialize) -> Vec<u8> {
     serde_json::to_vec(obj).unwrap()
 }
 
+/// Build an OpenRPC-like document for `rpc.discover`, listing the `methods` of `title`.
+///
+/// Each method is given by its name and an optional description.
+pub fn discover_document<'a>(
+    title: &str,
+    methods: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+) -> Value {
+    let mut methods: Vec<_> = methods.into_iter().collect();
+    methods.sort_unstable();
+    let methods: Vec<Value> = methods
+        .into_iter()
+        .map(|(name, description)| match description {
+            Some(description) => json!({"name": name, "description": description}),
+            None => json!({"name": name}),
+        })
+        .collect();
+    json!({
+        "openrpc": "1.2.6",
+        "info": {"title": title, "version": env!("CARGO_PKG_VERSION")},
+        "methods": methods,
+    })
+}
+
 pub fn is_sign_in(slice: &[u8]) -> bool {
+    is_request_for(slice, "sign_in")
+}
+
+/// A single request or a batch of requests
+pub enum Requests {
+    Single(Request),
+    /// Invalid entries of the batch are given by their id, as far as it can be extracted.
+    Batch(Vec<Result<Request, Option<u16>>>),
+}
+
+/// Parse a single request or a batch (array) of requests.
+///
+/// Invalid JSON is a parse error, while an empty batch or something else than a request is an
+/// invalid request, as defined by JSON-RPC 2.0.
+pub fn parse_requests(slice: &[u8]) -> Result<Requests, Error> {
+    match serde_json::from_slice::<Value>(slice) {
+        Err(_) => Err(Error::ParseError),
+        Ok(Value::Array(batch)) if batch.is_empty() => Err(Error::InvalidRequest),
+        Ok(Value::Array(batch)) => Ok(Requests::Batch(
+            batch
+                .into_iter()
+                .map(|entry| {
+                    let id = entry
+                        .get("id")
+                        .and_then(Value::as_u64)
+                        .and_then(|id| u16::try_from(id).ok());
+                    serde_json::from_value(entry).map_err(|_| id)
+                })
+                .collect(),
+        )),
+        Ok(value) => serde_json::from_value(value)
+            .map(Requests::Single)
+            .map_err(|_| Error::InvalidRequest),
+    }
+}
+
+/// Extract just the id of a request in `slice`, even if the request is otherwise invalid.
+pub fn extract_id(slice: &[u8]) -> Option<u16> {
+    #[derive(Deserialize)]
+    struct Id {
+        #[serde(default)]
+        id: Option<u16>,
+    }
+    serde_json::from_slice::<Id>(slice).ok()?.id
+}
+
+/// Whether `slice` contains a response, either with a result or an error.
+pub fn is_response(slice: &[u8]) -> bool {
+    serde_json::from_slice::<ResponseContent>(slice).is_ok()
+}
+
+/// Whether `slice` contains a request calling `method`.
+pub fn is_request_for(slice: &[u8], method: &str) -> bool {
     match serde_json::from_slice::<Request>(slice) {
         Err(_) => false,
-        Ok(request) => request.method == String::from("sign_in"),
+        Ok(request) => request.method == method,
     }
 }
 
@@ -76,6 +199,33 @@ pub fn is_sign_in(slice: &[u8]) -> bool {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_discover_document() {
+        let document = discover_document("motor", [("move", Some("Move it")), ("halt", None)]);
+        assert_eq!(document["info"]["title"], "motor");
+        assert_eq!(
+            document["methods"],
+            json!([{"name": "halt"}, {"name": "move", "description": "Move it"}])
+        );
+    }
+
+    #[test]
+    fn test_request_without_params() {
+        let request = Request::build(3, "pong");
+        let string = serde_json::to_string(&request).unwrap();
+        assert_eq!(string, "{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"pong\"}")
+    }
+
+    #[test]
+    fn test_request_with_params() {
+        let request = Request::build(3, "set").with_params(Some(serde_json::json!({"a": 5})));
+        let string = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            string,
+            "{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"set\",\"params\":{\"a\":5}}"
+        )
+    }
+
     #[test]
     fn test_response_null() {
         let response = Response::build(1, None::<u8>);
@@ -83,6 +233,113 @@ mod test {
         assert_eq!(string, "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":null}")
     }
 
+    #[test]
+    fn test_is_response() {
+        assert!(is_response(&to_vec(&Response::build(1, Value::Null))));
+        assert!(is_response(&to_vec(&ErrorResponse::from_error(
+            None,
+            &Error::ParseError
+        ))));
+        assert!(!is_response(&to_vec(&Request::build(1, "pong"))));
+        assert!(!is_response(b"no json"));
+    }
+
+    #[test]
+    fn test_error_response_without_data() {
+        let response = ErrorResponse::build(1, -32000, "Server error.", None);
+        let string = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            string,
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{\"code\":-32000,\"message\":\"Server error.\"}}"
+        )
+    }
+
+    #[test]
+    fn test_error_response_with_data() {
+        let response = ErrorResponse::build(1, -32010, "xyz", Some(serde_json::json!([1, 2])));
+        let string = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            string,
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{\"code\":-32010,\"message\":\"xyz\",\"data\":[1,2]}}"
+        )
+    }
+
+    #[test]
+    fn test_error_response_from_error() {
+        let response = ErrorResponse::from_error(2, &Error::ReceiverUnknown);
+        let string = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            string,
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"error\":{\"code\":-32093,\"message\":\"Receiver is not in addresses list.\"}}"
+        )
+    }
+
+    #[test]
+    fn test_error_response_null_id() {
+        let response = ErrorResponse::from_error(None, &Error::ParseError);
+        let string = serde_json::to_string(&response).unwrap();
+        assert!(string.starts_with("{\"jsonrpc\":\"2.0\",\"id\":null,"));
+        let parsed: ErrorResponse = serde_json::from_str(&string).unwrap();
+        assert_eq!(parsed.id, None);
+    }
+
+    #[test]
+    fn test_parse_requests() {
+        let single = br#"{"jsonrpc": "2.0", "id": 3, "method": "pong"}"#;
+        assert!(matches!(parse_requests(single), Ok(Requests::Single(r)) if r.id == 3));
+        let batch = br#"[{"jsonrpc": "2.0", "id": 3, "method": "pong"}, {"id": 4}, 5]"#;
+        let Ok(Requests::Batch(entries)) = parse_requests(batch) else {
+            panic!("Not parsed as batch.");
+        };
+        assert!(matches!(&entries[0], Ok(r) if r.method == "pong"));
+        assert!(matches!(entries[1], Err(Some(4))));
+        assert!(matches!(entries[2], Err(None)));
+        assert!(matches!(parse_requests(b"[]"), Err(Error::InvalidRequest)));
+        assert!(matches!(parse_requests(b"{}"), Err(Error::InvalidRequest)));
+        assert!(matches!(parse_requests(b"[{"), Err(Error::ParseError)));
+    }
+
+    #[test]
+    fn test_extract_id() {
+        assert_eq!(extract_id(br#"{"id": 42, "method": 5}"#), Some(42));
+        assert_eq!(extract_id(br#"{"method": "pong"}"#), None);
+        assert_eq!(extract_id(br#"{"id": "abc"}"#), None);
+        assert_eq!(extract_id(b"{\"id\": 4"), None);
+    }
+
+    #[test]
+    fn test_error_response_from_custom_error() {
+        let error = Error::custom(-32010, "abc", Some(serde_json::json!(true))).unwrap();
+        let response = ErrorResponse::from_error(2, &error);
+        let string = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            string,
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"error\":{\"code\":-32010,\"message\":\"abc\",\"data\":true}}"
+        )
+    }
+
+    #[test]
+    fn test_response_content_result() {
+        let content: ResponseContent =
+            serde_json::from_str("{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":5}").unwrap();
+        match content {
+            ResponseContent::Result(response) => assert_eq!(response.result, 5),
+            ResponseContent::Error(_) => panic!("Result expected."),
+        }
+    }
+
+    #[test]
+    fn test_response_content_error() {
+        let content: ResponseContent = serde_json::from_str(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{\"code\":-32000,\"message\":\"Server error.\"}}",
+        )
+        .unwrap();
+        match content {
+            ResponseContent::Error(response) => assert_eq!(response.error.code, -32000),
+            ResponseContent::Result(_) => panic!("Error expected."),
+        }
+    }
+
     #[test]
     fn test_response_number() {
         let response = Response::build(1, 123);
@@ -5,6 +5,8 @@
 const VERSION: u8 = 0; // LECO protocol version
 
 pub mod core {
+    use std::{fmt, str::FromStr};
+
     use uuid::Uuid;
 
     /// Create a new conversation id
@@ -15,7 +17,39 @@ pub mod core {
         /*let cid: [u8; 16] = [
             99, 111, 110, 118, 101, 114, 115, 97, 116, 105, 111, 110, 95, 105, 100, 59,
         ];*/
-        return uuid.into_bytes();
+        uuid.into_bytes()
+    }
+
+    /// Join `namespace` and `name` to a full name, e.g. `N1.motor`.
+    ///
+    /// Without namespace, the full name is just the name.
+    pub fn compose_full_name(namespace: &[u8], name: &[u8]) -> Vec<u8> {
+        if namespace.is_empty() {
+            return name.to_vec();
+        }
+        [namespace, b".", name].concat()
+    }
+
+    /// Whether a message of the LECO protocol `version` can be understood.
+    ///
+    /// As long as the protocol is in its draft stage (version 0), only the same version is.
+    pub fn is_compatible_version(version: u8) -> bool {
+        version == super::VERSION
+    }
+
+    /// Check that `name` may be used by a Component, i.e. it is neither empty, nor contains a
+    /// `.`, nor is reserved for the Coordinator.
+    pub fn validate_name(name: &[u8]) -> Result<(), String> {
+        let name_str = String::from_utf8_lossy(name);
+        if name.is_empty() {
+            Err("The name is empty.".to_string())
+        } else if name.contains(&b'.') {
+            Err(format!("The name '{name_str}' contains a '.'."))
+        } else if name == b"COORDINATOR" {
+            Err(format!("The name '{name_str}' is reserved."))
+        } else {
+            Ok(())
+        }
     }
 
     /// Different types of content
@@ -56,33 +90,180 @@ pub mod core {
     }
 
     impl<'a> FullName<'a> {
-        fn from_split(split: Vec<&'a [u8]>) -> Result<Self, String> {
-            match split.len() {
-                1 => Ok(Self {
+        /// Take the parts of a name split at the dots, without collecting them, as names are
+        /// parsed for every routed message.
+        fn from_split(mut split: impl Iterator<Item = &'a [u8]>) -> Result<Self, String> {
+            match (split.next(), split.next(), split.next()) {
+                (Some(name), None, _) => Ok(Self {
                     namespace: &[],
-                    name: split[0],
-                }),
-                2 => Ok(Self {
-                    namespace: split[0],
-                    name: split[1],
+                    name,
                 }),
-                x => Err(format!("Invalid number {x} of elements in name found.")),
+                (Some(namespace), Some(name), None) => Ok(Self { namespace, name }),
+                (first, second, third) => {
+                    let x = [first, second, third].iter().flatten().count() + split.count();
+                    Err(format!("Invalid number {x} of elements in name found."))
+                }
             }
         }
-        pub fn from_vec(vec: &'a Vec<u8>) -> Result<Self, String> {
-            // 46 is value of ASCII "."
-            let parts: Vec<&[u8]> = vec.split(|e| *e == 46u8).collect();
-            Self::from_split(parts)
+        pub fn from_vec(vec: &'a [u8]) -> Result<Self, String> {
+            Self::from_slice(vec)
         }
         pub fn from_slice(slice: &'a [u8]) -> Result<Self, String> {
-            let parts: Vec<&[u8]> = slice.split(|e| *e == 46u8).collect();
-            Self::from_split(parts)
+            // 46 is value of ASCII "."
+            Self::from_split(slice.split(|e| *e == 46u8))
+        }
+    }
+
+    /// Address of a zmq socket
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ruleco::core::Endpoint;
+    /// let endpoint: Endpoint = "tcp://localhost:12300".parse().unwrap();
+    /// assert_eq!(endpoint, Endpoint::tcp("localhost", 12300).unwrap());
+    /// assert_eq!(endpoint.to_string(), "tcp://localhost:12300");
+    /// ```
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Endpoint {
+        /// `host` is a host name, an IP address (IPv6 in brackets), or `*` for binding to all
+        /// interfaces
+        Tcp { host: String, port: u16 },
+        /// Path of a socket file
+        Ipc(String),
+        /// Name of an in-process endpoint, which requires all sockets to share one zmq context
+        Inproc(String),
+    }
+
+    impl Endpoint {
+        /// Create a tcp endpoint, checking the host.
+        pub fn tcp(host: &str, port: u16) -> Result<Self, String> {
+            let invalid = host.is_empty()
+                || host.contains(|c: char| c.is_whitespace() || c == '/')
+                || (host.contains(':') && !(host.starts_with('[') && host.ends_with(']')));
+            match invalid {
+                true => Err(format!("Invalid host '{host}'.")),
+                false => Ok(Self::Tcp {
+                    host: host.to_string(),
+                    port,
+                }),
+            }
+        }
+    }
+
+    impl FromStr for Endpoint {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || format!("Invalid endpoint '{s}'.");
+            let (transport, address) = s.split_once("://").ok_or_else(invalid)?;
+            if address.is_empty() {
+                return Err(invalid());
+            }
+            match transport {
+                "tcp" => {
+                    let (host, port) = address.rsplit_once(':').ok_or_else(invalid)?;
+                    let port = port.parse().map_err(|_| invalid())?;
+                    Self::tcp(host, port)
+                }
+                "ipc" => Ok(Self::Ipc(address.to_string())),
+                "inproc" => Ok(Self::Inproc(address.to_string())),
+                _ => Err(invalid()),
+            }
+        }
+    }
+
+    impl fmt::Display for Endpoint {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Tcp { host, port } => write!(f, "tcp://{host}:{port}"),
+                Self::Ipc(path) => write!(f, "ipc://{path}"),
+                Self::Inproc(name) => write!(f, "inproc://{name}"),
+            }
+        }
+    }
+
+    /// Keys of a CurveZMQ client, encrypting the connection and authenticating the client
+    ///
+    /// Create key pairs with [`zmq::CurveKeyPair::new`], which requires libzmq with CURVE.
+    #[derive(Clone, PartialEq, Eq)]
+    pub struct CurveClientKeys {
+        /// Public key of the server, e.g. the Coordinator
+        pub server_public_key: [u8; 32],
+        pub public_key: [u8; 32],
+        pub secret_key: [u8; 32],
+    }
+
+    impl CurveClientKeys {
+        /// Make `socket` a CURVE client, which has to happen before connecting it.
+        pub fn apply(&self, socket: &zmq::Socket) -> Result<(), zmq::Error> {
+            socket.set_curve_serverkey(&self.server_public_key)?;
+            socket.set_curve_publickey(&self.public_key)?;
+            socket.set_curve_secretkey(&self.secret_key)
+        }
+    }
+
+    impl fmt::Debug for CurveClientKeys {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // Keep the secret key out of logs.
+            f.debug_struct("CurveClientKeys")
+                .field("server_public_key", &self.server_public_key)
+                .field("public_key", &self.public_key)
+                .finish_non_exhaustive()
         }
     }
 
     #[cfg(test)]
     mod test {
-        use crate::core::FullName;
+        use crate::core::{
+            compose_full_name, is_compatible_version, validate_name, Endpoint, FullName,
+        };
+
+        #[test]
+        fn test_compose_full_name() {
+            assert_eq!(compose_full_name(b"N1", b"motor"), b"N1.motor");
+            assert_eq!(compose_full_name(b"", b"motor"), b"motor");
+        }
+
+        #[test]
+        fn test_is_compatible_version() {
+            assert!(is_compatible_version(crate::VERSION));
+            assert!(!is_compatible_version(9));
+        }
+
+        #[test]
+        fn test_validate_name() {
+            assert_eq!(validate_name(b"motor_1"), Ok(()));
+            for name in [b"".as_slice(), b"N1.motor", b"COORDINATOR"] {
+                assert!(validate_name(name).is_err());
+            }
+        }
+
+        #[test]
+        fn test_parse_endpoints() {
+            for endpoint in [
+                "tcp://*:12300",
+                "tcp://[::1]:5",
+                "ipc:///tmp/coordinator",
+                "inproc://coordinator",
+            ] {
+                assert_eq!(endpoint.parse::<Endpoint>().unwrap().to_string(), endpoint);
+            }
+        }
+
+        #[test]
+        fn test_parse_invalid_endpoints() {
+            for endpoint in [
+                "tcp://:::1",
+                "tcp://localhost",
+                "tcp://localhost:70000",
+                "udp://localhost:5",
+                "inproc://",
+                "localhost:5",
+            ] {
+                assert!(endpoint.parse::<Endpoint>().is_err(), "{endpoint}");
+            }
+        }
 
         #[test]
         fn test_full_name() {
@@ -106,11 +287,20 @@ pub mod core {
                 }
             )
         }
+        #[test]
+        fn test_full_name_with_too_many_parts() {
+            assert_eq!(
+                FullName::from_slice(b"a.b.c.d"),
+                Err("Invalid number 4 of elements in name found.".to_string())
+            );
+        }
     }
 }
 
 pub mod control_protocol;
 
+pub mod coordinator;
+
 pub mod data_protocol;
 
 pub mod json;
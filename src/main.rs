@@ -1,4 +1,3 @@
-use ruleco;
 use std::{thread, time};
 
 fn main() {